@@ -0,0 +1,391 @@
+//! 可选的本地 HTTP 服务，以 JSON 提供库的操作，供编辑器插件等本地工具调用：
+//!
+//! - `GET /api/items` 完整的条目树
+//! - `POST /api/items` 新建 prompt（需要读写权限）
+//! - `GET /api/items/{id}` 单个条目（id 或 `文件夹/名称` 路径）
+//! - `GET /api/search?q=...&type=prompt` 全文搜索
+//! - `POST /api/items/{id}/render` 以 `{"values": {...}}` 渲染 prompt
+//!
+//! 返回的条目不含 API Key 等凭据。`POST /mcp` 为 MCP 客户端提供同样的库（见 [`crate::mcp`]）。
+//! 每个请求都要带 `Authorization: Bearer <令牌>`，令牌在设置中生成，只保存其 SHA-256；
+//! 应用锁定期间所有请求返回 423
+
+use crate::applock::AppLock;
+use crate::errors::{Error, ErrorCode};
+use crate::log_error;
+use crate::mcp;
+use crate::models::{
+    ApiConfig, ApiTokenInfo, ApiTokenScope, ItemType, NewApiToken, SearchFilters, SearchResult, TreeItem,
+};
+use crate::sensitive;
+use crate::store::Store;
+use crate::sync;
+use crate::template::{self, TemplateSpec};
+use axum::extract::{Extension, Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+
+/// 令牌的前缀，便于识别与密钥扫描
+const TOKEN_PREFIX: &str = "pm_";
+/// 令牌最近使用时间的记录精度（毫秒），避免每个请求都写 api_tokens.json
+const LAST_USED_INTERVAL: i64 = 60 * 60 * 1000;
+
+/// api_tokens.json 中保存的令牌
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct StoredToken {
+    #[serde(flatten)]
+    info: ApiTokenInfo,
+    /// 令牌的 SHA-256（十六进制）
+    hash: String,
+}
+
+struct Server {
+    port: u16,
+    shutdown: Arc<Notify>,
+}
+
+/// 本地 API 的设置（保存在 api.json）与正在运行的服务
+pub struct ApiState {
+    config: Mutex<ApiConfig>,
+    server: Mutex<Option<Server>>,
+    path: PathBuf,
+    tokens: Mutex<Vec<StoredToken>>,
+    tokens_path: PathBuf,
+    app: AppHandle,
+}
+
+impl ApiState {
+    pub fn new(app: &AppHandle, dir: PathBuf) -> Self {
+        let path = dir.join("api.json");
+        let config = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        let tokens_path = dir.join("api_tokens.json");
+        let tokens = fs::read_to_string(&tokens_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        ApiState {
+            config: Mutex::new(config),
+            server: Mutex::new(None),
+            path,
+            tokens: Mutex::new(tokens),
+            tokens_path,
+            app: app.clone(),
+        }
+    }
+
+    fn save_tokens(&self, tokens: &[StoredToken]) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(tokens).map_err(|e| e.to_string())?;
+        fs::write(&self.tokens_path, content).map_err(|e| e.to_string())
+    }
+
+    pub fn tokens(&self) -> Vec<ApiTokenInfo> {
+        self.tokens.lock().unwrap().iter().map(|t| t.info.clone()).collect()
+    }
+
+    /// 生成新令牌；令牌本身只返回这一次，之后无法再查看
+    pub fn create_token(&self, name: &str, scope: ApiTokenScope) -> Result<NewApiToken, Error> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(ErrorCode::TokenNameRequired.into());
+        }
+        let mut secret = [0u8; 32];
+        OsRng.fill_bytes(&mut secret);
+        let token = format!("{}{}", TOKEN_PREFIX, hex(&secret));
+        let info = ApiTokenInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            scope,
+            created_at: chrono::Utc::now().timestamp_millis(),
+            last_used: None,
+        };
+
+        let mut tokens = self.tokens.lock().map_err(|e| e.to_string())?;
+        tokens.push(StoredToken {
+            info: info.clone(),
+            hash: hash(&token),
+        });
+        self.save_tokens(&tokens)?;
+        Ok(NewApiToken { info, token })
+    }
+
+    pub fn revoke_token(&self, id: &str) -> Result<(), Error> {
+        let mut tokens = self.tokens.lock().map_err(|e| e.to_string())?;
+        let before = tokens.len();
+        tokens.retain(|t| t.info.id != id);
+        if tokens.len() == before {
+            return Err(ErrorCode::TokenNotFound.into());
+        }
+        Ok(self.save_tokens(&tokens)?)
+    }
+
+    /// 校验请求带的令牌，返回其权限；最近使用时间距上次记录超过 [`LAST_USED_INTERVAL`] 时才写入磁盘
+    fn authenticate(&self, token: &str) -> Option<ApiTokenScope> {
+        let hash = hash(token);
+        let mut tokens = self.tokens.lock().ok()?;
+        let stored = tokens.iter_mut().find(|t| t.hash == hash)?;
+        let scope = stored.info.scope;
+        let now = chrono::Utc::now().timestamp_millis();
+        if !matches!(stored.info.last_used, Some(last) if now - last < LAST_USED_INTERVAL) {
+            stored.info.last_used = Some(now);
+            if let Err(e) = self.save_tokens(&tokens) {
+                log_error!("Failed to save API tokens: {}", e);
+            }
+        }
+        Some(scope)
+    }
+
+    pub fn config(&self) -> ApiConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// 服务正在运行时的地址
+    pub fn url(&self) -> Option<String> {
+        let server = self.server.lock().unwrap();
+        server.as_ref().map(|s| format!("http://127.0.0.1:{}", s.port))
+    }
+
+    /// 按设置启动服务（启动时调用）
+    pub async fn start(&self) -> Result<(), String> {
+        let config = self.config();
+        if config.enabled {
+            let server = self.listen(config.port).await?;
+            self.replace(Some(server));
+        }
+        Ok(())
+    }
+
+    /// 保存设置并按需启动、停止或换端口重启；新端口被占用时保持原状
+    pub async fn set_config(&self, config: ApiConfig) -> Result<ApiConfig, String> {
+        let running = self.server.lock().map_err(|e| e.to_string())?.as_ref().map(|s| s.port);
+        if !config.enabled {
+            self.replace(None);
+        } else if running != Some(config.port) {
+            let server = self.listen(config.port).await?;
+            self.replace(Some(server));
+        }
+
+        let content = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+        fs::write(&self.path, content).map_err(|e| e.to_string())?;
+        *self.config.lock().map_err(|e| e.to_string())? = config.clone();
+        Ok(config)
+    }
+
+    /// 换上新的服务并停止原来的
+    fn replace(&self, server: Option<Server>) {
+        let old = match self.server.lock() {
+            Ok(mut current) => std::mem::replace(&mut *current, server),
+            Err(_) => return,
+        };
+        if let Some(old) = old {
+            old.shutdown.notify_one();
+        }
+    }
+
+    async fn listen(&self, port: u16) -> Result<Server, String> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .await
+            .map_err(|e| format!("Failed to listen on port {}: {}", port, e))?;
+        let shutdown = Arc::new(Notify::new());
+        let signal = shutdown.clone();
+        let app = router(self.app.clone());
+        tauri::async_runtime::spawn(async move {
+            let server = axum::serve(listener, app).with_graceful_shutdown(async move { signal.notified().await });
+            if let Err(e) = server.await {
+                log_error!("Local API server stopped: {}", e);
+            }
+        });
+        Ok(Server { port, shutdown })
+    }
+}
+
+fn router(app: AppHandle) -> Router {
+    Router::new()
+        .route("/api/items", get(list_items).post(create_item))
+        .route("/api/items/*reference", get(get_item).post(render_item))
+        .route("/api/search", get(search))
+        .route("/mcp", post(mcp))
+        .layer(middleware::from_fn_with_state(app.clone(), authorize))
+        .with_state(app)
+}
+
+/// 校验 Bearer 令牌，并把令牌的权限交给各个处理函数
+async fn authorize(State(app): State<AppHandle>, mut request: Request, next: Next) -> Response {
+    if app.state::<AppLock>().is_locked() {
+        return ApiError(StatusCode::LOCKED, "App is locked".to_string()).into_response();
+    }
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+    let Some(scope) = token.and_then(|token| app.state::<ApiState>().authenticate(token)) else {
+        return ApiError(StatusCode::UNAUTHORIZED, "Missing or invalid API token".to_string()).into_response();
+    };
+    request.extensions_mut().insert(scope);
+    next.run(request).await
+}
+
+fn require_write(scope: ApiTokenScope) -> Result<(), ApiError> {
+    match scope {
+        ApiTokenScope::ReadWrite => Ok(()),
+        ApiTokenScope::Read => Err(ApiError(StatusCode::FORBIDDEN, "Token is read-only".to_string())),
+    }
+}
+
+fn hash(token: &str) -> String {
+    hex(&Sha256::digest(token.as_bytes()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 错误以 `{"error": "..."}` 返回
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+fn find(app: &AppHandle, reference: &str) -> Result<TreeItem, ApiError> {
+    app.state::<Store>()
+        .find_by_reference(reference)
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, format!("Item not found: {}", reference)))
+}
+
+/// 与界面一样，敏感条目的内容以占位文本代替
+async fn list_items(State(app): State<AppHandle>) -> ApiResult<Vec<TreeItem>> {
+    let items = sync::strip_secrets(&app.state::<Store>().get_tree());
+    Ok(Json(sensitive::redacted_all(items)))
+}
+
+async fn get_item(State(app): State<AppHandle>, Path(reference): Path<String>) -> ApiResult<TreeItem> {
+    let item = find(&app, &reference)?;
+    Ok(Json(sensitive::redacted(sync::strip_secrets(&[item]).remove(0))))
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+    /// 逗号分隔的条目类型，例如 `prompt,snippet`
+    r#type: Option<String>,
+}
+
+async fn search(State(app): State<AppHandle>, Query(query): Query<SearchQuery>) -> ApiResult<Vec<SearchResult>> {
+    let filters = match query.r#type.filter(|t| !t.is_empty()) {
+        Some(types) => {
+            let types = types
+                .split(',')
+                .map(|t| serde_json::from_value::<ItemType>(serde_json::Value::String(t.trim().to_string())))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| ApiError(StatusCode::BAD_REQUEST, format!("Invalid type: {}", e)))?;
+            Some(SearchFilters {
+                types: Some(types),
+                date: None,
+            })
+        }
+        None => None,
+    };
+    let store = app.state::<Store>();
+    let mut results = store.search(query.q, filters);
+    sensitive::redact_results(&mut results, &store.sensitive_ids());
+    Ok(Json(results))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateRequest {
+    name: String,
+    #[serde(default)]
+    content: String,
+    /// 父文件夹（id 或路径），缺省放在根下
+    parent: Option<String>,
+}
+
+/// `POST /api/items`
+async fn create_item(
+    State(app): State<AppHandle>,
+    Extension(scope): Extension<ApiTokenScope>,
+    Json(request): Json<CreateRequest>,
+) -> ApiResult<TreeItem> {
+    require_write(scope)?;
+    let parent_id = request.parent.map(|p| find(&app, &p).map(|f| f.id)).transpose()?;
+    let item = TreeItem {
+        id: String::new(),
+        name: request.name,
+        item_type: ItemType::Prompt,
+        children: Vec::new(),
+        parent_id: parent_id.clone(),
+        content: Some(request.content.into()),
+        versions: None,
+        metadata: Default::default(),
+    };
+    let item = app
+        .state::<Store>()
+        .add_item(parent_id, item)
+        .map_err(|e| ApiError(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    Ok(Json(sync::strip_secrets(&[item]).remove(0)))
+}
+
+#[derive(Deserialize, Default)]
+struct RenderRequest {
+    #[serde(default)]
+    values: HashMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct RenderResponse {
+    content: String,
+}
+
+/// `POST /api/items/{id}/render`；敏感条目不能通过 API 渲染
+async fn render_item(
+    State(app): State<AppHandle>,
+    Path(reference): Path<String>,
+    request: Option<Json<RenderRequest>>,
+) -> ApiResult<RenderResponse> {
+    let Some(reference) = reference.strip_suffix("/render") else {
+        return Err(ApiError(StatusCode::NOT_FOUND, "Not found".to_string()));
+    };
+    let item = find(&app, reference)?;
+    if sensitive::is_sensitive(&item) {
+        return Err(ApiError(StatusCode::FORBIDDEN, "Item is sensitive".to_string()));
+    }
+    let values = request.map(|Json(r)| r.values).unwrap_or_default();
+    let store = app.state::<Store>();
+    let content = store
+        .expand_includes(&item)
+        .and_then(|content| template::render(&content, &TemplateSpec::of(&item.metadata), &values))
+        .map_err(|e| ApiError(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    Ok(Json(RenderResponse { content }))
+}
+
+/// MCP 的 Streamable HTTP 传输（只返回 JSON，不使用 SSE）；通知返回 202
+async fn mcp(State(app): State<AppHandle>, Json(message): Json<serde_json::Value>) -> Response {
+    match mcp::handle(&app.state::<Store>(), message) {
+        Some(response) => Json(response).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
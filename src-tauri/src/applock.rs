@@ -0,0 +1,184 @@
+use crate::errors::{Error, ErrorCode};
+use crate::models::AppLockStatus;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 锁定后除这些命令外一律返回错误
+const ALLOWED_WHILE_LOCKED: &[&str] = &["get_app_lock_status", "unlock_app"];
+const MIN_PASSPHRASE_LEN: usize = 8;
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+/// 空闲超时后自动锁定时发出的事件
+pub const EVENT_LOCKED: &str = "app-lock://locked";
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct LockConfig {
+    /// 主密码的 argon2 哈希（PHC 格式）；为空表示未启用
+    password_hash: Option<String>,
+    /// 无操作多少分钟后自动锁定，为空表示不自动锁定
+    idle_timeout_minutes: Option<u32>,
+    /// 显示敏感条目的内容前要求输入主密码
+    #[serde(default)]
+    guard_sensitive: bool,
+}
+
+/// 应用锁：启用后每次启动都处于锁定状态，输入主密码后在本次运行期间解锁。
+/// 设置保存在 app_lock.json，解锁状态只保存在内存中
+pub struct AppLock {
+    config: Mutex<LockConfig>,
+    locked: Mutex<bool>,
+    last_activity: Mutex<Instant>,
+    path: PathBuf,
+}
+
+impl AppLock {
+    pub fn new(dir: PathBuf) -> Self {
+        let path = dir.join("app_lock.json");
+        let config: LockConfig = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        AppLock {
+            locked: Mutex::new(config.password_hash.is_some()),
+            config: Mutex::new(config),
+            last_activity: Mutex::new(Instant::now()),
+            path,
+        }
+    }
+
+    fn save(&self, config: &LockConfig) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+        fs::write(&self.path, content).map_err(|e| e.to_string())
+    }
+
+    pub fn status(&self) -> AppLockStatus {
+        let config = self.config.lock().unwrap();
+        AppLockStatus {
+            enabled: config.password_hash.is_some(),
+            locked: self.is_locked(),
+            idle_timeout_minutes: config.idle_timeout_minutes,
+            guard_sensitive: config.guard_sensitive,
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        *self.locked.lock().unwrap()
+    }
+
+    /// 命令是否可以执行；未锁定时同时记为一次操作
+    pub fn allows(&self, command: &str) -> bool {
+        if self.is_locked() {
+            return ALLOWED_WHILE_LOCKED.contains(&command);
+        }
+        *self.last_activity.lock().unwrap() = Instant::now();
+        true
+    }
+
+    pub fn unlock(&self, passphrase: &str) -> Result<(), Error> {
+        let config = self.config.lock().map_err(|e| e.to_string())?;
+        if let Some(hash) = &config.password_hash {
+            verify(hash, passphrase)?;
+        }
+        *self.locked.lock().map_err(|e| e.to_string())? = false;
+        *self.last_activity.lock().map_err(|e| e.to_string())? = Instant::now();
+        Ok(())
+    }
+
+    pub fn lock(&self) -> Result<(), Error> {
+        if self.config.lock().map_err(|e| e.to_string())?.password_hash.is_none() {
+            return Err(ErrorCode::AppLockDisabled.into());
+        }
+        *self.locked.lock().map_err(|e| e.to_string())? = true;
+        Ok(())
+    }
+
+    /// 设置主密码并启用应用锁；已启用时需要提供当前密码
+    pub fn set_passphrase(&self, current: Option<&str>, passphrase: &str) -> Result<AppLockStatus, Error> {
+        if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+            return Err(Error::with(ErrorCode::PassphraseTooShort, MIN_PASSPHRASE_LEN));
+        }
+        let mut config = self.config.lock().map_err(|e| e.to_string())?;
+        if let Some(hash) = &config.password_hash {
+            verify(hash, current.unwrap_or_default())?;
+        }
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = Argon2::default()
+            .hash_password(passphrase.as_bytes(), &salt)
+            .map_err(|e| e.to_string())?;
+        config.password_hash = Some(hash.to_string());
+        self.save(&config)?;
+        drop(config);
+        Ok(self.status())
+    }
+
+    pub fn disable(&self, passphrase: &str) -> Result<AppLockStatus, Error> {
+        let mut config = self.config.lock().map_err(|e| e.to_string())?;
+        let Some(hash) = &config.password_hash else {
+            return Err(ErrorCode::AppLockDisabled.into());
+        };
+        verify(hash, passphrase)?;
+        config.password_hash = None;
+        self.save(&config)?;
+        drop(config);
+        *self.locked.lock().map_err(|e| e.to_string())? = false;
+        Ok(self.status())
+    }
+
+    pub fn set_idle_timeout(&self, minutes: Option<u32>) -> Result<AppLockStatus, String> {
+        let mut config = self.config.lock().map_err(|e| e.to_string())?;
+        config.idle_timeout_minutes = minutes.filter(|m| *m > 0);
+        self.save(&config)?;
+        drop(config);
+        Ok(self.status())
+    }
+
+    pub fn set_guard_sensitive(&self, guard: bool) -> Result<AppLockStatus, String> {
+        let mut config = self.config.lock().map_err(|e| e.to_string())?;
+        config.guard_sensitive = guard;
+        self.save(&config)?;
+        drop(config);
+        Ok(self.status())
+    }
+
+    /// 显示敏感内容前的检查：启用了应用锁且要求验证时需要正确的主密码
+    pub fn authorize_reveal(&self, passphrase: Option<&str>) -> Result<(), Error> {
+        let config = self.config.lock().map_err(|e| e.to_string())?;
+        match &config.password_hash {
+            Some(hash) if config.guard_sensitive => verify(hash, passphrase.unwrap_or_default()),
+            _ => Ok(()),
+        }
+    }
+
+    /// 在后台检查空闲时间，超时后锁定并通知界面
+    pub fn watch_idle(app: AppHandle) {
+        std::thread::spawn(move || loop {
+            std::thread::sleep(IDLE_CHECK_INTERVAL);
+            let lock = app.state::<AppLock>();
+            if lock.idle_expired() && lock.lock().is_ok() {
+                let _ = app.emit(EVENT_LOCKED, ());
+            }
+        });
+    }
+
+    fn idle_expired(&self) -> bool {
+        let config = self.config.lock().unwrap();
+        let (Some(_), Some(minutes)) = (&config.password_hash, config.idle_timeout_minutes) else {
+            return false;
+        };
+        !self.is_locked() && self.last_activity.lock().unwrap().elapsed() >= Duration::from_secs(minutes as u64 * 60)
+    }
+}
+
+fn verify(hash: &str, passphrase: &str) -> Result<(), Error> {
+    let hash = PasswordHash::new(hash).map_err(|e| e.to_string())?;
+    Argon2::default()
+        .verify_password(passphrase.as_bytes(), &hash)
+        .map_err(|_| ErrorCode::WrongPassphrase.into())
+}
@@ -0,0 +1,64 @@
+use crate::models::{AuditEntry, AuditFilters, StoreChange};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const DEFAULT_LIMIT: usize = 500;
+
+/// 库的修改记录：每次变更追加一行 JSON 到 audit.jsonl，只追加不改写。
+/// 图形界面、命令行与 MCP 的修改都经过 [`crate::store::Store`]，因此都会被记录
+/// 启用加密后仍以明文追加：记录中没有条目内容，但摘要含条目名称
+pub struct AuditLog {
+    path: PathBuf,
+    /// 串行化写入，避免多行交错
+    writing: Mutex<()>,
+}
+
+impl AuditLog {
+    pub fn new(dir: &Path) -> Self {
+        AuditLog {
+            path: dir.join("audit.jsonl"),
+            writing: Mutex::new(()),
+        }
+    }
+
+    pub fn record(&self, device: &str, change: &StoreChange) -> Result<(), String> {
+        let entry = AuditEntry {
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            device: device.to_string(),
+            kind: change.kind,
+            ids: change.ids.clone(),
+            summary: change.summary.clone(),
+        };
+        let mut line = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+        line.push('\n');
+
+        let _writing = self.writing.lock().map_err(|e| e.to_string())?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| e.to_string())?;
+        file.write_all(line.as_bytes()).map_err(|e| e.to_string())
+    }
+
+    /// 符合条件的记录，最新的在前；无法解析的行（例如写到一半）会被跳过
+    pub fn query(&self, filters: &AuditFilters) -> Vec<AuditEntry> {
+        let Ok(content) = fs::read_to_string(&self.path) else {
+            return Vec::new();
+        };
+        let text = filters.query.as_deref().map(str::to_lowercase);
+        content
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+            .filter(|e| filters.item_id.as_ref().map_or(true, |id| e.ids.contains(id)))
+            .filter(|e| filters.kinds.as_ref().map_or(true, |kinds| kinds.contains(&e.kind)))
+            .filter(|e| filters.since.map_or(true, |since| e.timestamp >= since))
+            .filter(|e| filters.until.map_or(true, |until| e.timestamp <= until))
+            .filter(|e| text.as_ref().map_or(true, |t| e.summary.to_lowercase().contains(t)))
+            .take(filters.limit.unwrap_or(DEFAULT_LIMIT))
+            .collect()
+    }
+}
@@ -0,0 +1,250 @@
+use crate::errors::{Error, ErrorCode};
+use crate::log_error;
+use crate::models::{CaptureConfig, ItemMetadata, ItemType, TreeItem};
+use crate::picker;
+use crate::store::Store;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+/// 收集到新的 prompt 后发出，内容为新条目
+pub const EVENT_CAPTURED: &str = "capture://captured";
+const INBOX_NAME: &str = "Inbox";
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const NAME_LENGTH: usize = 60;
+/// 常见的 prompt 开头（小写比较）
+const PROMPT_OPENINGS: &[&str] = &[
+    "you are",
+    "you're",
+    "act as",
+    "i want you to act as",
+    "your task is",
+    "your role is",
+    "system:",
+    "### instruction",
+    "<system>",
+    "pretend you are",
+    "imagine you are",
+];
+
+/// 剪贴板收集：开启后每秒检查一次剪贴板，看起来像 prompt 的文本存入收件箱文件夹；
+/// 也可以用全局快捷键直接收集当前剪贴板。设置保存在 capture.json
+pub struct CaptureState {
+    config: Mutex<CaptureConfig>,
+    /// 上次看到的剪贴板内容，避免重复收集；本应用复制出去的文本也记在这里
+    last_seen: Mutex<Option<String>>,
+    path: PathBuf,
+}
+
+impl CaptureState {
+    pub fn new(dir: PathBuf) -> Self {
+        let path = dir.join("capture.json");
+        let config = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        CaptureState {
+            config: Mutex::new(config),
+            last_seen: Mutex::new(None),
+            path,
+        }
+    }
+
+    pub fn config(&self) -> CaptureConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// 保存设置；快捷键变化时先注册新的再注销旧的
+    pub fn set_config(&self, app: &AppHandle, mut new: CaptureConfig) -> Result<CaptureConfig, Error> {
+        new.shortcut = new.shortcut.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        if new.min_length > new.max_length {
+            return Err(ErrorCode::CaptureLengthRange.into());
+        }
+        let mut config = self.config.lock().map_err(|e| e.to_string())?;
+        let old = config.shortcut.as_deref().and_then(|s| picker::parse(s).ok());
+        let shortcut = new.shortcut.as_deref().map(picker::parse).transpose()?;
+        if shortcut != old {
+            if let Some(shortcut) = shortcut {
+                app.global_shortcut().register(shortcut).map_err(|e| {
+                    format!(
+                        "Failed to register shortcut {}: {}",
+                        new.shortcut.as_deref().unwrap_or_default(),
+                        e
+                    )
+                })?;
+            }
+            if let Some(old) = old {
+                let _ = app.global_shortcut().unregister(old);
+            }
+        }
+        // 刚开启时剪贴板里已有的内容不算新复制的
+        if new.enabled && !config.enabled {
+            self.ignore(&app.clipboard().read_text().unwrap_or_default());
+        }
+
+        *config = new;
+        let content = serde_json::to_string_pretty(&*config).map_err(|e| e.to_string())?;
+        fs::write(&self.path, content).map_err(|e| e.to_string())?;
+        Ok(config.clone())
+    }
+
+    /// 注册收集快捷键并开始轮询剪贴板（启动时调用）
+    pub fn start(&self, app: &AppHandle) -> Result<(), String> {
+        self.ignore(&app.clipboard().read_text().unwrap_or_default());
+        let app_handle = app.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(POLL_INTERVAL);
+            if let Err(e) = app_handle.state::<CaptureState>().poll(&app_handle) {
+                log_error!("Clipboard capture failed: {}", e);
+            }
+        });
+
+        match self.config().shortcut {
+            Some(shortcut) => app
+                .global_shortcut()
+                .register(picker::parse(&shortcut)?)
+                .map_err(|e| e.to_string()),
+            None => Ok(()),
+        }
+    }
+
+    /// 记下不需要收集的文本（例如本应用刚复制出去的 prompt）
+    pub fn ignore(&self, text: &str) {
+        if let Ok(mut last_seen) = self.last_seen.lock() {
+            *last_seen = Some(text.to_string());
+        }
+    }
+
+    fn poll(&self, app: &AppHandle) -> Result<(), String> {
+        let config = self.config();
+        if !config.enabled {
+            return Ok(());
+        }
+        let Ok(text) = app.clipboard().read_text() else {
+            return Ok(());
+        };
+        {
+            let mut last_seen = self.last_seen.lock().map_err(|e| e.to_string())?;
+            if last_seen.as_deref() == Some(text.as_str()) {
+                return Ok(());
+            }
+            *last_seen = Some(text.clone());
+        }
+        if looks_like_prompt(&text, &config) {
+            self.capture(app, &text)?;
+        }
+        Ok(())
+    }
+
+    /// 全局快捷键触发时调用；不是收集快捷键时返回 false
+    pub fn handle(&self, app: &AppHandle, shortcut: &Shortcut) -> bool {
+        let config = self.config();
+        let matches = config.shortcut.as_deref().and_then(|s| picker::parse(s).ok());
+        if matches.as_ref() != Some(shortcut) {
+            return false;
+        }
+        if let Err(e) = self.capture_clipboard(app) {
+            log_error!("Clipboard capture failed: {}", e);
+        }
+        true
+    }
+
+    /// 不经判断直接收集当前剪贴板
+    pub fn capture_clipboard(&self, app: &AppHandle) -> Result<TreeItem, Error> {
+        let text = app.clipboard().read_text().map_err(|e| e.to_string())?;
+        if text.trim().is_empty() {
+            return Err(ErrorCode::ClipboardEmpty.into());
+        }
+        self.ignore(&text);
+        self.capture(app, &text)
+    }
+
+    fn capture(&self, app: &AppHandle, text: &str) -> Result<TreeItem, Error> {
+        let store = app.state::<Store>();
+        let inbox_id = self.inbox(&store)?;
+        let content = text.trim().to_string();
+        // 收件箱里已有相同内容时不再重复添加
+        if let Some(existing) = store.get_item(&inbox_id).and_then(|inbox| {
+            inbox
+                .children
+                .into_iter()
+                .find(|c| c.content.as_deref() == Some(content.as_str()))
+        }) {
+            return Ok(existing);
+        }
+
+        let item = TreeItem {
+            id: String::new(),
+            name: title(&content),
+            item_type: ItemType::Prompt,
+            children: Vec::new(),
+            parent_id: Some(inbox_id.clone()),
+            content: Some(content.into()),
+            versions: None,
+            metadata: ItemMetadata::default(),
+        };
+        let item = store.add_item(Some(inbox_id), item)?;
+        let _ = app.emit(EVENT_CAPTURED, &item);
+        Ok(item)
+    }
+
+    /// 收件箱文件夹的 id，不存在时在根目录新建
+    fn inbox(&self, store: &Store) -> Result<String, String> {
+        let configured = self.config().inbox_id;
+        if let Some(folder) = configured
+            .as_deref()
+            .and_then(|id| store.get_item(id))
+            .filter(|item| item.item_type == ItemType::Folder && item.metadata.read_only != Some(true))
+        {
+            return Ok(folder.id);
+        }
+        if let Some(folder) = store
+            .get_all()
+            .into_iter()
+            .find(|item| item.item_type == ItemType::Folder && item.name == INBOX_NAME)
+        {
+            return Ok(folder.id);
+        }
+
+        let folder = TreeItem {
+            id: String::new(),
+            name: INBOX_NAME.to_string(),
+            item_type: ItemType::Folder,
+            children: Vec::new(),
+            parent_id: None,
+            content: None,
+            versions: None,
+            metadata: ItemMetadata::default(),
+        };
+        Ok(store.add_item(None, folder)?.id)
+    }
+}
+
+/// 长度在范围内，并且以常见的 prompt 开头或包含 `{{变量}}`
+pub fn looks_like_prompt(text: &str, config: &CaptureConfig) -> bool {
+    let text = text.trim();
+    let length = text.chars().count();
+    if length < config.min_length || length > config.max_length {
+        return false;
+    }
+    let lower = text.to_lowercase();
+    PROMPT_OPENINGS.iter().any(|opening| lower.starts_with(opening)) || (text.contains("{{") && text.contains("}}"))
+}
+
+/// 以第一行作为名称
+fn title(content: &str) -> String {
+    let line = content
+        .lines()
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or_default()
+        .trim();
+    let mut name: String = line.chars().take(NAME_LENGTH).collect();
+    if line.chars().count() > NAME_LENGTH {
+        name.push('…');
+    }
+    name
+}
@@ -0,0 +1,327 @@
+//! 命令行模式：`prompt-manager list|get|add|search|render|export|mcp ...`，
+//! 直接读写与图形界面相同的 store.json，可在脚本与 git hook 中使用。
+//! 界面正在运行时它会检测到 store.json 的变化并合并进来
+
+use crate::mcp;
+use crate::models::{ItemType, SearchFilters, TreeItem};
+use crate::store::{self, Store};
+use crate::sync;
+use crate::template::{self, TemplateSpec};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// 这些子命令以命令行模式运行，其余参数（例如 deep link）照常启动界面
+const COMMANDS: &[&str] = &[
+    "list", "get", "add", "search", "render", "export", "mcp", "help", "--help", "-h",
+];
+
+#[derive(Parser)]
+#[command(name = "prompt-manager", about = "Manage prompts from the command line")]
+struct Cli {
+    /// 数据目录，缺省为应用的数据目录
+    #[arg(long, global = true, env = "PROMPT_MANAGER_DATA_DIR")]
+    data_dir: Option<PathBuf>,
+    /// 加密的 store.json 的口令
+    #[arg(long, global = true, env = "PROMPT_MANAGER_PASSPHRASE", hide_env_values = true)]
+    passphrase: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List items as `id<TAB>type<TAB>path`
+    List {
+        /// Only list items under this folder (id or path)
+        #[arg(long)]
+        folder: Option<String>,
+        #[arg(long, value_enum)]
+        r#type: Option<TypeArg>,
+        /// Print the items as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the content of an item (id or `Folder/Name` path)
+    Get {
+        reference: String,
+        /// Print the whole item as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Add a prompt; content is read from stdin when --content is omitted
+    Add {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        content: Option<String>,
+        /// Parent folder (id or path)
+        #[arg(long)]
+        parent: Option<String>,
+        #[arg(long, value_enum, default_value = "prompt")]
+        r#type: TypeArg,
+    },
+    /// Full-text search in names and contents
+    Search {
+        query: String,
+        #[arg(long, value_enum)]
+        r#type: Vec<TypeArg>,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Render a prompt with `--var name=value` pairs
+    Render {
+        reference: String,
+        #[arg(long = "var", value_parser = parse_var)]
+        vars: Vec<(String, String)>,
+    },
+    /// Export the library (API keys removed) as JSON or as a Markdown directory
+    Export {
+        /// Output file or directory; JSON goes to stdout when omitted
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value = "json")]
+        format: ExportFormat,
+        /// Only export this folder (id or path)
+        #[arg(long)]
+        folder: Option<String>,
+    },
+    /// Serve the library as an MCP server over stdio
+    Mcp,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TypeArg {
+    Folder,
+    Prompt,
+    Snippet,
+}
+
+impl From<TypeArg> for ItemType {
+    fn from(arg: TypeArg) -> Self {
+        match arg {
+            TypeArg::Folder => ItemType::Folder,
+            TypeArg::Prompt => ItemType::Prompt,
+            TypeArg::Snippet => ItemType::Snippet,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Json,
+    Markdown,
+}
+
+fn parse_var(arg: &str) -> Result<(String, String), String> {
+    arg.split_once('=')
+        .map(|(name, value)| (name.trim().to_string(), value.to_string()))
+        .ok_or_else(|| format!("Expected name=value, got '{}'", arg))
+}
+
+/// 以子命令启动时运行命令行并返回退出码；否则返回 None，照常启动界面
+pub fn run_from_args() -> Option<i32> {
+    let first = std::env::args().nth(1)?;
+    if !COMMANDS.contains(&first.as_str()) {
+        return None;
+    }
+    attach_console();
+    let cli = Cli::parse();
+    let dir = cli.data_dir.unwrap_or_else(store::default_data_dir);
+    let result = open(&dir, cli.passphrase.as_deref()).and_then(|store| {
+        let result = run(&store, cli.command, cli.passphrase.as_deref());
+        store.flush();
+        result
+    });
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            Some(1)
+        }
+    }
+}
+
+/// 打开数据目录中的库；store.json 已加密时用口令解锁
+fn open(dir: &Path, passphrase: Option<&str>) -> Result<Store, String> {
+    let store = Store::open(dir);
+    if store.is_locked() {
+        let passphrase = passphrase
+            .ok_or_else(|| "store.json is encrypted; pass --passphrase or set PROMPT_MANAGER_PASSPHRASE".to_string())?;
+        store.unlock(passphrase)?;
+    }
+    Ok(store)
+}
+
+fn run(store: &Store, command: Command, passphrase: Option<&str>) -> Result<(), String> {
+    let mut out = std::io::stdout().lock();
+    match command {
+        Command::List { folder, r#type, json } => {
+            let roots = match folder {
+                Some(folder) => find(store, &folder)?.children,
+                None => store.get_tree(),
+            };
+            let mut rows = Vec::new();
+            flatten(&roots, "", &mut rows);
+            let rows: Vec<(String, TreeItem)> = rows
+                .into_iter()
+                .filter(|(_, item)| r#type.map_or(true, |t| item.item_type == t.into()))
+                .collect();
+            if json {
+                let items: Vec<TreeItem> = rows.into_iter().map(|(_, item)| item).collect();
+                print_json(&mut out, &sync::strip_secrets(&items))
+            } else {
+                for (path, item) in rows {
+                    writeln!(out, "{}\t{}\t{}", item.id, item.item_type.label(), path).map_err(|e| e.to_string())?;
+                }
+                Ok(())
+            }
+        }
+        Command::Get { reference, json } => {
+            let item = find(store, &reference)?;
+            if json {
+                print_json(&mut out, &sync::strip_secrets(&[item])[0])
+            } else {
+                writeln!(out, "{}", item.content.as_deref().unwrap_or_default()).map_err(|e| e.to_string())
+            }
+        }
+        Command::Add {
+            name,
+            content,
+            parent,
+            r#type,
+        } => {
+            let content = match content {
+                Some(content) => content,
+                None if !std::io::stdin().is_terminal() => {
+                    let mut content = String::new();
+                    std::io::stdin()
+                        .read_to_string(&mut content)
+                        .map_err(|e| e.to_string())?;
+                    content
+                }
+                None => String::new(),
+            };
+            let parent_id = parent.map(|p| find(store, &p).map(|f| f.id)).transpose()?;
+            let item_type: ItemType = r#type.into();
+            let item = TreeItem {
+                id: String::new(),
+                name,
+                content: (item_type != ItemType::Folder).then(|| content.into()),
+                item_type,
+                children: Vec::new(),
+                parent_id: parent_id.clone(),
+                versions: None,
+                metadata: Default::default(),
+            };
+            let item = store.add_item(parent_id, item)?;
+            writeln!(out, "{}", item.id).map_err(|e| e.to_string())
+        }
+        Command::Search { query, r#type, json } => {
+            let filters = (!r#type.is_empty()).then(|| SearchFilters {
+                types: Some(r#type.into_iter().map(ItemType::from).collect()),
+                date: None,
+            });
+            let results = store.search(query, filters);
+            if json {
+                return print_json(&mut out, &results);
+            }
+            for result in results {
+                writeln!(out, "{}\t{}", result.item_id, result.item_name).map_err(|e| e.to_string())?;
+                for m in result.matches {
+                    writeln!(out, "  {}: {}", m.line_number, m.line_content.trim()).map_err(|e| e.to_string())?;
+                }
+            }
+            Ok(())
+        }
+        Command::Render { reference, vars } => {
+            let item = find(store, &reference)?;
+            let values: HashMap<String, String> = vars.into_iter().collect();
+            let content = store.expand_includes(&item)?;
+            let text = template::render(&content, &TemplateSpec::of(&item.metadata), &values)?;
+            write!(out, "{}", text).map_err(|e| e.to_string())
+        }
+        Command::Export { output, format, folder } => {
+            let items = match folder {
+                Some(folder) => vec![find(store, &folder)?],
+                None => store.get_all(),
+            };
+            let items = sync::strip_secrets(&items);
+            match (format, output) {
+                (ExportFormat::Json, None) => print_json(&mut out, &items),
+                (ExportFormat::Json, Some(path)) => {
+                    let content = serde_json::to_string_pretty(&items).map_err(|e| e.to_string())?;
+                    std::fs::write(path, content).map_err(|e| e.to_string())
+                }
+                (ExportFormat::Markdown, Some(dir)) => sync::export_layout(&items, &dir),
+                (ExportFormat::Markdown, None) => Err("Markdown export needs --output <dir>".to_string()),
+            }
+        }
+        Command::Mcp => serve_mcp(store.path().parent().unwrap_or(Path::new(".")), passphrase),
+    }
+}
+
+/// 每行一条 JSON-RPC 消息；store.json 被界面修改后重新读取。stdout 只用于协议消息
+fn serve_mcp(dir: &Path, passphrase: Option<&str>) -> Result<(), String> {
+    let modified = || fs::metadata(dir.join("store.json")).and_then(|m| m.modified()).ok();
+    let mut loaded = (modified(), open(dir, passphrase)?);
+    let mut out = std::io::stdout().lock();
+    for line in std::io::stdin().lock().lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if modified() != loaded.0 {
+            loaded = (modified(), open(dir, passphrase)?);
+        }
+        let response = match serde_json::from_str(&line) {
+            Ok(message) => mcp::handle(&loaded.1, message),
+            Err(e) => Some(mcp::parse_error(e)),
+        };
+        if let Some(response) = response {
+            writeln!(out, "{}", response).map_err(|e| e.to_string())?;
+            out.flush().map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+fn find(store: &Store, reference: &str) -> Result<TreeItem, String> {
+    store
+        .find_by_reference(reference)
+        .ok_or_else(|| format!("Item not found: {}", reference))
+}
+
+/// 展开为 (路径, 条目) 列表，先父后子
+fn flatten(nodes: &[TreeItem], prefix: &str, rows: &mut Vec<(String, TreeItem)>) {
+    for node in nodes {
+        let path = if prefix.is_empty() {
+            node.name.clone()
+        } else {
+            format!("{}/{}", prefix, node.name)
+        };
+        let mut item = node.clone();
+        item.children.clear();
+        rows.push((path.clone(), item));
+        flatten(&node.children, &path, rows);
+    }
+}
+
+fn print_json(out: &mut impl Write, value: &impl serde::Serialize) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    writeln!(out, "{}", content).map_err(|e| e.to_string())
+}
+
+/// Windows 下发布版本是 GUI 程序，需要连接到启动它的终端才能输出
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
@@ -0,0 +1,49 @@
+use crate::capture::CaptureState;
+use crate::errors::{Error, ErrorCode};
+use crate::models::TreeItem;
+use crate::store::Store;
+use crate::template::{self, TemplateSpec};
+use crate::tray;
+use crate::usage::UsageState;
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_notification::NotificationExt;
+
+/// 展开引用并替换变量，返回条目与渲染结果
+pub fn render(store: &Store, item_id: &str, values: &HashMap<String, String>) -> Result<(TreeItem, String), Error> {
+    let item = store.get_item(item_id).ok_or(ErrorCode::ItemNotFound)?;
+    let content = store.expand_includes(&item)?;
+    let text = template::render(&content, &TemplateSpec::of(&item.metadata), values)?;
+    Ok((item, text))
+}
+
+/// 写入系统剪贴板并记录一次使用，可选弹出系统通知
+pub fn write(app: &AppHandle, item: &TreeItem, text: &str, notify: bool) -> Result<(), String> {
+    app.state::<CaptureState>().ignore(text);
+    app.clipboard().write_text(text).map_err(|e| e.to_string())?;
+    app.state::<UsageState>().record(&item.id)?;
+    tray::refresh(app);
+
+    if notify {
+        let _ = app
+            .notification()
+            .builder()
+            .title("Copied to clipboard")
+            .body(&item.name)
+            .show();
+    }
+    Ok(())
+}
+
+/// 渲染 prompt 后复制到剪贴板；托盘、快速选择器与主窗口共用。返回复制的文本
+pub fn copy_prompt(
+    app: &AppHandle,
+    item_id: &str,
+    values: &HashMap<String, String>,
+    notify: bool,
+) -> Result<String, Error> {
+    let (item, text) = render(&app.state::<Store>(), item_id, values)?;
+    write(app, &item, &text, notify)?;
+    Ok(text)
+}
@@ -0,0 +1,147 @@
+use crate::applock::AppLock;
+use crate::logs;
+use crate::models::{EncryptionStatus, IntegrityIssue, IntegrityIssueKind, ItemType, PerfStats};
+use crate::store::Store;
+use crate::tree::Tree;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// 修改时间比当前时间晚多少毫秒以内不算问题（设备之间的时钟误差）
+const CLOCK_TOLERANCE: i64 = 5 * 60 * 1000;
+
+/// 诊断包中的 diagnostics.json
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Summary {
+    app_version: &'static str,
+    os: &'static str,
+    os_family: &'static str,
+    arch: &'static str,
+    generated_at: String,
+    data_dir: PathBuf,
+    store_path: PathBuf,
+    data_files: Vec<DataFile>,
+    encryption: EncryptionStatus,
+    app_lock_enabled: bool,
+    perf: PerfStats,
+}
+
+#[derive(Serialize)]
+struct DataFile {
+    name: String,
+    size: u64,
+}
+
+/// 检查条目树的结构：parentId、子节点、版本 id 与修改时间
+pub fn check_tree(tree: &Tree) -> Vec<IntegrityIssue> {
+    let latest = chrono::Utc::now().timestamp_millis() + CLOCK_TOLERANCE;
+    let mut issues = Vec::new();
+    let mut issue = |kind, id: &str, message: String| {
+        issues.push(IntegrityIssue {
+            kind,
+            item_id: Some(id.to_string()),
+            message,
+        })
+    };
+    tree.walk(|node, _| {
+        let parent = tree.parent(&node.id);
+        if node.parent_id.as_deref() != parent {
+            issue(
+                IntegrityIssueKind::ParentMismatch,
+                &node.id,
+                format!("parentId is {:?} but the item is under {:?}", node.parent_id, parent),
+            );
+        }
+        let children = tree.children(&node.id).len();
+        if children > 0 && !matches!(node.item_type, ItemType::Folder | ItemType::Provider) {
+            issue(
+                IntegrityIssueKind::UnexpectedChildren,
+                &node.id,
+                format!("{} has {} children", node.item_type.label(), children),
+            );
+        }
+        let mut versions = HashSet::new();
+        for version in node.versions.iter().flatten() {
+            if !versions.insert(version.id.as_str()) {
+                issue(
+                    IntegrityIssueKind::DuplicateVersionId,
+                    &node.id,
+                    format!("Version id {} appears more than once", version.id),
+                );
+            }
+        }
+        if node.metadata.last_modified.is_some_and(|t| t > latest) {
+            issue(
+                IntegrityIssueKind::FutureTimestamp,
+                &node.id,
+                "Last modified time is in the future".to_string(),
+            );
+        }
+    });
+    issues
+}
+
+/// 把应用版本、系统信息、数据目录概况、最近的日志与完整性检查结果打包为 zip。
+/// 不包含 store.json 及任何条目的名称与内容
+pub fn export(app: &AppHandle, destination: &Path) -> Result<(), String> {
+    let store = app.state::<Store>();
+    let store_path = store.path().to_path_buf();
+    let data_dir = store_path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let summary = Summary {
+        app_version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        os_family: std::env::consts::FAMILY,
+        arch: std::env::consts::ARCH,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        data_files: data_files(&data_dir),
+        data_dir,
+        store_path,
+        encryption: store.encryption_status(),
+        app_lock_enabled: app.state::<AppLock>().status().enabled,
+        perf: store.perf_stats(),
+    };
+
+    let file = File::create(destination).map_err(|e| format!("Failed to create {}: {}", destination.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut add = |name: &str, content: &[u8]| -> Result<(), String> {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(content).map_err(|e| e.to_string())
+    };
+    add(
+        "diagnostics.json",
+        &serde_json::to_vec_pretty(&summary).map_err(|e| e.to_string())?,
+    )?;
+    add(
+        "integrity.json",
+        &serde_json::to_vec_pretty(&store.check_integrity()).map_err(|e| e.to_string())?,
+    )?;
+    add("logs.txt", logs::recent().join("\n").as_bytes())?;
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 数据目录下的文件（不含子目录）及大小
+fn data_files(dir: &Path) -> Vec<DataFile> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<DataFile> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            Some(DataFile {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                size: metadata.len(),
+            })
+        })
+        .collect();
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    files
+}
@@ -0,0 +1,52 @@
+use crate::clipboard;
+use crate::store::Store;
+use crate::sync;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 拖出的临时文件保留的时间，之后在下一次拖动时清理
+const KEEP: Duration = Duration::from_secs(60 * 60);
+
+/// 渲染 prompt 并写入临时目录中的 `.md` / `.txt` 文件，返回文件路径，供前端发起系统拖放。
+/// 每个条目一个子目录，文件名与条目名相同
+pub fn prepare_file(
+    store: &Store,
+    item_id: &str,
+    values: &HashMap<String, String>,
+    extension: &str,
+) -> Result<PathBuf, String> {
+    let extension = match extension.trim_start_matches('.') {
+        "md" | "markdown" => "md",
+        "txt" | "text" => "txt",
+        other => return Err(format!("Unsupported file type: {}", other)),
+    };
+    let (item, text) = clipboard::render(store, item_id, values)?;
+
+    let root = std::env::temp_dir().join("prompt-manager-drag");
+    clean_up(&root);
+    let dir = root.join(sync::file_name(&item.id));
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("{}.{}", sync::file_name(&item.name), extension));
+    fs::write(&path, text).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// 删除之前拖出的过期文件
+fn clean_up(root: &Path) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > KEEP);
+        if expired {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use crate::models::{DuplicateGroup, DuplicateItem, ItemType};
+use crate::tree::Tree;
+use rayon::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// 相似度按连续几个词（shingle）的 Jaccard 系数计算
+const SHINGLE_WORDS: usize = 3;
+
+struct Entry {
+    item: DuplicateItem,
+    normalized: String,
+}
+
+/// 找出内容相同或相似度不低于 threshold 的 prompt 与片段。
+/// 先按规范化后的内容分组找出完全相同的，再两两比较各组的代表；
+/// 大小相差太多、不可能达到阈值的组合直接跳过
+pub fn find_duplicates(tree: &Tree, threshold: f64) -> Vec<DuplicateGroup> {
+    let mut entries = Vec::new();
+    tree.walk(|node, path| {
+        if !matches!(node.item_type, ItemType::Prompt | ItemType::Snippet) {
+            return;
+        }
+        let normalized = normalize(node.content.as_deref().unwrap_or_default());
+        if normalized.is_empty() {
+            return;
+        }
+        entries.push(Entry {
+            item: DuplicateItem {
+                id: node.id.clone(),
+                name: node.name.clone(),
+                path: path.to_string(),
+            },
+            normalized,
+        });
+    });
+
+    let mut groups = Groups::new(entries.len());
+    let mut exact: HashMap<&str, usize> = HashMap::new();
+    let mut representatives = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        match exact.get(entry.normalized.as_str()) {
+            Some(&first) => groups.union(first, index, 1.0),
+            None => {
+                exact.insert(&entry.normalized, index);
+                representatives.push(index);
+            }
+        }
+    }
+
+    if threshold < 1.0 {
+        let mut shingles: Vec<(usize, HashSet<u64>)> = representatives
+            .par_iter()
+            .map(|&index| (index, shingles(&entries[index].normalized)))
+            .collect();
+        shingles.sort_by_key(|(_, set)| set.len());
+        let shingles = &shingles;
+        let pairs: Vec<(usize, usize, f64)> = (0..shingles.len())
+            .into_par_iter()
+            .flat_map_iter(move |i| {
+                let (a, set_a) = &shingles[i];
+                shingles[i + 1..]
+                    .iter()
+                    // 按大小排序后，后面的集合更大，Jaccard 系数不超过 |A| / |B|
+                    .take_while(move |(_, set_b)| set_a.len() as f64 >= threshold * set_b.len() as f64)
+                    .filter_map(move |(b, set_b)| {
+                        let similarity = jaccard(set_a, set_b);
+                        (similarity >= threshold).then_some((*a, *b, similarity))
+                    })
+            })
+            .collect();
+        for (a, b, similarity) in pairs {
+            groups.union(a, b, similarity);
+        }
+    }
+
+    groups.collect(entries)
+}
+
+/// 忽略大小写与空白的差异
+fn normalize(content: &str) -> String {
+    content
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn shingles(normalized: &str) -> HashSet<u64> {
+    let words: Vec<&str> = normalized.split(' ').collect();
+    words
+        .windows(SHINGLE_WORDS.min(words.len()))
+        .map(|window| {
+            let mut hasher = DefaultHasher::new();
+            window.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let common = a.intersection(b).count();
+    common as f64 / (a.len() + b.len() - common) as f64
+}
+
+/// 并查集；记录每组中最低的相似度
+struct Groups {
+    parent: Vec<usize>,
+    similarity: Vec<f64>,
+    exact: Vec<bool>,
+}
+
+impl Groups {
+    fn new(len: usize) -> Self {
+        Groups {
+            parent: (0..len).collect(),
+            similarity: vec![1.0; len],
+            exact: vec![true; len],
+        }
+    }
+
+    fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut current = index;
+        while self.parent[current] != root {
+            current = std::mem::replace(&mut self.parent[current], root);
+        }
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize, similarity: f64) {
+        let (a, b) = (self.find(a), self.find(b));
+        let similarity = similarity.min(self.similarity[a]).min(self.similarity[b]);
+        let exact = similarity >= 1.0 && self.exact[a] && self.exact[b];
+        if a != b {
+            self.parent[b] = a;
+        }
+        self.similarity[a] = similarity;
+        self.exact[a] = exact;
+    }
+
+    /// 两个以上条目的组，相似度高的在前
+    fn collect(mut self, entries: Vec<Entry>) -> Vec<DuplicateGroup> {
+        let mut members: HashMap<usize, Vec<DuplicateItem>> = HashMap::new();
+        let mut order = Vec::new();
+        for (index, entry) in entries.into_iter().enumerate() {
+            let root = self.find(index);
+            let items = members.entry(root).or_default();
+            if items.is_empty() {
+                order.push(root);
+            }
+            items.push(entry.item);
+        }
+        let mut groups: Vec<DuplicateGroup> = order
+            .into_iter()
+            .filter_map(|root| {
+                let items = members.remove(&root)?;
+                (items.len() > 1).then(|| DuplicateGroup {
+                    exact: self.exact[root],
+                    similarity: self.similarity[root],
+                    items,
+                })
+            })
+            .collect();
+        groups.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+        groups
+    }
+}
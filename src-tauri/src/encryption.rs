@@ -0,0 +1,126 @@
+use crate::errors::{Error, ErrorCode};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+const VERSION: u32 = 1;
+const KDF: &str = "argon2id";
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// 加密后写入 store.json 的内容；密钥由口令经 argon2id 与随机盐派生，内容用 AES-256-GCM 加密
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    /// 格式版本，同时用来区分加密与未加密的 store.json
+    encrypted: u32,
+    kdf: String,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// 由口令派生的密钥；保存盐以便每次写入时无需重新派生
+#[derive(Clone)]
+pub struct Key {
+    key: [u8; 32],
+    salt: Vec<u8>,
+}
+
+impl Key {
+    /// 用新的随机盐派生密钥（启用加密或更换口令时）
+    pub fn generate(passphrase: &str) -> Result<Key, Error> {
+        if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+            return Err(Error::with(ErrorCode::PassphraseTooShort, MIN_PASSPHRASE_LEN));
+        }
+        let mut salt = vec![0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        Ok(Key::derive(passphrase, salt)?)
+    }
+
+    fn derive(passphrase: &str, salt: Vec<u8>) -> Result<Key, String> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| e.to_string())?;
+        Ok(Key { key, salt })
+    }
+
+    /// 口令是否与此密钥一致
+    pub fn verify(&self, passphrase: &str) -> bool {
+        Key::derive(passphrase, self.salt.clone()).is_ok_and(|other| other.key == self.key)
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<String, String> {
+        let mut nonce = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher()?
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|e| e.to_string())?;
+        let envelope = Envelope {
+            encrypted: VERSION,
+            kdf: KDF.to_string(),
+            salt: STANDARD.encode(&self.salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        };
+        serde_json::to_string_pretty(&envelope).map_err(|e| e.to_string())
+    }
+
+    /// 用已有密钥解密；文件由其他设备用同一口令重新加密过（盐不同）时需要用口令重新打开
+    pub fn decrypt(&self, content: &str) -> Result<Vec<u8>, Error> {
+        let envelope = parse(content)?;
+        if decode(&envelope.salt)? != self.salt {
+            return Err("store.json was encrypted with a different key".into());
+        }
+        decrypt_envelope(self, &envelope)
+    }
+
+    fn cipher(&self) -> Result<Aes256Gcm, String> {
+        Aes256Gcm::new_from_slice(&self.key).map_err(|e| e.to_string())
+    }
+}
+
+/// 内容是否为加密后的 store.json（包括本版本不支持的加密格式，以免被当作空库覆盖）
+pub fn is_encrypted(content: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(content).is_ok_and(|value| value.get("encrypted").is_some())
+}
+
+/// 用口令解密，返回明文与派生出的密钥
+pub fn open(passphrase: &str, content: &str) -> Result<(Key, Vec<u8>), Error> {
+    let envelope = parse(content)?;
+    let key = Key::derive(passphrase, decode(&envelope.salt)?)?;
+    let plaintext = decrypt_envelope(&key, &envelope)?;
+    Ok((key, plaintext))
+}
+
+fn parse(content: &str) -> Result<Envelope, String> {
+    let envelope: Envelope = serde_json::from_str(content).map_err(|_| "store.json is not encrypted".to_string())?;
+    if envelope.encrypted != VERSION || envelope.kdf != KDF {
+        return Err(format!(
+            "Unsupported encryption format {} ({})",
+            envelope.encrypted, envelope.kdf
+        ));
+    }
+    Ok(envelope)
+}
+
+fn decrypt_envelope(key: &Key, envelope: &Envelope) -> Result<Vec<u8>, Error> {
+    let nonce = decode(&envelope.nonce)?;
+    if nonce.len() != 12 {
+        return Err("Invalid nonce in encrypted store".into());
+    }
+    key.cipher()?
+        .decrypt(Nonce::from_slice(&nonce), decode(&envelope.ciphertext)?.as_slice())
+        .map_err(|_| ErrorCode::WrongPassphrase.into())
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, String> {
+    STANDARD
+        .decode(value)
+        .map_err(|e| format!("Invalid encrypted store: {}", e))
+}
@@ -0,0 +1,354 @@
+use crate::locale;
+use crate::models::Language;
+use serde::Serialize;
+use std::fmt;
+
+/// 错误码：在产生错误的地方给出，前端据此决定如何提示（例如锁定时跳到解锁界面），界面文字按错误码翻译
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorCode {
+    ItemNotFound,
+    ModelNotFound,
+    ProviderNotFound,
+    ParentNotFound,
+    TargetParentNotFound,
+    SnippetNotFound,
+    IncludedItemNotFound,
+    PathNotFound,
+    WebhookNotFound,
+    TokenNotFound,
+    RunNotFound,
+    ConflictNotFound,
+    StoreLocked,
+    AppLocked,
+    WrongPassphrase,
+    WrongPairingCode,
+    /// 条目属于只读的挂载库
+    ReadOnly,
+    NotSnippet,
+    NotModel,
+    NotProvider,
+    NotFolder,
+    /// 只有 prompt 可以运行
+    NotRunnable,
+    /// 片段只能插入到 prompt 中
+    NotInsertable,
+    MoveIntoSelf,
+    MoveIntoSubtree,
+    GitSyncDisabled,
+    WebDavSyncDisabled,
+    AppLockDisabled,
+    EncryptionDisabled,
+    EncryptionEnabled,
+    /// 库已加密时不能启用同步目标
+    SyncWhileEncrypted,
+    /// 启用加密前需要关闭同步目标
+    SyncTargetsEnabled,
+    UnresolvedConflicts,
+    /// 同步目录中有不是本应用导出的文件
+    SyncDirNotEmpty,
+    PassphraseTooShort,
+    EmptyVariableName,
+    EnumWithoutOptions,
+    InvalidDefaultValue,
+    NotANumber,
+    NotABoolean,
+    NotAnOption,
+    /// 多个变量的值不合法
+    InvalidValues,
+    TokenNameRequired,
+    BranchRequired,
+    InvalidWebDavUrl,
+    InvalidWebhookUrl,
+    WebhookSchemeUnsupported,
+    UnsupportedLink,
+    CaptureLengthRange,
+    ClipboardEmpty,
+    FontFamilyRequired,
+    FontSizeOutOfRange,
+    InvalidThreshold,
+    TemperatureOutOfRange,
+    TopPOutOfRange,
+    MaxTokensTooSmall,
+    UnknownTimeZone,
+    MaintenanceIntervalOutOfRange,
+    NoAutoVersionsKept,
+    ModelNameMissing,
+    PricingMissing,
+    MissingValues,
+    IncludeCycle,
+    TemplateError,
+    /// 带行列位置的模板错误
+    TemplateErrorAt,
+    RequestFailed,
+    ConnectionFailed,
+    SendTimedOut,
+    TransferRejected,
+    WebDavUploadFailed,
+    WebDavDownloadFailed,
+    /// 上传期间服务器上的副本被其他设备修改
+    WebDavChanged,
+    Cancelled,
+    /// 没有错误码的错误，说明原样显示
+    Unknown,
+}
+
+impl ErrorCode {
+    /// 说明的模板，`{}` 依次替换为错误附带的值
+    fn template(self, language: Language) -> &'static str {
+        use ErrorCode::*;
+        match language {
+            Language::En => match self {
+                ItemNotFound => "Item not found",
+                ModelNotFound => "Model not found",
+                ProviderNotFound => "Provider not found",
+                ParentNotFound => "Parent not found",
+                TargetParentNotFound => "Target parent not found",
+                SnippetNotFound => "Snippet not found",
+                IncludedItemNotFound => "Included item not found: {}",
+                PathNotFound => "Path not found: {}",
+                WebhookNotFound => "Webhook not found",
+                TokenNotFound => "Token not found",
+                RunNotFound => "Run not found",
+                ConflictNotFound => "Conflict not found",
+                StoreLocked => "Store is locked",
+                AppLocked => "App is locked",
+                WrongPassphrase => "Wrong passphrase",
+                WrongPairingCode => "Wrong pairing code",
+                ReadOnly => "Item belongs to a read-only library",
+                NotSnippet => "Target is not a snippet",
+                NotModel => "Target is not a model",
+                NotProvider => "Target is not a provider",
+                NotFolder => "Target is not a folder",
+                NotRunnable => "Only prompts can be run",
+                NotInsertable => "Snippets can only be inserted into prompts",
+                MoveIntoSelf => "Cannot move item into itself",
+                MoveIntoSubtree => "Cannot move folder into its own subtree",
+                GitSyncDisabled => "Git sync is not enabled",
+                WebDavSyncDisabled => "WebDAV sync is not enabled",
+                AppLockDisabled => "App lock is not enabled",
+                EncryptionDisabled => "Encryption is not enabled",
+                EncryptionEnabled => "Encryption is already enabled",
+                SyncWhileEncrypted => "Sync cannot be enabled while the library is encrypted",
+                SyncTargetsEnabled => "Disable git, WebDAV and folder sync before enabling encryption",
+                UnresolvedConflicts => "Resolve sync conflicts before enabling encryption",
+                SyncDirNotEmpty => "{} is not empty and was not exported by Prompt Manager",
+                PassphraseTooShort => "Passphrase must be at least {} characters",
+                EmptyVariableName => "Variable name cannot be empty",
+                EnumWithoutOptions => "Enum variable '{}' needs at least one option",
+                InvalidDefaultValue => "Invalid default value for '{}': '{}'",
+                NotANumber => "'{}' expects a number, got '{}'",
+                NotABoolean => "'{}' expects true or false, got '{}'",
+                NotAnOption => "'{}' expects one of {}, got '{}'",
+                InvalidValues => "Invalid values for variables: {}",
+                TokenNameRequired => "Token name is required",
+                BranchRequired => "Branch cannot be empty",
+                InvalidWebDavUrl => "WebDAV URL must start with http:// or https://",
+                InvalidWebhookUrl => "Invalid webhook URL: {}",
+                WebhookSchemeUnsupported => "Webhook URL must use http or https",
+                UnsupportedLink => "Unsupported link: {}",
+                CaptureLengthRange => "Minimum length must not exceed maximum length",
+                ClipboardEmpty => "Clipboard does not contain text",
+                FontFamilyRequired => "Editor font family is required",
+                FontSizeOutOfRange => "Editor font size must be between {} and {}",
+                InvalidThreshold => "Threshold must be between 0 and 1",
+                TemperatureOutOfRange => "temperature must be between 0 and {}",
+                TopPOutOfRange => "top_p must be between 0 and 1",
+                MaxTokensTooSmall => "max_tokens must be at least 1",
+                UnknownTimeZone => "Unknown time zone: {}",
+                MaintenanceIntervalOutOfRange => "Maintenance interval must be between {} and {} hours",
+                NoAutoVersionsKept => "Keep at least one auto-saved version",
+                ModelNameMissing => "Model name is not configured",
+                PricingMissing => "No pricing configured for model {}",
+                MissingValues => "Missing values for variables: {}",
+                IncludeCycle => "Include cycle detected: {}",
+                TemplateError => "Template error: {}",
+                TemplateErrorAt => "Template error at line {}, column {}: {}",
+                RequestFailed => "Request failed ({}): {}",
+                ConnectionFailed => "Failed to connect to {}: {}",
+                SendTimedOut => "Timed out sending to {}",
+                TransferRejected => "{} rejected the transfer",
+                WebDavUploadFailed => "WebDAV upload failed: {}",
+                WebDavDownloadFailed => "WebDAV download failed: {}",
+                WebDavChanged => "The WebDAV copy changed during upload, please sync again",
+                Cancelled => "Request cancelled",
+                Unknown => "{}",
+            },
+            Language::Zh => match self {
+                ItemNotFound => "找不到条目",
+                ModelNotFound => "找不到模型",
+                ProviderNotFound => "找不到服务商",
+                ParentNotFound => "找不到父文件夹",
+                TargetParentNotFound => "找不到目标文件夹",
+                SnippetNotFound => "找不到片段",
+                IncludedItemNotFound => "找不到引用的条目：{}",
+                PathNotFound => "找不到路径：{}",
+                WebhookNotFound => "找不到 Webhook",
+                TokenNotFound => "找不到令牌",
+                RunNotFound => "找不到运行记录",
+                ConflictNotFound => "找不到冲突",
+                StoreLocked => "数据库已锁定",
+                AppLocked => "应用已锁定",
+                WrongPassphrase => "密码错误",
+                WrongPairingCode => "配对码错误",
+                ReadOnly => "条目属于只读库",
+                NotSnippet => "目标不是片段",
+                NotModel => "目标不是模型",
+                NotProvider => "目标不是服务商",
+                NotFolder => "目标不是文件夹",
+                NotRunnable => "只能运行 prompt",
+                NotInsertable => "片段只能插入到 prompt 中",
+                MoveIntoSelf => "不能把条目移到自身中",
+                MoveIntoSubtree => "不能把文件夹移到自己的子文件夹中",
+                GitSyncDisabled => "未启用 Git 同步",
+                WebDavSyncDisabled => "未启用 WebDAV 同步",
+                AppLockDisabled => "未启用应用锁",
+                EncryptionDisabled => "未启用加密",
+                EncryptionEnabled => "已启用加密",
+                SyncWhileEncrypted => "库已加密，不能启用同步",
+                SyncTargetsEnabled => "启用加密前请先关闭 Git、WebDAV 与目录同步",
+                UnresolvedConflicts => "启用加密前请先解决同步冲突",
+                SyncDirNotEmpty => "{} 不是空目录，也不是由 Prompt Manager 导出的",
+                PassphraseTooShort => "密码至少需要 {} 个字符",
+                EmptyVariableName => "变量名不能为空",
+                EnumWithoutOptions => "枚举变量“{}”至少需要一个选项",
+                InvalidDefaultValue => "变量“{}”的默认值“{}”不合法",
+                NotANumber => "变量“{}”应为数字，实际为“{}”",
+                NotABoolean => "变量“{}”应为 true 或 false，实际为“{}”",
+                NotAnOption => "变量“{}”应为以下之一：{}，实际为“{}”",
+                InvalidValues => "以下变量的值不合法：{}",
+                TokenNameRequired => "请输入令牌名称",
+                BranchRequired => "分支不能为空",
+                InvalidWebDavUrl => "WebDAV 地址必须以 http:// 或 https:// 开头",
+                InvalidWebhookUrl => "Webhook 地址无效：{}",
+                WebhookSchemeUnsupported => "Webhook 地址必须使用 http 或 https",
+                UnsupportedLink => "不支持的链接：{}",
+                CaptureLengthRange => "最小长度不能大于最大长度",
+                ClipboardEmpty => "剪贴板中没有文本",
+                FontFamilyRequired => "请输入编辑器字体",
+                FontSizeOutOfRange => "编辑器字号应在 {} 到 {} 之间",
+                InvalidThreshold => "阈值应在 0 到 1 之间",
+                TemperatureOutOfRange => "temperature 应在 0 到 {} 之间",
+                TopPOutOfRange => "top_p 应在 0 到 1 之间",
+                MaxTokensTooSmall => "max_tokens 至少为 1",
+                UnknownTimeZone => "未知的时区：{}",
+                MaintenanceIntervalOutOfRange => "维护间隔应在 {} 到 {} 小时之间",
+                NoAutoVersionsKept => "至少保留一个自动保存的版本",
+                ModelNameMissing => "未配置模型名称",
+                PricingMissing => "模型 {} 未配置价格",
+                MissingValues => "以下变量缺少值：{}",
+                IncludeCycle => "检测到循环引用：{}",
+                TemplateError => "模板错误：{}",
+                TemplateErrorAt => "模板错误（第 {} 行，第 {} 列）：{}",
+                RequestFailed => "请求失败（{}）：{}",
+                ConnectionFailed => "无法连接到 {}：{}",
+                SendTimedOut => "发送到 {} 超时",
+                TransferRejected => "{} 拒绝了传输",
+                WebDavUploadFailed => "WebDAV 上传失败：{}",
+                WebDavDownloadFailed => "WebDAV 下载失败：{}",
+                WebDavChanged => "上传期间 WebDAV 上的副本已被修改，请重新同步",
+                Cancelled => "请求已取消",
+                Unknown => "{}",
+            },
+        }
+    }
+}
+
+/// 带错误码的错误，values 为说明中的名称、状态码、原始错误等
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub code: ErrorCode,
+    pub values: Vec<String>,
+}
+
+impl Error {
+    pub fn new(code: ErrorCode, values: Vec<String>) -> Self {
+        Error { code, values }
+    }
+
+    /// 附带一个值的错误
+    pub fn with(code: ErrorCode, value: impl ToString) -> Self {
+        Error::new(code, vec![value.to_string()])
+    }
+
+    /// 按语言生成说明
+    pub fn message(&self, language: Language) -> String {
+        let mut values = self.values.iter();
+        let mut parts = self.code.template(language).split("{}");
+        let mut message = parts.next().unwrap_or_default().to_string();
+        for part in parts {
+            message.push_str(values.next().map(String::as_str).unwrap_or_default());
+            message.push_str(part);
+        }
+        message
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message(Language::En))
+    }
+}
+
+impl From<ErrorCode> for Error {
+    fn from(code: ErrorCode) -> Self {
+        Error::new(code, Vec::new())
+    }
+}
+
+/// 没有错误码的错误（I/O、序列化等）
+impl From<String> for Error {
+    fn from(detail: String) -> Self {
+        Error::with(ErrorCode::Unknown, detail)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(detail: &str) -> Self {
+        Error::from(detail.to_string())
+    }
+}
+
+/// 仍以 String 传递错误的调用方只保留英文说明
+impl From<Error> for String {
+    fn from(error: Error) -> Self {
+        error.to_string()
+    }
+}
+
+/// 命令返回的错误：message 为按界面语言翻译的说明，detail 为英文说明
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub detail: String,
+}
+
+impl From<Error> for CommandError {
+    fn from(error: Error) -> Self {
+        CommandError {
+            code: error.code,
+            message: error.message(locale::language()),
+            detail: error.to_string(),
+        }
+    }
+}
+
+impl From<ErrorCode> for CommandError {
+    fn from(code: ErrorCode) -> Self {
+        CommandError::from(Error::from(code))
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(detail: String) -> Self {
+        CommandError::from(Error::from(detail))
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(detail: &str) -> Self {
+        CommandError::from(detail.to_string())
+    }
+}
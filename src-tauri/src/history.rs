@@ -0,0 +1,221 @@
+use crate::log_error;
+use crate::models::{HistoryPruning, RunRecord};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct HistoryData {
+    #[serde(default)]
+    pruning: HistoryPruning,
+    /// item id -> 运行记录（按时间先后排列）
+    #[serde(default)]
+    runs: HashMap<String, Vec<RunRecord>>,
+}
+
+/// 运行历史，独立保存在 run_history.json，避免撑大主 store
+pub struct RunHistory {
+    data: Mutex<HistoryData>,
+    path: PathBuf,
+    /// 库加密时不记录：运行记录含 prompt 原文与模型输出，而 run_history.json 是明文
+    paused: AtomicBool,
+}
+
+impl RunHistory {
+    /// `encrypted` 为库是否启用了加密，是则清空之前留下的记录
+    pub fn new(dir: PathBuf, encrypted: bool) -> Self {
+        let path = dir.join("run_history.json");
+        let data = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        let history = RunHistory {
+            data: Mutex::new(data),
+            path,
+            paused: AtomicBool::new(false),
+        };
+        if let Err(e) = history.set_paused(encrypted) {
+            log_error!("Failed to clear run history: {}", e);
+        }
+        history
+    }
+
+    /// 启用加密时暂停并清空全部运行记录（run_history.json 只保留清理设置），关闭加密后恢复记录
+    pub fn set_paused(&self, paused: bool) -> Result<(), String> {
+        self.paused.store(paused, Ordering::SeqCst);
+        let mut data = self.data.lock().map_err(|e| e.to_string())?;
+        if paused && !data.runs.is_empty() {
+            data.runs.clear();
+            self.save(&data)?;
+        }
+        Ok(())
+    }
+
+    fn save(&self, data: &HistoryData) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+        fs::write(&self.path, content).map_err(|e| e.to_string())
+    }
+
+    pub fn record(&self, record: RunRecord) -> Result<(), String> {
+        if self.paused.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        let mut data = self.data.lock().map_err(|e| e.to_string())?;
+        data.runs.entry(record.item_id.clone()).or_default().push(record);
+        let pruning = data.pruning.clone();
+        Self::prune(&mut data.runs, &pruning);
+        self.save(&data)
+    }
+
+    /// 返回某个 prompt 的运行历史，最新的在前
+    pub fn get(&self, item_id: &str) -> Vec<RunRecord> {
+        let data = self.data.lock().unwrap();
+        let mut runs = data.runs.get(item_id).cloned().unwrap_or_default();
+        runs.reverse();
+        runs
+    }
+
+    pub fn clear(&self, item_id: &str) -> Result<(), String> {
+        let mut data = self.data.lock().map_err(|e| e.to_string())?;
+        data.runs.remove(item_id);
+        self.save(&data)
+    }
+
+    pub fn pruning(&self) -> HistoryPruning {
+        self.data.lock().unwrap().pruning.clone()
+    }
+
+    pub fn set_pruning(&self, pruning: HistoryPruning) -> Result<HistoryPruning, String> {
+        if pruning.max_runs_per_item == 0 {
+            return Err("maxRunsPerItem must be at least 1".to_string());
+        }
+        let mut data = self.data.lock().map_err(|e| e.to_string())?;
+        data.pruning = pruning.clone();
+        Self::prune(&mut data.runs, &pruning);
+        self.save(&data)?;
+        Ok(pruning)
+    }
+
+    fn prune(runs: &mut HashMap<String, Vec<RunRecord>>, pruning: &HistoryPruning) {
+        let cutoff = pruning
+            .max_age_days
+            .map(|days| chrono::Utc::now().timestamp_millis() - days as i64 * 86_400_000);
+
+        for records in runs.values_mut() {
+            if let Some(cutoff) = cutoff {
+                records.retain(|r| r.timestamp >= cutoff);
+            }
+            if records.len() > pruning.max_runs_per_item {
+                let excess = records.len() - pruning.max_runs_per_item;
+                records.drain(..excess);
+            }
+        }
+        runs.retain(|_, records| !records.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ItemMetadata, ItemType, TreeItem};
+    use crate::store::Store;
+    use std::path::Path;
+
+    const MARKER: &str = "plaintext marker 7f3a";
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("prompt-manager-history-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn run(item_id: &str) -> RunRecord {
+        RunRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            item_id: item_id.to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            model: "model".to_string(),
+            parameters: None,
+            input: MARKER.to_string(),
+            response: MARKER.to_string(),
+            latency_ms: 0,
+            usage: None,
+            cost: None,
+            cancelled: false,
+            error: None,
+        }
+    }
+
+    /// 数据目录（含子目录）中内容包含 text 的文件
+    fn files_containing(dir: &Path, text: &str) -> Vec<PathBuf> {
+        let mut found = Vec::new();
+        for entry in fs::read_dir(dir).unwrap().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                found.extend(files_containing(&path, text));
+            } else if String::from_utf8_lossy(&fs::read(&path).unwrap()).contains(text) {
+                found.push(path);
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn enabling_encryption_leaves_no_plaintext_in_the_data_dir() {
+        let dir = temp_dir();
+        let store = Store::open(&dir);
+        let history = RunHistory::new(dir.clone(), false);
+        let item = TreeItem {
+            id: String::new(),
+            name: "Greeting".to_string(),
+            item_type: ItemType::Prompt,
+            children: Vec::new(),
+            parent_id: None,
+            content: Some(MARKER.into()),
+            versions: None,
+            metadata: ItemMetadata::default(),
+        };
+        let item = store.add_item(None, item).unwrap();
+        history.record(run(&item.id)).unwrap();
+        store.flush();
+        assert_eq!(files_containing(&dir, MARKER).len(), 2);
+
+        store.enable_encryption("passphrase").unwrap();
+        history.set_paused(true).unwrap();
+        history.record(run(&item.id)).unwrap();
+
+        assert!(files_containing(&dir, MARKER).is_empty());
+        assert!(history.get(&item.id).is_empty());
+    }
+
+    #[test]
+    fn clears_runs_left_from_before_encryption_on_startup() {
+        let dir = temp_dir();
+        let history = RunHistory::new(dir.clone(), false);
+        let pruning = HistoryPruning {
+            max_runs_per_item: 5,
+            max_age_days: Some(30),
+        };
+        history.set_pruning(pruning).unwrap();
+        history.record(run("a")).unwrap();
+
+        let history = RunHistory::new(dir.clone(), true);
+
+        assert!(history.get("a").is_empty());
+        assert!(files_containing(&dir, MARKER).is_empty());
+        assert_eq!(history.pruning().max_runs_per_item, 5);
+    }
+
+    #[test]
+    fn records_again_after_encryption_is_disabled() {
+        let history = RunHistory::new(temp_dir(), true);
+        history.set_paused(false).unwrap();
+        history.record(run("a")).unwrap();
+        assert_eq!(history.get("a").len(), 1);
+    }
+}
@@ -0,0 +1,367 @@
+//! 局域网内点对点发送条目：接收方通过 mDNS 广播自己并显示配对码，
+//! 发送方连接后双方以配对码做 SPAKE2 密钥交换，用得到的会话密钥以 ChaCha20-Poly1305 加密传输 JSON。
+//! 配对码不参与任何可离线验证的数据，每次连接至多猜一次；不正确时接收方无法解密，连续失败多次后自动停止接收
+
+use crate::errors::{Error, ErrorCode};
+use crate::log_error;
+use crate::models::{ItemType, LanPeer, LanSession, TreeItem};
+use crate::store::Store;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use spake2::{Ed25519Group, Identity, Password, Spake2};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
+
+pub const EVENT_RECEIVED: &str = "lan://received";
+
+const SERVICE_TYPE: &str = "_prompt-manager._tcp.local.";
+const PROTOCOL: &[u8] = b"prompt-manager-lan-v2";
+/// SPAKE2 消息长度：一个字节的角色标记加一个 Ed25519 点
+const HANDSHAKE_LEN: usize = 33;
+const MAX_PAYLOAD: usize = 64 * 1024 * 1024;
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+/// 配对码连续错误达到该次数后停止接收，防止猜测
+const MAX_FAILURES: u32 = 5;
+
+const STATUS_OK: u8 = 0;
+const STATUS_BAD_CODE: u8 = 1;
+const STATUS_INVALID: u8 = 2;
+
+/// 传输的内容（加密前）
+#[derive(Serialize, Deserialize)]
+struct Payload {
+    from: String,
+    items: Vec<TreeItem>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct LanReceivedEvent {
+    from: String,
+    /// 收到的条目放入的新文件夹
+    folder_id: String,
+    count: usize,
+}
+
+struct Receiver {
+    session: LanSession,
+    daemon: ServiceDaemon,
+    fullname: String,
+    shutdown: Arc<Notify>,
+}
+
+pub struct LanState {
+    receiver: Mutex<Option<Receiver>>,
+    app: AppHandle,
+}
+
+impl LanState {
+    pub fn new(app: &AppHandle) -> Self {
+        Self {
+            receiver: Mutex::new(None),
+            app: app.clone(),
+        }
+    }
+
+    /// 开始接收：监听随机端口并通过 mDNS 广播；已在接收时返回当前会话
+    pub async fn start_receive(&self) -> Result<LanSession, String> {
+        if let Some(receiver) = self.receiver.lock().map_err(|e| e.to_string())?.as_ref() {
+            return Ok(receiver.session.clone());
+        }
+
+        let listener = TcpListener::bind(("0.0.0.0", 0)).await.map_err(|e| e.to_string())?;
+        let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+        let session = LanSession {
+            name: device_name(),
+            port,
+            code: format!("{:06}", OsRng.next_u32() % 1_000_000),
+        };
+
+        let daemon = ServiceDaemon::new().map_err(|e| format!("Failed to start mDNS: {}", e))?;
+        let host = format!("{}.local.", host_label(&session.name));
+        let instance = format!("{} ({})", session.name, port);
+        let info = ServiceInfo::new(
+            SERVICE_TYPE,
+            &instance,
+            &host,
+            "",
+            port,
+            &[("name", session.name.as_str())][..],
+        )
+        .map_err(|e| e.to_string())?
+        .enable_addr_auto();
+        let fullname = info.get_fullname().to_string();
+        daemon
+            .register(info)
+            .map_err(|e| format!("Failed to advertise via mDNS: {}", e))?;
+
+        let shutdown = Arc::new(Notify::new());
+        tauri::async_runtime::spawn(accept_loop(
+            self.app.clone(),
+            listener,
+            session.code.clone(),
+            shutdown.clone(),
+        ));
+
+        let mut receiver = self.receiver.lock().map_err(|e| e.to_string())?;
+        if let Some(existing) = receiver.as_ref() {
+            // 并发调用时以先启动的会话为准
+            shutdown.notify_one();
+            let _ = daemon.shutdown();
+            return Ok(existing.session.clone());
+        }
+        *receiver = Some(Receiver {
+            session: session.clone(),
+            daemon,
+            fullname,
+            shutdown,
+        });
+        Ok(session)
+    }
+
+    pub fn stop_receive(&self) {
+        let receiver = self.receiver.lock().ok().and_then(|mut r| r.take());
+        if let Some(receiver) = receiver {
+            receiver.shutdown.notify_one();
+            let _ = receiver.daemon.unregister(&receiver.fullname);
+            let _ = receiver.daemon.shutdown();
+        }
+    }
+
+    pub fn session(&self) -> Option<LanSession> {
+        self.receiver.lock().ok()?.as_ref().map(|r| r.session.clone())
+    }
+
+    /// 在局域网中查找正在接收的设备（阻塞 `timeout`），不包括自己
+    pub fn discover(&self, timeout: Duration) -> Result<Vec<LanPeer>, String> {
+        let own = self
+            .receiver
+            .lock()
+            .map_err(|e| e.to_string())?
+            .as_ref()
+            .map(|r| r.fullname.clone());
+        let daemon = ServiceDaemon::new().map_err(|e| format!("Failed to start mDNS: {}", e))?;
+        let events = daemon.browse(SERVICE_TYPE).map_err(|e| e.to_string())?;
+
+        let deadline = std::time::Instant::now() + timeout;
+        let mut peers: Vec<(String, LanPeer)> = Vec::new();
+        while let Some(remaining) = deadline.checked_duration_since(std::time::Instant::now()) {
+            let Ok(event) = events.recv_timeout(remaining) else {
+                break;
+            };
+            let ServiceEvent::ServiceResolved(info) = event else {
+                continue;
+            };
+            let fullname = info.get_fullname().to_string();
+            if own.as_deref() == Some(fullname.as_str()) || peers.iter().any(|(name, _)| *name == fullname) {
+                continue;
+            }
+            // 优先使用 IPv4 地址
+            let mut addresses: Vec<_> = info.get_addresses().iter().copied().collect();
+            addresses.sort_by_key(|ip| !ip.is_ipv4());
+            if let Some(ip) = addresses.first() {
+                let name = info.get_property_val_str("name").unwrap_or(&fullname).to_string();
+                peers.push((
+                    fullname.clone(),
+                    LanPeer {
+                        name,
+                        host: ip.to_string(),
+                        port: info.get_port(),
+                    },
+                ));
+            }
+        }
+        let _ = daemon.shutdown();
+        Ok(peers.into_iter().map(|(_, peer)| peer).collect())
+    }
+}
+
+/// 把条目（含子节点，不含 API Key 等凭据）发送给正在接收的设备
+pub async fn send_items(items: Vec<TreeItem>, peer: &LanPeer, code: &str) -> Result<(), Error> {
+    let payload = Payload {
+        from: device_name(),
+        items: crate::sync::strip_secrets(&items),
+    };
+    let plaintext = serde_json::to_vec(&payload).map_err(|e| e.to_string())?;
+
+    let status = tokio::time::timeout(IO_TIMEOUT, async {
+        let mut stream = TcpStream::connect((peer.host.as_str(), peer.port))
+            .await
+            .map_err(|e| Error::new(ErrorCode::ConnectionFailed, vec![peer.name.clone(), e.to_string()]))?;
+        let cipher = handshake(&mut stream, code, true).await?;
+
+        let mut nonce = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|e| e.to_string())?;
+        stream.write_all(&nonce).await.map_err(|e| e.to_string())?;
+        write_frame(&mut stream, &ciphertext).await?;
+        Ok::<u8, Error>(stream.read_u8().await.map_err(|e| e.to_string())?)
+    })
+    .await
+    .map_err(|_| Error::with(ErrorCode::SendTimedOut, &peer.name))??;
+
+    match status {
+        STATUS_OK => Ok(()),
+        STATUS_BAD_CODE => Err(ErrorCode::WrongPairingCode.into()),
+        _ => Err(Error::with(ErrorCode::TransferRejected, &peer.name)),
+    }
+}
+
+async fn accept_loop(app: AppHandle, listener: TcpListener, code: String, shutdown: Arc<Notify>) {
+    let mut failures = 0;
+    loop {
+        let mut stream = tokio::select! {
+            _ = shutdown.notified() => return,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(_) => continue,
+            },
+        };
+
+        // 一次只处理一个连接，便于统计配对码错误
+        let received = tokio::time::timeout(IO_TIMEOUT, receive(&mut stream, &code)).await;
+        let status = match received {
+            Ok(Ok(payload)) => match import(&app, payload) {
+                Ok(()) => STATUS_OK,
+                Err(e) => {
+                    log_error!("Failed to import received items: {}", e);
+                    STATUS_INVALID
+                }
+            },
+            Ok(Err(status)) => status,
+            Err(_) => STATUS_INVALID,
+        };
+        let _ = stream.write_u8(status).await;
+
+        if status == STATUS_BAD_CODE {
+            failures += 1;
+            if failures >= MAX_FAILURES {
+                app.state::<LanState>().stop_receive();
+                return;
+            }
+        }
+    }
+}
+
+/// 接收并解密一次传输，失败时返回要回复的状态
+async fn receive(stream: &mut TcpStream, code: &str) -> Result<Payload, u8> {
+    let cipher = handshake(stream, code, false).await.map_err(|_| STATUS_INVALID)?;
+    let mut nonce = [0u8; 12];
+    stream.read_exact(&mut nonce).await.map_err(|_| STATUS_INVALID)?;
+    let ciphertext = read_frame(stream).await.map_err(|_| STATUS_INVALID)?;
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| STATUS_BAD_CODE)?;
+    serde_json::from_slice(&plaintext).map_err(|_| STATUS_INVALID)
+}
+
+/// 以配对码做 SPAKE2 交换并派生会话密钥。配对码不一致时双方得到不同的密钥，
+/// 中间人也只能在一次连接中验证一个猜测，无法拿交换的消息或密文离线穷举六位配对码
+async fn handshake(stream: &mut TcpStream, code: &str, initiator: bool) -> Result<ChaCha20Poly1305, String> {
+    let password = Password::new(code.trim().as_bytes());
+    let (sender, receiver) = (Identity::new(b"sender"), Identity::new(b"receiver"));
+    let (spake, outbound) = if initiator {
+        Spake2::<Ed25519Group>::start_a(&password, &sender, &receiver)
+    } else {
+        Spake2::<Ed25519Group>::start_b(&password, &sender, &receiver)
+    };
+    stream.write_all(&outbound).await.map_err(|e| e.to_string())?;
+    let mut inbound = [0u8; HANDSHAKE_LEN];
+    stream.read_exact(&mut inbound).await.map_err(|e| e.to_string())?;
+    let shared = spake.finish(&inbound).map_err(|_| "Invalid handshake".to_string())?;
+
+    let key = Sha256::new().chain_update(PROTOCOL).chain_update(&shared).finalize();
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+async fn write_frame(stream: &mut TcpStream, data: &[u8]) -> Result<(), String> {
+    stream.write_u32(data.len() as u32).await.map_err(|e| e.to_string())?;
+    stream.write_all(data).await.map_err(|e| e.to_string())
+}
+
+async fn read_frame(stream: &mut TcpStream) -> Result<Vec<u8>, String> {
+    let len = stream.read_u32().await.map_err(|e| e.to_string())? as usize;
+    if len > MAX_PAYLOAD {
+        return Err("Payload too large".to_string());
+    }
+    let mut data = vec![0u8; len];
+    stream.read_exact(&mut data).await.map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+/// 收到的条目使用新的 id 放入一个新文件夹，避免与本地条目冲突
+fn import(app: &AppHandle, payload: Payload) -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let folder_id = uuid::Uuid::new_v4().to_string();
+    let mut children = crate::sync::strip_secrets(&payload.items);
+    let count = reassign_ids(&mut children, &folder_id);
+    let folder = TreeItem {
+        id: folder_id.clone(),
+        name: format!("Received from {}", payload.from),
+        item_type: ItemType::Folder,
+        children,
+        parent_id: None,
+        content: None,
+        versions: None,
+        metadata: crate::models::ItemMetadata {
+            last_modified: Some(now),
+            ..Default::default()
+        },
+    };
+    app.state::<Store>().upsert_item(folder)?;
+    let _ = app.emit(
+        EVENT_RECEIVED,
+        LanReceivedEvent {
+            from: payload.from,
+            folder_id,
+            count,
+        },
+    );
+    Ok(())
+}
+
+/// 重新生成 id 并修正父节点，返回条目数
+fn reassign_ids(items: &mut [TreeItem], parent_id: &str) -> usize {
+    let mut count = 0;
+    for item in items {
+        item.id = uuid::Uuid::new_v4().to_string();
+        item.parent_id = Some(parent_id.to_string());
+        count += 1 + reassign_ids(&mut item.children, &item.id.clone());
+    }
+    count
+}
+
+fn device_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "Prompt Manager".to_string())
+}
+
+/// mDNS 主机名只允许字母、数字与连字符
+fn host_label(name: &str) -> String {
+    let label: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let label = label.trim_matches('-');
+    if label.is_empty() {
+        "prompt-manager".to_string()
+    } else {
+        label.to_string()
+    }
+}
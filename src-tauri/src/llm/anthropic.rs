@@ -0,0 +1,144 @@
+use super::{apply_endpoint, ChatRequest, LlmProvider, StreamLine};
+use crate::models::TokenUsage;
+use serde_json::{json, Value};
+
+/// Messages API 要求必须给出 max_tokens
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Anthropic Messages API
+pub struct Anthropic;
+
+impl Anthropic {
+    fn body(req: &ChatRequest, stream: bool) -> Value {
+        let mut body = json!({
+            "model": req.model,
+            "max_tokens": req.params.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            "messages": [{ "role": "user", "content": req.content }],
+            "stream": stream,
+        });
+        if let Some(system) = req.system_prompt() {
+            body["system"] = json!(system);
+        }
+        if let Some(t) = req.params.temperature {
+            body["temperature"] = json!(t);
+        }
+        if let Some(p) = req.params.top_p {
+            body["top_p"] = json!(p);
+        }
+        body
+    }
+
+    fn url(req: &ChatRequest) -> String {
+        format!("{}/messages", req.base_url.trim_end_matches('/'))
+    }
+}
+
+impl LlmProvider for Anthropic {
+    fn default_base_url(&self) -> &'static str {
+        "https://api.anthropic.com/v1"
+    }
+
+    fn auth_header(&self) -> Option<&'static str> {
+        Some("x-api-key")
+    }
+
+    fn fixed_headers(&self) -> &'static [(&'static str, &'static str)] {
+        &[("anthropic-version", "2023-06-01")]
+    }
+
+    fn max_temperature(&self) -> f64 {
+        1.0
+    }
+
+    fn chat(&self, client: &reqwest::Client, req: &ChatRequest) -> reqwest::RequestBuilder {
+        apply_endpoint(client.post(Self::url(req)).json(&Self::body(req, false)), req)
+    }
+
+    fn parse_chat(&self, value: &Value) -> Result<(String, Option<TokenUsage>), String> {
+        if let Some(message) = value["error"]["message"].as_str() {
+            return Err(message.to_string());
+        }
+        let content = value["content"]
+            .as_array()
+            .map(|blocks| {
+                blocks
+                    .iter()
+                    .filter_map(|b| b["text"].as_str())
+                    .collect::<Vec<_>>()
+                    .join("")
+            })
+            .unwrap_or_default();
+        Ok((content, self.parse_usage(&value["usage"])))
+    }
+
+    fn stream(&self, client: &reqwest::Client, req: &ChatRequest) -> reqwest::RequestBuilder {
+        apply_endpoint(client.post(Self::url(req)).json(&Self::body(req, true)), req)
+    }
+
+    fn parse_stream_line(&self, line: &str) -> Option<StreamLine> {
+        // 只处理 data 行，event 行中的类型信息在 data 的 type 字段里也有
+        let data = line.strip_prefix("data:")?.trim();
+        let value = serde_json::from_str::<Value>(data).ok()?;
+        let line = match value["type"].as_str()? {
+            "message_start" => StreamLine {
+                usage: self.parse_usage(&value["message"]["usage"]),
+                ..Default::default()
+            },
+            "content_block_delta" => StreamLine {
+                delta: value["delta"]["text"].as_str().map(|s| s.to_string()),
+                ..Default::default()
+            },
+            "message_delta" => StreamLine {
+                usage: self.parse_usage(&value["usage"]),
+                ..Default::default()
+            },
+            "message_stop" => StreamLine {
+                done: true,
+                ..Default::default()
+            },
+            "error" => StreamLine {
+                error: Some(
+                    value["error"]["message"]
+                        .as_str()
+                        .unwrap_or("Unknown error")
+                        .to_string(),
+                ),
+                ..Default::default()
+            },
+            _ => return None,
+        };
+        Some(line)
+    }
+
+    fn models_url(&self, base_url: &str) -> String {
+        format!("{}/models", base_url.trim_end_matches('/'))
+    }
+
+    fn parse_models(&self, value: &Value) -> Vec<String> {
+        let mut names: Vec<String> = value["data"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|m| m["id"].as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn parse_usage(&self, value: &Value) -> Option<TokenUsage> {
+        if !value.is_object() {
+            return None;
+        }
+        let prompt_tokens = value["input_tokens"].as_u64().unwrap_or(0) as u32;
+        let completion_tokens = value["output_tokens"].as_u64().unwrap_or(0) as u32;
+        Some(TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        })
+    }
+}
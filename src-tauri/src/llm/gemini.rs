@@ -0,0 +1,126 @@
+use super::{apply_endpoint, ChatRequest, LlmProvider, StreamLine};
+use crate::models::TokenUsage;
+use serde_json::{json, Value};
+
+/// Google Gemini（Generative Language API）
+pub struct Gemini;
+
+impl Gemini {
+    fn body(req: &ChatRequest) -> Value {
+        let mut body = json!({
+            "contents": [{ "role": "user", "parts": [{ "text": req.content }] }],
+        });
+        if let Some(system) = req.system_prompt() {
+            body["systemInstruction"] = json!({ "parts": [{ "text": system }] });
+        }
+        let mut config = json!({});
+        if let Some(t) = req.params.temperature {
+            config["temperature"] = json!(t);
+        }
+        if let Some(p) = req.params.top_p {
+            config["topP"] = json!(p);
+        }
+        if let Some(m) = req.params.max_tokens {
+            config["maxOutputTokens"] = json!(m);
+        }
+        body["generationConfig"] = config;
+        body
+    }
+
+    fn url(req: &ChatRequest, method: &str) -> String {
+        format!("{}/models/{}:{}", req.base_url.trim_end_matches('/'), req.model, method)
+    }
+
+    /// 拼接 candidates[0] 中所有 part 的文本
+    fn text(value: &Value) -> Option<String> {
+        let parts = value["candidates"][0]["content"]["parts"].as_array()?;
+        Some(parts.iter().filter_map(|p| p["text"].as_str()).collect())
+    }
+}
+
+impl LlmProvider for Gemini {
+    fn default_base_url(&self) -> &'static str {
+        "https://generativelanguage.googleapis.com/v1beta"
+    }
+
+    fn auth_header(&self) -> Option<&'static str> {
+        Some("x-goog-api-key")
+    }
+
+    fn chat(&self, client: &reqwest::Client, req: &ChatRequest) -> reqwest::RequestBuilder {
+        apply_endpoint(
+            client.post(Self::url(req, "generateContent")).json(&Self::body(req)),
+            req,
+        )
+    }
+
+    fn parse_chat(&self, value: &Value) -> Result<(String, Option<TokenUsage>), String> {
+        if let Some(message) = value["error"]["message"].as_str() {
+            return Err(message.to_string());
+        }
+        Ok((
+            Self::text(value).unwrap_or_default(),
+            self.parse_usage(&value["usageMetadata"]),
+        ))
+    }
+
+    fn stream(&self, client: &reqwest::Client, req: &ChatRequest) -> reqwest::RequestBuilder {
+        let url = Self::url(req, "streamGenerateContent");
+        apply_endpoint(client.post(url).query(&[("alt", "sse")]).json(&Self::body(req)), req)
+    }
+
+    fn parse_stream_line(&self, line: &str) -> Option<StreamLine> {
+        let data = line.strip_prefix("data:")?.trim();
+        let value = serde_json::from_str::<Value>(data).ok()?;
+        Some(StreamLine {
+            delta: Self::text(&value),
+            usage: self.parse_usage(&value["usageMetadata"]),
+            error: value["error"]["message"].as_str().map(|s| s.to_string()),
+            done: false,
+        })
+    }
+
+    fn models_url(&self, base_url: &str) -> String {
+        format!("{}/models", base_url.trim_end_matches('/'))
+    }
+
+    fn parse_models(&self, value: &Value) -> Vec<String> {
+        let mut names: Vec<String> = value["models"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    // 只保留支持 generateContent 的模型（排除 embedding 等）
+                    .filter(|m| {
+                        m["supportedGenerationMethods"]
+                            .as_array()
+                            .map(|methods| methods.iter().any(|x| x == "generateContent"))
+                            .unwrap_or(true)
+                    })
+                    .filter_map(|m| m["name"].as_str())
+                    .map(|name| name.trim_start_matches("models/").to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn parse_usage(&self, value: &Value) -> Option<TokenUsage> {
+        if !value.is_object() {
+            return None;
+        }
+        let prompt_tokens = value["promptTokenCount"].as_u64().unwrap_or(0) as u32;
+        let completion_tokens = value["candidatesTokenCount"].as_u64().unwrap_or(0) as u32;
+        let total_tokens = value["totalTokenCount"]
+            .as_u64()
+            .map(|t| t as u32)
+            .unwrap_or(prompt_tokens + completion_tokens);
+        Some(TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens,
+        })
+    }
+}
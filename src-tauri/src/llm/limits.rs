@@ -0,0 +1,100 @@
+use crate::errors::{Error, ErrorCode};
+use crate::models::RateLimit;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+
+/// 并发上限 + 请求间最小间隔
+pub struct ProviderLimiter {
+    pub(super) limit: RateLimit,
+    semaphore: Arc<Semaphore>,
+    next_slot: tokio::sync::Mutex<Instant>,
+}
+
+impl ProviderLimiter {
+    pub(super) fn new(limit: RateLimit) -> Self {
+        ProviderLimiter {
+            semaphore: Arc::new(Semaphore::new(limit.max_concurrent.max(1) as usize)),
+            next_slot: tokio::sync::Mutex::new(Instant::now()),
+            limit,
+        }
+    }
+
+    /// 等待并发名额与速率窗口；返回的许可在运行结束前保持持有
+    pub async fn acquire(&self) -> OwnedSemaphorePermit {
+        let permit = self
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("limiter semaphore closed");
+        if let Some(rpm) = self.limit.requests_per_minute.filter(|&r| r > 0) {
+            let interval = Duration::from_secs(60) / rpm;
+            let mut next_slot = self.next_slot.lock().await;
+            let now = Instant::now();
+            let wait = next_slot.saturating_duration_since(now);
+            *next_slot = now.max(*next_slot) + interval;
+            drop(next_slot);
+            tokio::time::sleep(wait).await;
+        }
+        permit
+    }
+}
+
+/// 第 attempt 次重试前的等待时间：指数退避，上限 30 秒，附带少量抖动
+fn backoff_delay(attempt: u32) -> Duration {
+    let base = 500u64.saturating_mul(1 << attempt.saturating_sub(1).min(6));
+    let jitter = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_millis() as u64 % 250)
+        .unwrap_or(0);
+    Duration::from_millis((base + jitter).min(30_000))
+}
+
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    value
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(|secs| Duration::from_secs(secs.min(60)))
+}
+
+/// 发送请求，遇到 429 / 5xx / 连接错误时按退避策略重试；等待期间被取消则返回 Ok(None)
+pub(super) async fn send_with_retry(
+    builder: reqwest::RequestBuilder,
+    max_retries: u32,
+    cancel: &Notify,
+    on_retry: &mut impl FnMut(u32, Duration, &str),
+) -> Result<Option<reqwest::Response>, Error> {
+    let mut attempt = 0;
+    loop {
+        let request = builder.try_clone().ok_or("Request cannot be retried")?;
+        let (reason, delay) = match request.send().await {
+            Ok(response) if response.status().is_success() => return Ok(Some(response)),
+            Ok(response) => {
+                let status = response.status();
+                let retryable = status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+                if !retryable || attempt >= max_retries {
+                    let text = response.text().await.unwrap_or_default();
+                    return Err(Error::new(ErrorCode::RequestFailed, vec![status.to_string(), text]));
+                }
+                (status.to_string(), retry_after(&response))
+            }
+            Err(e) => {
+                if !(e.is_connect() || e.is_timeout()) || attempt >= max_retries {
+                    return Err(e.to_string().into());
+                }
+                (e.to_string(), None)
+            }
+        };
+
+        attempt += 1;
+        let delay = delay.unwrap_or_else(|| backoff_delay(attempt));
+        on_retry(attempt, delay, &reason);
+        tokio::select! {
+            _ = cancel.notified() => return Ok(None),
+            _ = tokio::time::sleep(delay) => {}
+        }
+    }
+}
@@ -0,0 +1,758 @@
+use crate::errors::{Error, ErrorCode};
+use crate::history::RunHistory;
+use crate::locale;
+use crate::log_error;
+use crate::models::{
+    GenerationParams, ItemType, LlmChunkEvent, LlmDoneEvent, LlmRetryEvent, LocalEndpoint, ModelPricing, ProxyConfig,
+    RateLimit, RunRecord, TokenUsage, TreeItem,
+};
+use crate::pricing;
+use crate::store::Store;
+use crate::template::{self, TemplateSpec};
+use futures_util::future::join_all;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+mod anthropic;
+mod gemini;
+mod limits;
+mod ollama;
+mod openai;
+
+use limits::send_with_retry;
+pub use limits::ProviderLimiter;
+
+pub const EVENT_CHUNK: &str = "llm://chunk";
+pub const EVENT_DONE: &str = "llm://done";
+pub const EVENT_RETRY: &str = "llm://retry";
+
+/// Provider 类型，由 Provider 节点 metadata.provider 字段决定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProviderKind {
+    OpenAi,
+    Ollama,
+    Anthropic,
+    Gemini,
+}
+
+impl ProviderKind {
+    pub fn from_name(name: &str) -> Self {
+        let lower = name.to_lowercase();
+        if lower.contains("ollama") {
+            ProviderKind::Ollama
+        } else if lower.contains("anthropic") || lower.contains("claude") {
+            ProviderKind::Anthropic
+        } else if lower.contains("gemini") || lower.contains("google") {
+            ProviderKind::Gemini
+        } else {
+            ProviderKind::OpenAi
+        }
+    }
+
+    /// Provider 节点的类型：优先 metadata.provider，其次节点名称
+    pub fn of(provider: &TreeItem) -> Self {
+        Self::from_name(provider.metadata.provider.as_deref().unwrap_or(&provider.name))
+    }
+
+    /// 该类型对应的协议适配器
+    pub fn adapter(&self) -> &'static dyn LlmProvider {
+        match self {
+            ProviderKind::OpenAi => &openai::OpenAi,
+            ProviderKind::Ollama => &ollama::Ollama,
+            ProviderKind::Anthropic => &anthropic::Anthropic,
+            ProviderKind::Gemini => &gemini::Gemini,
+        }
+    }
+
+    pub fn default_base_url(&self) -> &'static str {
+        self.adapter().default_base_url()
+    }
+
+    /// 校验生成参数是否在该 Provider 允许的范围内
+    pub fn validate(&self, params: &GenerationParams) -> Result<(), Error> {
+        if let Some(t) = params.temperature {
+            let max = self.adapter().max_temperature();
+            if !(0.0..=max).contains(&t) {
+                return Err(Error::with(ErrorCode::TemperatureOutOfRange, max));
+            }
+        }
+        if let Some(p) = params.top_p {
+            if !(0.0..=1.0).contains(&p) {
+                return Err(ErrorCode::TopPOutOfRange.into());
+            }
+        }
+        if params.max_tokens == Some(0) {
+            return Err(ErrorCode::MaxTokensTooSmall.into());
+        }
+        Ok(())
+    }
+}
+
+/// Provider 协议适配器：负责构建请求与解析响应，收发、重试与限流由本模块统一处理
+pub trait LlmProvider: Send + Sync {
+    fn default_base_url(&self) -> &'static str;
+
+    /// 默认放置 API Key 的请求头，None 表示使用 Authorization: Bearer
+    fn auth_header(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// 每个请求都需要携带的固定请求头
+    fn fixed_headers(&self) -> &'static [(&'static str, &'static str)] {
+        &[]
+    }
+
+    fn max_temperature(&self) -> f64 {
+        2.0
+    }
+
+    /// 非流式对话请求
+    fn chat(&self, client: &reqwest::Client, req: &ChatRequest) -> reqwest::RequestBuilder;
+
+    /// 解析非流式对话响应，返回 (输出文本, 用量)
+    fn parse_chat(&self, value: &Value) -> Result<(String, Option<TokenUsage>), String>;
+
+    /// 流式对话请求
+    fn stream(&self, client: &reqwest::Client, req: &ChatRequest) -> reqwest::RequestBuilder;
+
+    /// 解析流式响应中的一行（SSE 的 data 行或 NDJSON 行）
+    fn parse_stream_line(&self, line: &str) -> Option<StreamLine>;
+
+    /// 模型列表接口地址
+    fn models_url(&self, base_url: &str) -> String;
+
+    fn parse_models(&self, value: &Value) -> Vec<String>;
+
+    /// 从响应中解析 token 用量
+    fn parse_usage(&self, value: &Value) -> Option<TokenUsage>;
+}
+
+/// 一次对话请求所需的全部参数（从 prompt 的模型配置解析而来）
+#[derive(Debug, Clone)]
+pub struct ChatRequest {
+    pub base_url: String,
+    pub api_key: Option<String>,
+    pub auth_header: Option<String>,
+    pub headers: HashMap<String, String>,
+    pub query: HashMap<String, String>,
+    pub model: String,
+    pub content: String,
+    /// prompt 的模板模式与声明的变量，渲染时使用
+    pub template: TemplateSpec,
+    pub pricing: Option<ModelPricing>,
+    /// 通过 Model 节点解析时对应的 Model id
+    pub model_id: Option<String>,
+    pub kind: ProviderKind,
+    pub params: GenerationParams,
+    /// Model / Provider 上单独配置的代理，None 时使用全局代理
+    pub proxy: Option<ProxyConfig>,
+    /// 限流分组：同一 Provider 的请求共享并发与速率限制
+    pub limit_key: String,
+    pub rate_limit: RateLimit,
+}
+
+impl ChatRequest {
+    /// 解析运行配置：指定了 model_id 时使用该 Model 及其所属 Provider 的配置，否则使用 prompt 自身的模型配置
+    pub fn resolve(store: &Store, item_id: &str, model_id: Option<&str>) -> Result<Self, Error> {
+        let item = store.get_item(item_id).ok_or(ErrorCode::ItemNotFound)?;
+        let mut request = match model_id {
+            Some(model_id) => {
+                let model = store.get_item(model_id).ok_or(ErrorCode::ModelNotFound)?;
+                let provider = store.get_parent(model_id);
+                Self::from_model(&item, &model, provider.as_ref())?
+            }
+            None => Self::from_item(&item)?,
+        };
+        request.content = store.expand_includes(&item)?;
+        Ok(request)
+    }
+
+    pub fn from_model(item: &TreeItem, model: &TreeItem, provider: Option<&TreeItem>) -> Result<Self, Error> {
+        if item.item_type != ItemType::Prompt {
+            return Err(ErrorCode::NotRunnable.into());
+        }
+        let mut request = Self::for_model(model, provider, item.content.as_deref().unwrap_or_default().to_string())?;
+        request.template = TemplateSpec::of(&item.metadata);
+        Ok(request)
+    }
+
+    /// 使用 Model 及其所属 Provider 的配置构建请求
+    pub fn for_model(model: &TreeItem, provider: Option<&TreeItem>, content: String) -> Result<Self, Error> {
+        if model.item_type != ItemType::Model {
+            return Err(ErrorCode::NotModel.into());
+        }
+        let model_name = model
+            .metadata
+            .model_name
+            .clone()
+            .filter(|m| !m.trim().is_empty())
+            .unwrap_or_else(|| model.name.clone());
+        // Model 上的连接配置优先，其次是所属 Provider
+        let provider_meta = provider.map(|p| &p.metadata);
+        let kind = provider.map(ProviderKind::of).unwrap_or(ProviderKind::OpenAi);
+        let base_url = model
+            .metadata
+            .base_url
+            .clone()
+            .or_else(|| provider_meta.and_then(|m| m.base_url.clone()))
+            .filter(|u| !u.trim().is_empty())
+            .unwrap_or_else(|| kind.default_base_url().to_string());
+        let api_key = model
+            .metadata
+            .api_key
+            .clone()
+            .or_else(|| provider_meta.and_then(|m| m.api_key.clone()))
+            .filter(|k| !k.is_empty());
+        let auth_header = model
+            .metadata
+            .auth_header
+            .clone()
+            .or_else(|| provider_meta.and_then(|m| m.auth_header.clone()));
+        // 请求头与查询参数按键合并，Model 上的同名项覆盖 Provider
+        let mut headers = provider_meta.and_then(|m| m.extra_headers.clone()).unwrap_or_default();
+        headers.extend(model.metadata.extra_headers.clone().unwrap_or_default());
+        let mut query = provider_meta.and_then(|m| m.query_params.clone()).unwrap_or_default();
+        query.extend(model.metadata.query_params.clone().unwrap_or_default());
+
+        Ok(ChatRequest {
+            api_key,
+            auth_header,
+            headers,
+            query,
+            pricing: pricing::resolve(&model_name, &model.metadata),
+            model: model_name,
+            content,
+            template: TemplateSpec::default(),
+            model_id: Some(model.id.clone()),
+            kind,
+            params: model.metadata.generation.clone().unwrap_or_default(),
+            proxy: model
+                .metadata
+                .proxy
+                .clone()
+                .or_else(|| provider_meta.and_then(|m| m.proxy.clone())),
+            limit_key: provider.map(|p| p.id.clone()).unwrap_or_else(|| base_url.clone()),
+            rate_limit: provider_meta.and_then(|m| m.rate_limit.clone()).unwrap_or_default(),
+            base_url,
+        })
+    }
+
+    pub fn from_item(item: &TreeItem) -> Result<Self, Error> {
+        if item.item_type != ItemType::Prompt {
+            return Err(ErrorCode::NotRunnable.into());
+        }
+        let model = item
+            .metadata
+            .model_name
+            .clone()
+            .filter(|m| !m.trim().is_empty())
+            .ok_or(ErrorCode::ModelNameMissing)?;
+        let kind = ProviderKind::from_name(item.metadata.provider.as_deref().unwrap_or_default());
+        let base_url = item
+            .metadata
+            .base_url
+            .clone()
+            .filter(|u| !u.trim().is_empty())
+            .unwrap_or_else(|| kind.default_base_url().to_string());
+
+        Ok(ChatRequest {
+            limit_key: base_url.clone(),
+            base_url,
+            api_key: item.metadata.api_key.clone().filter(|k| !k.is_empty()),
+            auth_header: item.metadata.auth_header.clone(),
+            headers: item.metadata.extra_headers.clone().unwrap_or_default(),
+            query: item.metadata.query_params.clone().unwrap_or_default(),
+            pricing: pricing::resolve(&model, &item.metadata),
+            model,
+            content: item.content.as_deref().unwrap_or_default().to_string(),
+            template: TemplateSpec::of(&item.metadata),
+            model_id: None,
+            kind,
+            params: GenerationParams::default(),
+            proxy: item.metadata.proxy.clone(),
+            rate_limit: RateLimit::default(),
+        })
+    }
+
+    pub fn system_prompt(&self) -> Option<&str> {
+        self.params.system_prompt.as_deref().filter(|s| !s.is_empty())
+    }
+
+    /// 在 Model 默认参数之上应用调用方的覆盖值，并按 Provider 的范围校验
+    pub fn apply_params(&mut self, overrides: Option<&GenerationParams>) -> Result<(), Error> {
+        if let Some(overrides) = overrides {
+            self.params = self.params.merged_with(overrides);
+        }
+        self.kind.validate(&self.params)
+    }
+
+    /// 用给定的变量值渲染 prompt 内容
+    pub fn render(&mut self, values: &HashMap<String, String>) -> Result<(), Error> {
+        self.content = template::render(&self.content, &self.template, values)?;
+        Ok(())
+    }
+}
+
+/// 流式调用的结果
+pub struct ChatOutcome {
+    pub content: String,
+    pub usage: Option<TokenUsage>,
+    pub cancelled: bool,
+}
+
+/// 持久化在 llm_config.json 中的全局网络配置
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct LlmConfig {
+    global_proxy: Option<ProxyConfig>,
+}
+
+/// LLM 相关的全局状态：共享的 HTTP 客户端与正在进行的运行
+pub struct LlmState {
+    /// 使用全局代理的共享客户端，代理变更时重建
+    client: Mutex<reqwest::Client>,
+    config: Mutex<LlmConfig>,
+    config_path: PathBuf,
+    pub runs: RunRegistry,
+    /// 每个 Provider 一个限流器
+    limiters: Mutex<HashMap<String, Arc<ProviderLimiter>>>,
+}
+
+impl LlmState {
+    pub fn new(dir: PathBuf) -> Self {
+        let config_path = dir.join("llm_config.json");
+        let config: LlmConfig = fs::read_to_string(&config_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        let client = build_client(config.global_proxy.as_ref()).unwrap_or_else(|e| {
+            log_error!("Invalid global proxy, falling back to direct connection: {}", e);
+            reqwest::Client::new()
+        });
+
+        LlmState {
+            client: Mutex::new(client),
+            config: Mutex::new(config),
+            config_path,
+            runs: RunRegistry::default(),
+            limiters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 获取 Provider 的限流器；配置变化时重建
+    pub fn limiter(&self, key: &str, limit: &RateLimit) -> Arc<ProviderLimiter> {
+        let mut limiters = self.limiters.lock().unwrap();
+        match limiters.get(key) {
+            Some(limiter) if limiter.limit == *limit => limiter.clone(),
+            _ => {
+                let limiter = Arc::new(ProviderLimiter::new(limit.clone()));
+                limiters.insert(key.to_string(), limiter.clone());
+                limiter
+            }
+        }
+    }
+
+    /// 获取 HTTP 客户端：指定了代理时单独构建，否则复用全局客户端
+    pub fn client(&self, proxy: Option<&ProxyConfig>) -> Result<reqwest::Client, String> {
+        match proxy {
+            Some(proxy) => build_client(Some(proxy)),
+            None => Ok(self.client.lock().map_err(|e| e.to_string())?.clone()),
+        }
+    }
+
+    pub fn global_proxy(&self) -> Option<ProxyConfig> {
+        self.config.lock().unwrap().global_proxy.clone()
+    }
+
+    pub fn set_global_proxy(&self, proxy: Option<ProxyConfig>) -> Result<(), String> {
+        // 先构建客户端，代理地址非法时不落盘
+        let client = build_client(proxy.as_ref())?;
+        let mut config = self.config.lock().map_err(|e| e.to_string())?;
+        config.global_proxy = proxy;
+        let content = serde_json::to_string_pretty(&*config).map_err(|e| e.to_string())?;
+        fs::write(&self.config_path, content).map_err(|e| e.to_string())?;
+        *self.client.lock().map_err(|e| e.to_string())? = client;
+        Ok(())
+    }
+}
+
+fn build_client(proxy: Option<&ProxyConfig>) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(config) = proxy.filter(|p| !p.url.trim().is_empty()) {
+        let mut proxy = reqwest::Proxy::all(config.url.trim()).map_err(|e| format!("Invalid proxy: {}", e))?;
+        if let Some(username) = config.username.as_deref().filter(|u| !u.is_empty()) {
+            proxy = proxy.basic_auth(username, config.password.as_deref().unwrap_or_default());
+        }
+        if let Some(no_proxy) = config.no_proxy.as_deref() {
+            proxy = proxy.no_proxy(reqwest::NoProxy::from_string(no_proxy));
+        }
+        builder = builder.proxy(proxy);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// 正在进行的运行，按 run id 索引，用于取消
+#[derive(Default)]
+pub struct RunRegistry {
+    runs: Mutex<HashMap<String, Arc<Notify>>>,
+}
+
+impl RunRegistry {
+    pub fn register(&self, run_id: &str) -> Arc<Notify> {
+        let notify = Arc::new(Notify::new());
+        self.runs.lock().unwrap().insert(run_id.to_string(), notify.clone());
+        notify
+    }
+
+    pub fn finish(&self, run_id: &str) {
+        self.runs.lock().unwrap().remove(run_id);
+    }
+
+    pub fn cancel(&self, run_id: &str) -> Result<(), Error> {
+        let runs = self.runs.lock().map_err(|e| e.to_string())?;
+        let notify = runs.get(run_id).ok_or(ErrorCode::RunNotFound)?;
+        // notify_one 会保留许可，即使运行尚未进入等待也不会丢失取消信号
+        notify.notify_one();
+        Ok(())
+    }
+}
+
+/// 流式响应中解析出的一行
+#[derive(Default)]
+pub struct StreamLine {
+    pub delta: Option<String>,
+    pub usage: Option<TokenUsage>,
+    pub error: Option<String>,
+    pub done: bool,
+}
+
+/// OpenAI 风格的消息列表（system + user），OpenAI 与 Ollama 共用
+fn chat_messages(req: &ChatRequest) -> Vec<Value> {
+    let mut messages = Vec::new();
+    if let Some(system) = req.system_prompt() {
+        messages.push(json!({ "role": "system", "content": system }));
+    }
+    messages.push(json!({ "role": "user", "content": req.content }));
+    messages
+}
+
+/// 附加鉴权、固定请求头、自定义请求头与查询参数，使 Azure OpenAI、OpenRouter 等网关可以复用同一个适配器
+fn with_endpoint(
+    mut builder: reqwest::RequestBuilder,
+    adapter: &dyn LlmProvider,
+    api_key: Option<&str>,
+    auth_header: Option<&str>,
+    headers: &HashMap<String, String>,
+    query: &HashMap<String, String>,
+) -> reqwest::RequestBuilder {
+    if let Some(key) = api_key {
+        let header = auth_header
+            .map(str::trim)
+            .filter(|h| !h.is_empty())
+            .or(adapter.auth_header());
+        builder = match header {
+            Some(name) => builder.header(name, key),
+            None => builder.bearer_auth(key),
+        };
+    }
+    for (name, value) in adapter.fixed_headers() {
+        builder = builder.header(*name, *value);
+    }
+    for (name, value) in headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    if !query.is_empty() {
+        builder = builder.query(query);
+    }
+    builder
+}
+
+fn apply_endpoint(builder: reqwest::RequestBuilder, req: &ChatRequest) -> reqwest::RequestBuilder {
+    with_endpoint(
+        builder,
+        req.kind.adapter(),
+        req.api_key.as_deref(),
+        req.auth_header.as_deref(),
+        &req.headers,
+        &req.query,
+    )
+}
+
+/// 按 Provider 类型发起流式对话并逐段回调增量文本
+pub async fn stream_chat(
+    client: &reqwest::Client,
+    req: &ChatRequest,
+    cancel: &Notify,
+    mut on_delta: impl FnMut(&str),
+    mut on_retry: impl FnMut(u32, Duration, &str),
+) -> Result<ChatOutcome, Error> {
+    let adapter = req.kind.adapter();
+    let builder = adapter.stream(client, req);
+
+    let mut outcome = ChatOutcome {
+        content: String::new(),
+        usage: None,
+        cancelled: false,
+    };
+    let Some(response) = send_with_retry(builder, req.rate_limit.max_retries, cancel, &mut on_retry).await? else {
+        outcome.cancelled = true;
+        return Ok(outcome);
+    };
+
+    let mut stream = response.bytes_stream();
+    // 按字节缓冲，避免多字节字符被拆在两个分片之间
+    let mut buffer: Vec<u8> = Vec::new();
+
+    loop {
+        let chunk = tokio::select! {
+            _ = cancel.notified() => {
+                outcome.cancelled = true;
+                return Ok(outcome);
+            }
+            chunk = stream.next() => chunk,
+        };
+        let Some(chunk) = chunk else { break };
+        let bytes = chunk.map_err(|e| e.to_string())?;
+        buffer.extend_from_slice(&bytes);
+
+        // 按行切分，保留末尾不完整的行
+        while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(parsed) = adapter.parse_stream_line(line.trim()) else {
+                continue;
+            };
+            if let Some(error) = parsed.error {
+                return Err(error.into());
+            }
+
+            if let Some(delta) = parsed.delta.filter(|d| !d.is_empty()) {
+                outcome.content.push_str(&delta);
+                on_delta(&delta);
+            }
+            if let Some(usage) = parsed.usage {
+                // 部分 Provider 分段上报用量（如 Anthropic 开始时报输入、结束时报输出）
+                outcome.usage = Some(match outcome.usage.take() {
+                    Some(previous) => previous.merge(usage),
+                    None => usage,
+                });
+            }
+            if parsed.done {
+                return Ok(outcome);
+            }
+        }
+    }
+
+    Ok(outcome)
+}
+
+/// 非流式对话，仍然遵循重试策略
+pub async fn chat(client: &reqwest::Client, req: &ChatRequest) -> Result<ChatOutcome, Error> {
+    let adapter = req.kind.adapter();
+    let cancel = Notify::new();
+    let mut ignore_retry = |_: u32, _: Duration, _: &str| {};
+    let response = send_with_retry(
+        adapter.chat(client, req),
+        req.rate_limit.max_retries,
+        &cancel,
+        &mut ignore_retry,
+    )
+    .await?
+    .ok_or(ErrorCode::Cancelled)?;
+    let value: Value = response.json().await.map_err(|e| e.to_string())?;
+    let (content, usage) = adapter.parse_chat(&value)?;
+    Ok(ChatOutcome {
+        content,
+        usage,
+        cancelled: false,
+    })
+}
+
+/// 查询 Provider 可用的模型列表
+pub async fn list_models(state: &LlmState, provider: &TreeItem) -> Result<Vec<String>, Error> {
+    if provider.item_type != ItemType::Provider {
+        return Err(ErrorCode::NotProvider.into());
+    }
+    let client = state.client(provider.metadata.proxy.as_ref())?;
+    let kind = ProviderKind::of(provider);
+    let base_url = provider
+        .metadata
+        .base_url
+        .clone()
+        .filter(|u| !u.trim().is_empty())
+        .unwrap_or_else(|| kind.default_base_url().to_string());
+    let base_url = base_url.trim_end_matches('/');
+
+    let adapter = kind.adapter();
+    let builder = with_endpoint(
+        client.get(adapter.models_url(base_url)),
+        adapter,
+        provider.metadata.api_key.as_deref().filter(|k| !k.is_empty()),
+        provider.metadata.auth_header.as_deref(),
+        &provider.metadata.extra_headers.clone().unwrap_or_default(),
+        &provider.metadata.query_params.clone().unwrap_or_default(),
+    );
+
+    let response = builder.send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(Error::new(ErrorCode::RequestFailed, vec![status.to_string(), text]));
+    }
+    let value: Value = response.json().await.map_err(|e| e.to_string())?;
+    Ok(adapter.parse_models(&value))
+}
+
+/// 常见本地推理服务的默认地址：(名称, Provider 类型, base url)
+const LOCAL_ENDPOINTS: &[(&str, &str, &str)] = &[
+    ("Ollama", "Ollama", "http://localhost:11434"),
+    ("LM Studio", "OpenAI", "http://localhost:1234/v1"),
+    ("llama.cpp", "OpenAI", "http://localhost:8080/v1"),
+    ("vLLM", "OpenAI", "http://localhost:8000/v1"),
+    ("Jan", "OpenAI", "http://localhost:1337/v1"),
+];
+
+/// 探测本机上正在运行的推理服务，返回可直接用于创建 Provider 节点的配置
+pub async fn detect_local_endpoints() -> Vec<LocalEndpoint> {
+    // 本地探测不走代理，且使用较短的超时
+    let Ok(client) = reqwest::Client::builder()
+        .no_proxy()
+        .timeout(Duration::from_millis(800))
+        .build()
+    else {
+        return Vec::new();
+    };
+
+    let probes = LOCAL_ENDPOINTS.iter().map(|&(name, provider, base_url)| {
+        let client = client.clone();
+        async move {
+            let adapter = ProviderKind::from_name(provider).adapter();
+            let response = client.get(adapter.models_url(base_url)).send().await.ok()?;
+            if !response.status().is_success() {
+                return None;
+            }
+            let value: Value = response.json().await.ok()?;
+            Some(LocalEndpoint {
+                name: name.to_string(),
+                provider: provider.to_string(),
+                base_url: base_url.to_string(),
+                models: adapter.parse_models(&value),
+            })
+        }
+    });
+    join_all(probes).await.into_iter().flatten().collect()
+}
+
+fn actual_cost(pricing: Option<ModelPricing>, usage: Option<&TokenUsage>) -> Option<f64> {
+    let (pricing, usage) = (pricing?, usage?);
+    Some(pricing.cost(usage.prompt_tokens as usize, usage.completion_tokens as usize))
+}
+
+/// 执行一次运行：把增量通过 `llm://chunk` 推给前端，结束时发送 `llm://done` 并写入运行历史
+pub async fn run(
+    app: AppHandle,
+    run_id: String,
+    item_id: String,
+    req: ChatRequest,
+    cancel: Arc<Notify>,
+) -> LlmDoneEvent {
+    let state = app.state::<LlmState>();
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    let started = Instant::now();
+
+    // 排队等待 Provider 的并发名额与速率窗口，排队期间也可以取消
+    let limiter = state.limiter(&req.limit_key, &req.rate_limit);
+    let permit = tokio::select! {
+        _ = cancel.notified() => None,
+        permit = limiter.acquire() => Some(permit),
+    };
+
+    let result = match (permit, state.client(req.proxy.as_ref())) {
+        (None, _) => Ok(ChatOutcome {
+            content: String::new(),
+            usage: None,
+            cancelled: true,
+        }),
+        (Some(_permit), Ok(client)) => {
+            stream_chat(
+                &client,
+                &req,
+                &cancel,
+                |delta| {
+                    let _ = app.emit(
+                        EVENT_CHUNK,
+                        LlmChunkEvent {
+                            run_id: run_id.clone(),
+                            model_id: req.model_id.clone(),
+                            delta: delta.to_string(),
+                        },
+                    );
+                },
+                |attempt, delay, reason| {
+                    let _ = app.emit(
+                        EVENT_RETRY,
+                        LlmRetryEvent {
+                            run_id: run_id.clone(),
+                            model_id: req.model_id.clone(),
+                            attempt,
+                            delay_ms: delay.as_millis() as u64,
+                            reason: reason.to_string(),
+                        },
+                    );
+                },
+            )
+            .await
+        }
+        (Some(_), Err(e)) => Err(e.into()),
+    };
+
+    let done = match result {
+        Ok(outcome) => LlmDoneEvent {
+            run_id: run_id.clone(),
+            model_id: req.model_id.clone(),
+            cost: actual_cost(req.pricing, outcome.usage.as_ref()),
+            content: outcome.content,
+            usage: outcome.usage,
+            cancelled: outcome.cancelled,
+            error: None,
+        },
+        Err(e) => LlmDoneEvent {
+            run_id: run_id.clone(),
+            model_id: req.model_id.clone(),
+            content: String::new(),
+            usage: None,
+            cancelled: false,
+            error: Some(e.message(locale::language())),
+            cost: None,
+        },
+    };
+    state.runs.finish(&run_id);
+
+    let record = RunRecord {
+        id: run_id,
+        item_id,
+        timestamp,
+        model: req.model,
+        parameters: serde_json::to_value(&req.params).ok(),
+        input: req.content,
+        response: done.content.clone(),
+        latency_ms: started.elapsed().as_millis() as u64,
+        usage: done.usage.clone(),
+        cost: done.cost,
+        cancelled: done.cancelled,
+        error: done.error.clone(),
+    };
+    let _ = app.emit(EVENT_DONE, done.clone());
+    if let Err(e) = app.state::<RunHistory>().record(record) {
+        log_error!("Failed to record run history: {}", e);
+    }
+    done
+}
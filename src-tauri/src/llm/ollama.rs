@@ -0,0 +1,93 @@
+use super::{apply_endpoint, chat_messages, ChatRequest, LlmProvider, StreamLine};
+use crate::models::TokenUsage;
+use serde_json::{json, Value};
+
+/// Ollama 原生接口（/api/chat，NDJSON 流），本地运行无需 API Key
+pub struct Ollama;
+
+impl Ollama {
+    fn body(req: &ChatRequest, stream: bool) -> Value {
+        let mut options = json!({});
+        if let Some(t) = req.params.temperature {
+            options["temperature"] = json!(t);
+        }
+        if let Some(p) = req.params.top_p {
+            options["top_p"] = json!(p);
+        }
+        if let Some(m) = req.params.max_tokens {
+            options["num_predict"] = json!(m);
+        }
+        json!({
+            "model": req.model,
+            "messages": chat_messages(req),
+            "stream": stream,
+            "options": options,
+        })
+    }
+
+    fn url(req: &ChatRequest) -> String {
+        format!("{}/api/chat", req.base_url.trim_end_matches('/'))
+    }
+}
+
+impl LlmProvider for Ollama {
+    fn default_base_url(&self) -> &'static str {
+        "http://localhost:11434"
+    }
+
+    fn chat(&self, client: &reqwest::Client, req: &ChatRequest) -> reqwest::RequestBuilder {
+        apply_endpoint(client.post(Self::url(req)).json(&Self::body(req, false)), req)
+    }
+
+    fn parse_chat(&self, value: &Value) -> Result<(String, Option<TokenUsage>), String> {
+        if let Some(error) = value["error"].as_str() {
+            return Err(error.to_string());
+        }
+        let content = value["message"]["content"].as_str().unwrap_or_default().to_string();
+        Ok((content, self.parse_usage(value)))
+    }
+
+    fn stream(&self, client: &reqwest::Client, req: &ChatRequest) -> reqwest::RequestBuilder {
+        apply_endpoint(client.post(Self::url(req)).json(&Self::body(req, true)), req)
+    }
+
+    fn parse_stream_line(&self, line: &str) -> Option<StreamLine> {
+        let value = serde_json::from_str::<Value>(line).ok()?;
+        let done = value["done"].as_bool().unwrap_or(false);
+        Some(StreamLine {
+            delta: value["message"]["content"].as_str().map(|s| s.to_string()),
+            usage: if done { self.parse_usage(&value) } else { None },
+            error: value["error"].as_str().map(|s| s.to_string()),
+            done,
+        })
+    }
+
+    fn models_url(&self, base_url: &str) -> String {
+        format!("{}/api/tags", base_url.trim_end_matches('/'))
+    }
+
+    fn parse_models(&self, value: &Value) -> Vec<String> {
+        let mut names: Vec<String> = value["models"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|m| m["name"].as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn parse_usage(&self, value: &Value) -> Option<TokenUsage> {
+        let prompt_tokens = value["prompt_eval_count"].as_u64()? as u32;
+        let completion_tokens = value["eval_count"].as_u64().unwrap_or(0) as u32;
+        Some(TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        })
+    }
+}
@@ -0,0 +1,111 @@
+use super::{apply_endpoint, chat_messages, ChatRequest, LlmProvider, StreamLine};
+use crate::models::TokenUsage;
+use serde_json::{json, Value};
+
+/// OpenAI 及各类 OpenAI 兼容接口（Azure OpenAI、OpenRouter、DeepSeek、本地推理服务等）
+pub struct OpenAi;
+
+impl OpenAi {
+    fn body(req: &ChatRequest, stream: bool) -> Value {
+        let mut body = json!({
+            "model": req.model,
+            "messages": chat_messages(req),
+            "stream": stream,
+        });
+        if stream {
+            body["stream_options"] = json!({ "include_usage": true });
+        }
+        if let Some(t) = req.params.temperature {
+            body["temperature"] = json!(t);
+        }
+        if let Some(p) = req.params.top_p {
+            body["top_p"] = json!(p);
+        }
+        if let Some(m) = req.params.max_tokens {
+            body["max_tokens"] = json!(m);
+        }
+        body
+    }
+
+    fn url(req: &ChatRequest) -> String {
+        format!("{}/chat/completions", req.base_url.trim_end_matches('/'))
+    }
+}
+
+impl LlmProvider for OpenAi {
+    fn default_base_url(&self) -> &'static str {
+        "https://api.openai.com/v1"
+    }
+
+    fn chat(&self, client: &reqwest::Client, req: &ChatRequest) -> reqwest::RequestBuilder {
+        apply_endpoint(client.post(Self::url(req)).json(&Self::body(req, false)), req)
+    }
+
+    fn parse_chat(&self, value: &Value) -> Result<(String, Option<TokenUsage>), String> {
+        if let Some(message) = value["error"]["message"].as_str() {
+            return Err(message.to_string());
+        }
+        let content = value["choices"][0]["message"]["content"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        Ok((content, self.parse_usage(&value["usage"])))
+    }
+
+    fn stream(&self, client: &reqwest::Client, req: &ChatRequest) -> reqwest::RequestBuilder {
+        apply_endpoint(client.post(Self::url(req)).json(&Self::body(req, true)), req)
+    }
+
+    fn parse_stream_line(&self, line: &str) -> Option<StreamLine> {
+        let data = line.strip_prefix("data:")?.trim();
+        if data == "[DONE]" {
+            return Some(StreamLine {
+                done: true,
+                ..Default::default()
+            });
+        }
+        let value = serde_json::from_str::<Value>(data).ok()?;
+        Some(StreamLine {
+            delta: value["choices"][0]["delta"]["content"].as_str().map(|s| s.to_string()),
+            usage: self.parse_usage(&value["usage"]),
+            error: value["error"]["message"].as_str().map(|s| s.to_string()),
+            done: false,
+        })
+    }
+
+    fn models_url(&self, base_url: &str) -> String {
+        format!("{}/models", base_url.trim_end_matches('/'))
+    }
+
+    fn parse_models(&self, value: &Value) -> Vec<String> {
+        let mut names: Vec<String> = value["data"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|m| m["id"].as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn parse_usage(&self, value: &Value) -> Option<TokenUsage> {
+        if !value.is_object() {
+            return None;
+        }
+        let prompt_tokens = value["prompt_tokens"].as_u64().unwrap_or(0) as u32;
+        let completion_tokens = value["completion_tokens"].as_u64().unwrap_or(0) as u32;
+        let total_tokens = value["total_tokens"]
+            .as_u64()
+            .map(|t| t as u32)
+            .unwrap_or(prompt_tokens + completion_tokens);
+        Some(TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens,
+        })
+    }
+}
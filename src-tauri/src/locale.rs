@@ -0,0 +1,55 @@
+use crate::errors::{Error, ErrorCode};
+use crate::models::Language;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::sync::Mutex;
+
+/// 界面语言与时区，由设置载入与修改时更新；命令行等没有设置的场合使用英文与系统时区
+static LANGUAGE: Mutex<Language> = Mutex::new(Language::En);
+static TIMEZONE: Mutex<Option<Tz>> = Mutex::new(None);
+
+pub fn language() -> Language {
+    LANGUAGE.lock().map(|l| *l).unwrap_or_default()
+}
+
+/// 设置当前语言与时区；timezone 为空时使用系统时区
+pub fn apply(language: Language, timezone: Option<&str>) {
+    if let Ok(mut current) = LANGUAGE.lock() {
+        *current = language;
+    }
+    if let Ok(mut current) = TIMEZONE.lock() {
+        *current = timezone.and_then(|tz| parse_timezone(tz).ok());
+    }
+}
+
+/// 解析 IANA 时区名，例如 `Asia/Shanghai`
+pub fn parse_timezone(name: &str) -> Result<Tz, Error> {
+    name.parse().map_err(|_| Error::with(ErrorCode::UnknownTimeZone, name))
+}
+
+/// 日期过滤的起点（毫秒时间戳）：`today` 为今天 0 点，`week` 为本周一 0 点，`month` 为本月 1 日 0 点，
+/// 均按用户所在时区计算；其他值（`any`）返回 None
+pub fn period_start(period: &str) -> Option<i64> {
+    match *TIMEZONE.lock().ok()? {
+        Some(tz) => start_of(Utc::now().with_timezone(&tz), period),
+        None => start_of(Local::now(), period),
+    }
+}
+
+fn start_of<T: TimeZone>(now: DateTime<T>, period: &str) -> Option<i64> {
+    let today = now.date_naive();
+    let date = match period {
+        "today" => today,
+        "week" => today - Duration::days(today.weekday().num_days_from_monday() as i64),
+        "month" => NaiveDate::from_ymd_opt(today.year(), today.month(), 1)?,
+        _ => return None,
+    };
+    let midnight = date.and_hms_opt(0, 0, 0)?;
+    // 夏令时切换当天 0 点可能不存在，此时退回到 UTC 的 0 点
+    Some(
+        now.timezone()
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map_or_else(|| midnight.and_utc().timestamp_millis(), |t| t.timestamp_millis()),
+    )
+}
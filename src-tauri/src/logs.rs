@@ -0,0 +1,33 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// 保留最近多少条日志
+const CAPACITY: usize = 500;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// 输出到 stderr，同时保留在内存中，导出诊断包时附上
+#[macro_export]
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        $crate::logs::record(format!($($arg)*))
+    };
+}
+
+pub fn record(message: String) {
+    eprintln!("{}", message);
+    if let Ok(mut recent) = RECENT.lock() {
+        if recent.len() == CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(format!("{} {}", chrono::Utc::now().to_rfc3339(), message));
+    }
+}
+
+/// 本次运行中最近的日志，最早的在前
+pub fn recent() -> Vec<String> {
+    RECENT
+        .lock()
+        .map(|recent| recent.iter().cloned().collect())
+        .unwrap_or_default()
+}
@@ -3,67 +3,1358 @@
     windows_subsystem = "windows"
 )]
 
+mod api;
+mod applock;
+mod audit;
+mod capture;
+mod cli;
+mod clipboard;
+mod diagnostics;
+mod drag;
+mod duplicates;
+mod encryption;
+mod errors;
+mod history;
+mod lan;
+mod llm;
+mod locale;
+mod logs;
+mod maintenance;
+mod mcp;
 mod models;
+mod mounts;
+mod perf;
+mod picker;
+mod pricing;
+mod secrets;
+mod sensitive;
+mod settings;
+mod startup;
+mod stats;
 mod store;
+mod sync;
+mod template;
+mod tokens;
+mod tray;
+mod tree;
+mod ui;
+mod usage;
+mod webhooks;
 
-use models::{SearchFilters, SearchResult, TreeItem};
+use api::ApiState;
+use applock::AppLock;
+use capture::CaptureState;
+use errors::{CommandError, Error, ErrorCode};
+use futures_util::future::join_all;
+use history::RunHistory;
+use lan::LanState;
+use llm::{ChatRequest, LlmState, ProviderKind};
+use maintenance::Maintenance;
+use models::{
+    ApiConfig, ApiTokenInfo, ApiTokenScope, AppLockStatus, AppSettings, AuditEntry, AuditFilters, CaptureConfig,
+    ChangeKind, ChildSort, ChildrenPage, ConflictChoice, CostEstimate, DuplicateGroup, EncryptionStatus,
+    GenerationParams, HistoryPruning, IntegrityIssue, ItemContent, ItemType, ItemUsage, LanPeer, LanSession,
+    LibraryStats, LightItem, LlmDoneEvent, LocalEndpoint, MaintenanceSummary, MountedLibrary, NewApiToken, PasteOptions,
+    PerfStats, PromptPreview, ProxyConfig, QuickMatch, RunRecord, SearchFilters, SearchResult, SecretFinding,
+    SettingsPatch, StartupConfig, StoreChange, SyncConfig, SyncConflict, SyncStatus, TemplateIssue, TemplateVariable,
+    TextStats, TokenCount, TrayMenuData, TreeItem, UiPrefs, VariableDecl, Webhook,
+};
+use picker::PickerState;
+use settings::SettingsState;
+use startup::StartupState;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use store::Store;
-use tauri::{Manager, State};
+use sync::SyncState;
+use tauri::{AppHandle, Emitter, Manager, RunEvent, State, WindowEvent};
+use tauri_plugin_autostart::MacosLauncher;
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_global_shortcut::ShortcutState;
+use template::TemplateSpec;
+use ui::UiState;
+use usage::UsageState;
+use webhooks::WebhookState;
 
 // --- Commands ---
 
+/// 敏感条目的内容以占位文本代替，用 reveal_content 读取原文
 #[tauri::command]
 fn get_items(store: State<Store>) -> Vec<TreeItem> {
-    store.get_all()
+    sensitive::redacted_all(store.get_tree())
 }
 
 #[tauri::command]
 fn get_item(id: String, store: State<Store>) -> Option<TreeItem> {
-    store.get_item(&id)
+    store.get_item(&id).map(sensitive::redacted)
 }
 
+/// 不含内容与历史版本的树，子节点只给出 id；内容用 get_item_content 按需读取
 #[tauri::command]
-fn add_item(parent_id: Option<String>, item: TreeItem, store: State<Store>) -> Result<TreeItem, String> {
-    store.add_item(parent_id, item)
+fn get_items_light(store: State<Store>) -> Vec<LightItem> {
+    store.get_items_light()
 }
 
+/// 未指定 limit 时每页的子节点数
+const CHILDREN_PAGE_SIZE: usize = 200;
+/// 查找重复内容时默认的相似度下限
+const DUPLICATE_THRESHOLD: f64 = 0.9;
+
+#[tauri::command]
+fn get_children_page(
+    parent_id: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    sort: Option<ChildSort>,
+    store: State<Store>,
+) -> Result<ChildrenPage, CommandError> {
+    Ok(store.get_children_page(
+        parent_id.as_deref(),
+        offset.unwrap_or(0),
+        limit.unwrap_or(CHILDREN_PAGE_SIZE),
+        sort.unwrap_or_default(),
+    )?)
+}
+
+#[tauri::command]
+fn get_item_content(id: String, store: State<Store>) -> Option<ItemContent> {
+    let mut content = store.get_item_content(&id)?;
+    if content.sensitive {
+        sensitive::redact_content(&mut content);
+    }
+    Some(content)
+}
+
+/// 读取敏感条目的原文；应用锁要求验证时需要主密码
+#[tauri::command]
+fn reveal_content(
+    id: String,
+    passphrase: Option<String>,
+    lock: State<AppLock>,
+    store: State<Store>,
+) -> Result<ItemContent, CommandError> {
+    lock.authorize_reveal(passphrase.as_deref())?;
+    store.get_item_content(&id).ok_or_else(|| ErrorCode::ItemNotFound.into())
+}
+
+/// 读取敏感条目原文的命令共用的检查
+fn authorize_sensitive(item: &TreeItem, passphrase: Option<&str>, lock: &AppLock) -> Result<(), Error> {
+    if sensitive::is_sensitive(item) {
+        lock.authorize_reveal(passphrase)?;
+    }
+    Ok(())
+}
+
+/// 检查 prompt 中的疑似密钥（API Key、Bearer token、私钥等）；ids 为空时检查整个库
+#[tauri::command]
+fn scan_secrets(ids: Option<Vec<String>>, store: State<Store>) -> Vec<SecretFinding> {
+    store.scan_secrets(ids.as_deref())
+}
+
+/// 库的统计：各类型数量、内容长度、标签分布、每周修改数与最长的 prompt
+#[tauri::command]
+fn get_library_stats(store: State<Store>) -> LibraryStats {
+    store.library_stats()
+}
+
+/// 字符数、词数、行数与估计的阅读时间；文件夹为其下全部 prompt 之和
+#[tauri::command]
+fn get_text_stats(id: String, store: State<Store>) -> Result<TextStats, CommandError> {
+    store.text_stats(&id).ok_or_else(|| ErrorCode::ItemNotFound.into())
+}
+
+/// 内容相同或相近的 prompt 分组，用于清理导入的重复条目；threshold 缺省为 0.9
 #[tauri::command]
-fn update_item(id: String, updates: TreeItem, store: State<Store>) -> Result<TreeItem, String> {
-    store.update_item(id, updates)
+fn find_duplicates(threshold: Option<f64>, store: State<Store>) -> Result<Vec<DuplicateGroup>, CommandError> {
+    Ok(store.find_duplicates(threshold.unwrap_or(DUPLICATE_THRESHOLD))?)
 }
 
+/// 检查 store.json 与条目树的结构问题；结果只含条目 id
 #[tauri::command]
-fn delete_item(id: String, store: State<Store>) -> Result<(), String> {
-    store.delete_item(id)
+fn check_integrity(store: State<Store>) -> Vec<IntegrityIssue> {
+    store.check_integrity()
+}
+
+/// 把版本、系统信息、最近的日志与完整性检查结果导出为 zip，附在问题反馈中；不含条目内容
+#[tauri::command]
+fn export_diagnostics(path: String, app: AppHandle) -> Result<(), CommandError> {
+    Ok(diagnostics::export(&app, &PathBuf::from(path))?)
+}
+
+/// 库的修改记录（新增、修改、删除、移动、导入等），最新的在前
+#[tauri::command]
+fn get_audit_log(filters: Option<AuditFilters>, store: State<Store>) -> Vec<AuditEntry> {
+    store.audit_log(&filters.unwrap_or_default())
+}
+
+/// 最近操作的耗时、条目数量与库文件大小，反馈卡顿时附上
+#[tauri::command]
+fn get_perf_stats(store: State<Store>) -> PerfStats {
+    store.perf_stats()
+}
+
+#[tauri::command]
+fn add_item(parent_id: Option<String>, item: TreeItem, store: State<Store>) -> Result<TreeItem, CommandError> {
+    Ok(store.add_item(parent_id, item).map(sensitive::redacted)?)
+}
+
+#[tauri::command]
+fn update_item(id: String, updates: TreeItem, store: State<Store>) -> Result<TreeItem, CommandError> {
+    Ok(store.update_item(id, updates).map(sensitive::redacted)?)
+}
+
+#[tauri::command]
+fn delete_item(id: String, store: State<Store>) -> Result<(), CommandError> {
+    Ok(store.delete_item(id)?)
 }
 
 #[tauri::command]
 fn search_items(query: String, filters: Option<SearchFilters>, store: State<Store>) -> Vec<SearchResult> {
-    store.search(query, filters)
+    let mut results = store.search(query, filters);
+    sensitive::redact_results(&mut results, &store.sensitive_ids());
+    results
+}
+
+/// 快速选择器的模糊搜索（名称、标签、路径），缺省返回 20 条
+#[tauri::command]
+fn quick_search(query: String, limit: Option<usize>, store: State<Store>) -> Vec<QuickMatch> {
+    store.quick_search(&query, limit.unwrap_or(20))
+}
+
+#[tauri::command]
+fn move_item(item_id: String, new_parent_id: Option<String>, store: State<Store>) -> Result<TreeItem, CommandError> {
+    Ok(store.move_item(item_id, new_parent_id).map(sensitive::redacted)?)
+}
+
+/// 列出 prompt 中的 `{{variable}}` 占位符（去重，附带出现位置）；敏感条目与 reveal_content 一样需要验证
+#[tauri::command]
+fn list_variables(
+    item_id: String,
+    passphrase: Option<String>,
+    lock: State<AppLock>,
+    store: State<Store>,
+) -> Result<Vec<TemplateVariable>, CommandError> {
+    let item = store.get_item(&item_id).ok_or(ErrorCode::ItemNotFound)?;
+    authorize_sensitive(&item, passphrase.as_deref(), &lock)?;
+    Ok(template::list_variables(item.content.as_deref().unwrap_or_default()))
+}
+
+/// 用给定的变量值渲染 prompt，返回最终文本；复制与运行共用这一逻辑
+#[tauri::command]
+fn render_prompt(
+    item_id: String,
+    values: HashMap<String, String>,
+    passphrase: Option<String>,
+    lock: State<AppLock>,
+    store: State<Store>,
+) -> Result<String, CommandError> {
+    let item = store.get_item(&item_id).ok_or(ErrorCode::ItemNotFound)?;
+    authorize_sensitive(&item, passphrase.as_deref(), &lock)?;
+    let content = store.expand_includes(&item)?;
+    Ok(template::render(&content, &TemplateSpec::of(&item.metadata), &values)?)
+}
+
+/// 检查模板中未声明 / 未使用的变量、不成对的花括号与失效的引用
+#[tauri::command]
+fn validate_template(item_id: String, store: State<Store>) -> Result<Vec<TemplateIssue>, CommandError> {
+    let item = store.get_item(&item_id).ok_or(ErrorCode::ItemNotFound)?;
+    Ok(store.validate_template(&item))
+}
+
+/// 列出直接或间接引用了该条目的 prompt
+#[tauri::command]
+fn get_dependents(id: String, store: State<Store>) -> Vec<TreeItem> {
+    sensitive::redacted_all(store.get_dependents(&id))
+}
+
+/// 用变量的示例值 / 默认值渲染预览，供编辑器实时显示模型实际看到的内容
+#[tauri::command]
+fn preview_prompt(
+    item_id: String,
+    passphrase: Option<String>,
+    lock: State<AppLock>,
+    store: State<Store>,
+) -> Result<PromptPreview, CommandError> {
+    let item = store.get_item(&item_id).ok_or(ErrorCode::ItemNotFound)?;
+    authorize_sensitive(&item, passphrase.as_deref(), &lock)?;
+    let content = store.expand_includes(&item)?;
+    Ok(template::preview(&content, &TemplateSpec::of(&item.metadata))?)
+}
+
+#[tauri::command]
+fn list_snippets(store: State<Store>) -> Vec<TreeItem> {
+    sensitive::redacted_all(store.list_snippets())
+}
+
+/// 将片段插入 prompt 的指定位置（按字符计，缺省追加到末尾）；
+/// `linked` 为 true 时插入 `{{> prompt:id}}` 引用而不是复制内容
+#[tauri::command]
+fn insert_snippet(
+    item_id: String,
+    snippet_id: String,
+    position: Option<usize>,
+    linked: Option<bool>,
+    store: State<Store>,
+) -> Result<TreeItem, CommandError> {
+    let mut item = store.get_item(&item_id).ok_or(ErrorCode::ItemNotFound)?;
+    let snippet = store.get_item(&snippet_id).ok_or(ErrorCode::SnippetNotFound)?;
+    if snippet.item_type != ItemType::Snippet {
+        return Err(ErrorCode::NotSnippet.into());
+    }
+    if !matches!(item.item_type, ItemType::Prompt | ItemType::Snippet) {
+        return Err(ErrorCode::NotInsertable.into());
+    }
+
+    let text = if linked.unwrap_or(false) {
+        format!("{{{{> prompt:{}}}}}", snippet.id)
+    } else {
+        snippet.content.as_deref().unwrap_or_default().to_string()
+    };
+    let mut content = item.content.as_deref().unwrap_or_default().to_string();
+    let offset = position
+        .and_then(|p| content.char_indices().nth(p).map(|(i, _)| i))
+        .unwrap_or(content.len());
+    content.insert_str(offset, &text);
+    item.content = Some(content.into());
+    Ok(store.update_item(item_id, item).map(sensitive::redacted)?)
+}
+
+/// 新增或更新 prompt 声明的变量（按名称匹配）
+#[tauri::command]
+fn set_variable(item_id: String, variable: VariableDecl, store: State<Store>) -> Result<TreeItem, CommandError> {
+    if variable.name.trim().is_empty() {
+        return Err(ErrorCode::EmptyVariableName.into());
+    }
+    template::check_declaration(&variable)?;
+    let mut item = store.get_item(&item_id).ok_or(ErrorCode::ItemNotFound)?;
+    let variables = item.metadata.variables.get_or_insert_with(Vec::new);
+    match variables.iter_mut().find(|v| v.name == variable.name) {
+        Some(existing) => *existing = variable,
+        None => variables.push(variable),
+    }
+    Ok(store.update_item(item_id, item).map(sensitive::redacted)?)
+}
+
+#[tauri::command]
+fn remove_variable(item_id: String, name: String, store: State<Store>) -> Result<TreeItem, CommandError> {
+    let mut item = store.get_item(&item_id).ok_or(ErrorCode::ItemNotFound)?;
+    let variables = item.metadata.variables.get_or_insert_with(Vec::new);
+    variables.retain(|v| v.name != name);
+    Ok(store.update_item(item_id, item).map(sensitive::redacted)?)
+}
+
+/// 开始一次流式运行，立即返回 run id；输出通过 `llm://chunk` / `llm://done` 事件推送
+#[tauri::command]
+fn run_prompt(
+    item_id: String,
+    model_id: Option<String>,
+    params: Option<GenerationParams>,
+    variables: Option<HashMap<String, String>>,
+    app: AppHandle,
+    store: State<Store>,
+    llm_state: State<LlmState>,
+) -> Result<String, CommandError> {
+    let mut request = ChatRequest::resolve(&store, &item_id, model_id.as_deref())?;
+    request.apply_params(params.as_ref())?;
+    request.render(&variables.unwrap_or_default())?;
+
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let cancel = llm_state.runs.register(&run_id);
+    tauri::async_runtime::spawn(llm::run(app, run_id.clone(), item_id, request, cancel));
+    Ok(run_id)
+}
+
+/// 用同一个 prompt 并发运行多个模型，每个模型有独立的 run id 与事件流，全部结束后按 model id 返回结果
+#[tauri::command]
+async fn run_comparison(
+    item_id: String,
+    model_ids: Vec<String>,
+    variables: Option<HashMap<String, String>>,
+    app: AppHandle,
+    store: State<'_, Store>,
+    llm_state: State<'_, LlmState>,
+) -> Result<HashMap<String, LlmDoneEvent>, CommandError> {
+    // 先解析全部模型，任何一个配置有误都不启动运行
+    let values = variables.unwrap_or_default();
+    let mut requests = Vec::new();
+    for model_id in &model_ids {
+        let mut request = ChatRequest::resolve(&store, &item_id, Some(model_id))?;
+        request.apply_params(None)?;
+        request.render(&values)?;
+        requests.push(request);
+    }
+
+    let runs = requests.into_iter().map(|request| {
+        let run_id = uuid::Uuid::new_v4().to_string();
+        let cancel = llm_state.runs.register(&run_id);
+        llm::run(app.clone(), run_id, item_id.clone(), request, cancel)
+    });
+    let results = join_all(runs).await;
+
+    Ok(model_ids.into_iter().zip(results).collect())
+}
+
+/// 设置 Model 的默认生成参数（按所属 Provider 的允许范围校验）
+#[tauri::command]
+fn set_model_params(model_id: String, params: GenerationParams, store: State<Store>) -> Result<TreeItem, CommandError> {
+    let model = store.get_item(&model_id).ok_or(ErrorCode::ModelNotFound)?;
+    if model.item_type != ItemType::Model {
+        return Err(ErrorCode::NotModel.into());
+    }
+    let kind = store
+        .get_parent(&model_id)
+        .map(|p| ProviderKind::of(&p))
+        .unwrap_or(ProviderKind::OpenAi);
+    kind.validate(&params)?;
+
+    let mut updates = model;
+    updates.metadata.generation = Some(params);
+    Ok(store.update_item(model_id, updates).map(sensitive::redacted)?)
+}
+
+#[tauri::command]
+fn get_global_proxy(llm_state: State<LlmState>) -> Option<ProxyConfig> {
+    llm_state.global_proxy()
+}
+
+/// 设置全局代理（传 null 关闭）；Provider / Model 上的代理配置优先于全局代理
+#[tauri::command]
+fn set_global_proxy(proxy: Option<ProxyConfig>, llm_state: State<LlmState>) -> Result<(), CommandError> {
+    Ok(llm_state.set_global_proxy(proxy)?)
+}
+
+#[tauri::command]
+fn cancel_run(run_id: String, llm_state: State<LlmState>) -> Result<(), CommandError> {
+    Ok(llm_state.runs.cancel(&run_id)?)
+}
+
+/// 统计 token 数：参数既可以是条目 id，也可以是直接传入的文本
+#[tauri::command]
+fn count_tokens(
+    item_id_or_text: String,
+    model: Option<String>,
+    store: State<Store>,
+) -> Result<TokenCount, CommandError> {
+    let item = store.get_item(&item_id_or_text);
+    let model = model
+        .or_else(|| item.as_ref().and_then(|i| i.metadata.model_name.clone()))
+        .filter(|m| !m.trim().is_empty())
+        .unwrap_or_else(|| "gpt-4o".to_string());
+    let text = match item {
+        Some(item) => item.content.as_deref().unwrap_or_default().to_string(),
+        None => item_id_or_text,
+    };
+    Ok(tokens::count(&text, &model)?)
+}
+
+#[tauri::command]
+fn get_run_history(item_id: String, history: State<RunHistory>) -> Vec<RunRecord> {
+    history.get(&item_id)
+}
+
+#[tauri::command]
+fn clear_run_history(item_id: String, history: State<RunHistory>) -> Result<(), CommandError> {
+    Ok(history.clear(&item_id)?)
+}
+
+#[tauri::command]
+fn get_history_pruning(history: State<RunHistory>) -> HistoryPruning {
+    history.pruning()
+}
+
+#[tauri::command]
+fn set_history_pruning(pruning: HistoryPruning, history: State<RunHistory>) -> Result<HistoryPruning, CommandError> {
+    Ok(history.set_pruning(pruning)?)
+}
+
+/// 从 Provider 的模型接口拉取模型列表，并同步为其下的 Model 子节点
+#[tauri::command]
+async fn refresh_models(
+    provider_id: String,
+    store: State<'_, Store>,
+    llm_state: State<'_, LlmState>,
+) -> Result<Vec<TreeItem>, CommandError> {
+    let provider = store.get_item(&provider_id).ok_or(ErrorCode::ProviderNotFound)?;
+    let model_names = llm::list_models(&llm_state, &provider).await?;
+    let models = store.sync_models(&provider_id, model_names)?;
+    Ok(sensitive::redacted_all(models))
+}
+
+/// 用一条简短的请求验证 Model 的连接配置，返回模型的回复
+#[tauri::command]
+async fn test_model(
+    model_id: String,
+    store: State<'_, Store>,
+    llm_state: State<'_, LlmState>,
+) -> Result<String, CommandError> {
+    let model = store.get_item(&model_id).ok_or(ErrorCode::ModelNotFound)?;
+    let provider = store.get_parent(&model_id);
+    let mut request = ChatRequest::for_model(&model, provider.as_ref(), "ping".to_string())?;
+    request.params.max_tokens = Some(16);
+
+    let client = llm_state.client(request.proxy.as_ref())?;
+    Ok(llm::chat(&client, &request).await?.content)
+}
+
+/// 探测 localhost 上的 Ollama / OpenAI 兼容推理服务
+#[tauri::command]
+async fn detect_local_providers() -> Vec<LocalEndpoint> {
+    llm::detect_local_endpoints().await
+}
+
+/// 按 Model 的价格估算运行一次 prompt 的费用
+#[tauri::command]
+fn estimate_cost(
+    item_id: String,
+    model_id: String,
+    expected_output_tokens: usize,
+    store: State<Store>,
+) -> Result<CostEstimate, CommandError> {
+    let request = ChatRequest::resolve(&store, &item_id, Some(&model_id))?;
+    let pricing = request
+        .pricing
+        .ok_or_else(|| Error::with(ErrorCode::PricingMissing, &request.model))?;
+    let input_tokens = tokens::count(&request.content, &request.model)?.tokens;
+
+    let input_cost = pricing.cost(input_tokens, 0);
+    let output_cost = pricing.cost(0, expected_output_tokens);
+    Ok(CostEstimate {
+        model: request.model,
+        input_tokens,
+        output_tokens: expected_output_tokens,
+        input_cost,
+        output_cost,
+        total_cost: input_cost + output_cost,
+    })
+}
+
+#[tauri::command]
+fn get_sync_config(sync: State<SyncState>) -> SyncConfig {
+    sync.config()
+}
+
+/// 保存同步配置，并把当前库导出到同步目标；库已加密时不能启用同步目标（它们保存明文副本）
+#[tauri::command]
+fn set_sync_config(
+    config: SyncConfig,
+    store: State<Store>,
+    sync: State<SyncState>,
+) -> Result<SyncConfig, CommandError> {
+    if store.encryption_status().enabled && sync::has_targets(&config) {
+        return Err(ErrorCode::SyncWhileEncrypted.into());
+    }
+    let config = sync.set_config(config)?;
+    sync.queue(&store, "Export library".to_string());
+    sync.start_folder_sync(&store)?;
+    Ok(config)
+}
+
+/// 从 git 远端拉取并合并，返回合并后的条目树
+#[tauri::command]
+async fn sync_pull(app: AppHandle) -> Result<Vec<TreeItem>, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let store = app.state::<Store>();
+        app.state::<SyncState>().pull(&store)?;
+        Ok(sensitive::redacted_all(store.get_tree()))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 立即与全部启用的同步目标同步（git 先拉取再推送，WebDAV 双向同步），返回同步后的条目树
+#[tauri::command]
+async fn sync_now(
+    app: AppHandle,
+    store: State<'_, Store>,
+    sync: State<'_, SyncState>,
+) -> Result<Vec<TreeItem>, CommandError> {
+    let git_enabled = sync.config().git.is_some_and(|g| g.enabled);
+    if git_enabled {
+        let handle = app.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let store = handle.state::<Store>();
+            let sync = handle.state::<SyncState>();
+            sync.pull(&store)?;
+            sync.push(&store)
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+    }
+    if sync.config().webdav.is_some_and(|w| w.enabled) {
+        sync.webdav_sync(&store).await?;
+    }
+    Ok(sensitive::redacted_all(store.get_tree()))
+}
+
+/// 同步中两侧都修改、未能自动合并的条目
+#[tauri::command]
+fn list_conflicts(sync: State<SyncState>) -> Vec<SyncConflict> {
+    sync.conflicts()
+}
+
+#[tauri::command]
+fn resolve_conflict(
+    id: String,
+    choice: ConflictChoice,
+    content: Option<String>,
+    store: State<Store>,
+    sync: State<SyncState>,
+) -> Result<(), CommandError> {
+    Ok(sync.resolve_conflict(&store, &id, choice, content)?)
+}
+
+#[tauri::command]
+async fn sync_push(app: AppHandle) -> Result<(), CommandError> {
+    tauri::async_runtime::spawn_blocking(move || app.state::<SyncState>().push(&app.state::<Store>()))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(CommandError::from)
+}
+
+/// 开始在局域网中接收其他设备发送的条目，返回需要告诉发送方的配对码
+#[tauri::command]
+async fn start_receive(lan: State<'_, LanState>) -> Result<LanSession, CommandError> {
+    Ok(lan.start_receive().await?)
+}
+
+#[tauri::command]
+fn stop_receive(lan: State<LanState>) {
+    lan.stop_receive();
+}
+
+#[tauri::command]
+fn get_receive_session(lan: State<LanState>) -> Option<LanSession> {
+    lan.session()
+}
+
+/// 查找局域网中正在接收的设备（约 2 秒）
+#[tauri::command]
+async fn discover_peers(app: AppHandle) -> Result<Vec<LanPeer>, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || app.state::<LanState>().discover(std::time::Duration::from_secs(2)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(CommandError::from)
+}
+
+/// 把条目（含子节点）加密发送给另一台设备
+#[tauri::command]
+async fn send_items(
+    ids: Vec<String>,
+    peer: LanPeer,
+    code: String,
+    store: State<'_, Store>,
+) -> Result<(), CommandError> {
+    let items = ids
+        .iter()
+        .map(|id| store.get_item(id).ok_or(ErrorCode::ItemNotFound))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(lan::send_items(items, &peer, &code).await?)
+}
+
+#[tauri::command]
+fn list_mounts(store: State<Store>) -> Vec<MountedLibrary> {
+    store.list_mounts()
+}
+
+/// 以只读方式挂载共享库（Markdown 目录或库 JSON 文件），返回挂载后的根节点
+#[tauri::command]
+fn add_mount(name: String, path: String, store: State<Store>) -> Result<TreeItem, CommandError> {
+    Ok(store.add_mount(name, path).map(sensitive::redacted)?)
+}
+
+#[tauri::command]
+fn remove_mount(id: String, store: State<Store>) -> Result<(), CommandError> {
+    Ok(store.remove_mount(&id)?)
+}
+
+/// 重新读取全部挂载库，返回完整的条目树
+#[tauri::command]
+fn refresh_mounts(store: State<Store>) -> Vec<TreeItem> {
+    sensitive::redacted_all(store.refresh_mounts())
+}
+
+#[tauri::command]
+fn list_webhooks(hooks: State<WebhookState>) -> Vec<Webhook> {
+    hooks.list()
+}
+
+/// 新增（id 为空时）或更新 webhook
+#[tauri::command]
+fn save_webhook(hook: Webhook, hooks: State<WebhookState>) -> Result<Webhook, CommandError> {
+    Ok(hooks.upsert(hook)?)
+}
+
+#[tauri::command]
+fn delete_webhook(id: String, hooks: State<WebhookState>) -> Result<(), CommandError> {
+    Ok(hooks.remove(&id)?)
+}
+
+/// 发送一条测试事件，返回 HTTP 状态码
+#[tauri::command]
+async fn test_webhook(id: String, hooks: State<'_, WebhookState>) -> Result<u16, CommandError> {
+    Ok(hooks.test(&id).await?)
+}
+
+/// 各同步目标的待同步条目数、上次同步时间与最近的错误
+#[tauri::command]
+fn sync_status(sync: State<SyncState>) -> SyncStatus {
+    sync.status()
+}
+
+#[tauri::command]
+fn get_picker_shortcut(picker: State<PickerState>) -> String {
+    picker.shortcut()
+}
+
+/// 更换打开快速选择器的全局快捷键，例如 `CommandOrControl+Shift+P`
+#[tauri::command]
+fn set_picker_shortcut(shortcut: String, app: AppHandle, picker: State<PickerState>) -> Result<String, CommandError> {
+    Ok(picker.set_shortcut(&app, shortcut)?)
+}
+
+#[tauri::command]
+fn toggle_picker(app: AppHandle) -> Result<(), CommandError> {
+    Ok(picker::toggle(&app)?)
+}
+
+/// 在快速选择器中选中 prompt：复制、关闭选择器，并按设置粘贴到之前的窗口
+#[tauri::command]
+fn picker_select(item_id: String, values: Option<HashMap<String, String>>, app: AppHandle) -> Result<(), CommandError> {
+    Ok(picker::select(&app, &item_id, &values.unwrap_or_default())?)
+}
+
+#[tauri::command]
+fn get_paste_options(picker: State<PickerState>) -> PasteOptions {
+    picker.paste_options()
+}
+
+#[tauri::command]
+fn set_paste_options(options: PasteOptions, picker: State<PickerState>) -> Result<PasteOptions, CommandError> {
+    Ok(picker.set_paste_options(options)?)
+}
+
+/// 托盘菜单的内容（置顶、收藏、最近使用）
+#[tauri::command]
+fn get_tray_menu(store: State<Store>, usage: State<UsageState>) -> TrayMenuData {
+    tray::menu_data(&store, &usage)
+}
+
+/// 渲染 prompt 后复制到剪贴板并记录使用次数，返回复制的文本
+#[tauri::command]
+fn copy_prompt(
+    item_id: String,
+    values: HashMap<String, String>,
+    notify: Option<bool>,
+    app: AppHandle,
+) -> Result<String, CommandError> {
+    Ok(clipboard::copy_prompt(&app, &item_id, &values, notify.unwrap_or(false))?)
+}
+
+#[tauri::command]
+fn get_usage(item_id: String, usage: State<UsageState>) -> ItemUsage {
+    usage.get(&item_id)
+}
+
+/// 把渲染后的 prompt 写入临时文件并返回路径，供拖放到其他应用；`format` 为 `md`（缺省）或 `txt`
+#[tauri::command]
+fn prepare_drag_file(
+    item_id: String,
+    values: Option<HashMap<String, String>>,
+    format: Option<String>,
+    store: State<Store>,
+) -> Result<String, CommandError> {
+    let path = drag::prepare_file(
+        &store,
+        &item_id,
+        &values.unwrap_or_default(),
+        format.as_deref().unwrap_or("md"),
+    )?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// 与点击托盘菜单项相同：渲染后复制到剪贴板
+#[tauri::command]
+fn tray_select(item_id: String, app: AppHandle) -> Result<(), CommandError> {
+    Ok(tray::select(&app, &item_id)?)
+}
+
+#[tauri::command]
+fn get_capture_config(capture: State<CaptureState>) -> CaptureConfig {
+    capture.config()
+}
+
+/// 开关剪贴板收集、设置收集快捷键与收件箱文件夹
+#[tauri::command]
+fn set_capture_config(
+    config: CaptureConfig,
+    app: AppHandle,
+    capture: State<CaptureState>,
+) -> Result<CaptureConfig, CommandError> {
+    Ok(capture.set_config(&app, config)?)
+}
+
+/// 把当前剪贴板内容直接存入收件箱
+#[tauri::command]
+fn capture_clipboard(app: AppHandle, capture: State<CaptureState>) -> Result<TreeItem, CommandError> {
+    Ok(capture.capture_clipboard(&app).map(sensitive::redacted)?)
+}
+
+#[tauri::command]
+fn get_api_config(api: State<ApiState>) -> ApiConfig {
+    api.config()
+}
+
+/// 开关本地 REST API 或更换端口；端口被占用时返回错误
+#[tauri::command]
+async fn set_api_config(config: ApiConfig, api: State<'_, ApiState>) -> Result<ApiConfig, CommandError> {
+    Ok(api.set_config(config).await?)
+}
+
+/// 本地 REST API 正在运行时的地址
+#[tauri::command]
+fn get_api_url(api: State<ApiState>) -> Option<String> {
+    api.url()
+}
+
+#[tauri::command]
+fn list_api_tokens(api: State<ApiState>) -> Vec<ApiTokenInfo> {
+    api.tokens()
+}
+
+/// 生成本地 API 令牌；返回的令牌只显示这一次
+#[tauri::command]
+fn create_api_token(name: String, scope: ApiTokenScope, api: State<ApiState>) -> Result<NewApiToken, CommandError> {
+    Ok(api.create_token(&name, scope)?)
+}
+
+#[tauri::command]
+fn revoke_api_token(id: String, api: State<ApiState>) -> Result<(), CommandError> {
+    Ok(api.revoke_token(&id)?)
 }
 
 #[tauri::command]
-fn move_item(item_id: String, new_parent_id: Option<String>, store: State<Store>) -> Result<TreeItem, String> {
-    store.move_item(item_id, new_parent_id)
+fn get_startup_config(app: AppHandle, startup: State<StartupState>) -> StartupConfig {
+    startup.config(&app)
+}
+
+/// 开关开机启动（注册到系统）与启动时最小化到托盘
+#[tauri::command]
+fn set_startup_config(
+    config: StartupConfig,
+    app: AppHandle,
+    startup: State<StartupState>,
+) -> Result<StartupConfig, CommandError> {
+    Ok(startup.set_config(&app, config)?)
+}
+
+#[tauri::command]
+fn get_settings(settings: State<SettingsState>) -> AppSettings {
+    settings.settings()
+}
+
+/// 立即运行一次后台维护（自动备份、清理自动保存的版本），结果同时以事件发出
+#[tauri::command]
+async fn run_maintenance(app: AppHandle) -> Result<MaintenanceSummary, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || app.state::<Maintenance>().run(&app))
+        .await
+        .map_err(|e| CommandError::from(e.to_string()))
+}
+
+/// 上次保存的界面状态（展开的文件夹、选中的条目等）
+#[tauri::command]
+fn get_ui_prefs(ui: State<UiState>) -> UiPrefs {
+    ui.prefs()
+}
+
+#[tauri::command]
+fn set_expanded_items(ids: Vec<String>, ui: State<UiState>) -> Result<(), CommandError> {
+    Ok(ui.set_expanded(ids)?)
+}
+
+#[tauri::command]
+fn set_selected_item(id: Option<String>, ui: State<UiState>) -> Result<(), CommandError> {
+    Ok(ui.set_selected(id)?)
+}
+
+/// 修改部分设置；值无效时返回错误且不保存
+#[tauri::command]
+fn update_settings(
+    patch: SettingsPatch,
+    app: AppHandle,
+    settings: State<SettingsState>,
+) -> Result<AppSettings, CommandError> {
+    let settings = settings.update(patch)?;
+    let _ = app.emit(settings::EVENT_CHANGED, &settings);
+    Ok(settings)
+}
+
+#[tauri::command]
+fn set_favorite(id: String, favorite: bool, store: State<Store>) -> Result<TreeItem, CommandError> {
+    let item = store.set_flags(&id, Some(favorite), None, None)?;
+    Ok(sensitive::redacted(item))
+}
+
+#[tauri::command]
+fn set_pinned(id: String, pinned: bool, store: State<Store>) -> Result<TreeItem, CommandError> {
+    let item = store.set_flags(&id, None, Some(pinned), None)?;
+    Ok(sensitive::redacted(item))
+}
+
+/// 标记为敏感后列表与搜索结果中不再显示内容
+#[tauri::command]
+fn set_sensitive(id: String, sensitive: bool, store: State<Store>) -> Result<TreeItem, CommandError> {
+    let item = store.set_flags(&id, None, None, Some(sensitive))?;
+    Ok(sensitive::redacted(item))
+}
+
+#[tauri::command]
+fn get_encryption_status(store: State<Store>) -> EncryptionStatus {
+    store.encryption_status()
+}
+
+/// 用口令解密加密的 store.json，之后通知各窗口重新读取
+#[tauri::command]
+fn unlock_store(passphrase: String, app: AppHandle, store: State<Store>) -> Result<(), CommandError> {
+    if !store.is_locked() {
+        return Ok(());
+    }
+    store.unlock(&passphrase)?;
+    store_unlocked(&app);
+    Ok(())
+}
+
+fn store_unlocked(app: &AppHandle) {
+    let change = StoreChange {
+        kind: ChangeKind::Replaced,
+        ids: Vec::new(),
+        summary: "Unlock store".to_string(),
+    };
+    let _ = app.emit(store::EVENT_CHANGED, &change);
+    tray::refresh(app);
+}
+
+/// 启用加密；要求先关闭同步目标，见 [`SyncState::prepare_encryption`]。
+/// 运行历史随之清空并暂停记录；修改记录（audit.jsonl）不加密，其中的摘要含条目名称
+#[tauri::command]
+fn enable_encryption(
+    passphrase: String,
+    store: State<Store>,
+    sync: State<SyncState>,
+    history: State<RunHistory>,
+) -> Result<(), CommandError> {
+    sync.prepare_encryption()?;
+    store.enable_encryption(&passphrase)?;
+    Ok(history.set_paused(true)?)
+}
+
+#[tauri::command]
+fn change_passphrase(current: String, new: String, store: State<Store>) -> Result<(), CommandError> {
+    Ok(store.change_passphrase(&current, &new)?)
+}
+
+#[tauri::command]
+fn disable_encryption(passphrase: String, store: State<Store>, history: State<RunHistory>) -> Result<(), CommandError> {
+    store.disable_encryption(&passphrase)?;
+    Ok(history.set_paused(false)?)
+}
+
+/// 备份 store.json（启用加密时备份也是加密的），缺省保存到数据目录的 backups 中；返回备份文件路径
+#[tauri::command]
+fn backup_store(path: Option<String>, app: AppHandle, store: State<Store>) -> Result<String, CommandError> {
+    let destination = match path {
+        Some(path) => PathBuf::from(path),
+        None => store::data_dir(&app)
+            .join("backups")
+            .join(format!("store-{}.json", chrono::Local::now().format("%Y%m%d-%H%M%S"))),
+    };
+    store.backup(&destination)?;
+    Ok(destination.to_string_lossy().to_string())
+}
+
+// --- App lock ---
+
+#[tauri::command]
+fn get_app_lock_status(lock: State<AppLock>) -> AppLockStatus {
+    lock.status()
+}
+
+/// 输入主密码解锁；加密的 store.json 使用同一口令时一并解锁
+#[tauri::command]
+fn unlock_app(
+    passphrase: String,
+    app: AppHandle,
+    lock: State<AppLock>,
+    store: State<Store>,
+) -> Result<(), CommandError> {
+    lock.unlock(&passphrase)?;
+    if store.is_locked() && store.unlock(&passphrase).is_ok() {
+        store_unlocked(&app);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn lock_app(lock: State<AppLock>) -> Result<(), CommandError> {
+    Ok(lock.lock()?)
+}
+
+/// 设置或更换主密码（启用应用锁）；已启用时需要当前密码
+#[tauri::command]
+fn set_app_lock_passphrase(
+    current: Option<String>,
+    passphrase: String,
+    lock: State<AppLock>,
+) -> Result<AppLockStatus, CommandError> {
+    Ok(lock.set_passphrase(current.as_deref(), &passphrase)?)
+}
+
+#[tauri::command]
+fn disable_app_lock(passphrase: String, lock: State<AppLock>) -> Result<AppLockStatus, CommandError> {
+    Ok(lock.disable(&passphrase)?)
+}
+
+/// 显示敏感条目的内容前是否要求输入主密码
+#[tauri::command]
+fn set_app_lock_guard_sensitive(guard: bool, lock: State<AppLock>) -> Result<AppLockStatus, CommandError> {
+    Ok(lock.set_guard_sensitive(guard)?)
+}
+
+/// 无操作多少分钟后自动锁定，为空时不自动锁定
+#[tauri::command]
+fn set_app_lock_timeout(minutes: Option<u32>, lock: State<AppLock>) -> Result<AppLockStatus, CommandError> {
+    Ok(lock.set_idle_timeout(minutes)?)
+}
+
+// --- Deep links ---
+
+const DEEP_LINK_SCHEME: &str = "prompt-manager://";
+
+/// 通过链接打开条目时发出，内容为条目 id，前端据此选中该条目
+const EVENT_DEEP_LINK_OPEN: &str = "deep-link://open";
+
+/// 处理 `prompt-manager://` 链接：
+/// `open/{id}` 打开条目，`copy/{id}?变量=值` 渲染后复制，`new?name=...&content=...` 新建 prompt
+fn handle_deep_link(app: &AppHandle, url: &tauri::Url) -> Result<(), Error> {
+    if app.state::<AppLock>().is_locked() {
+        tray::show_main(app);
+        return Err(ErrorCode::AppLocked.into());
+    }
+    let action = url.host_str().unwrap_or_default();
+    let id = url.path().trim_matches('/');
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    match action {
+        "open" => open_item(app, id),
+        "copy" => {
+            if let Err(e) = clipboard::copy_prompt(app, id, &query, true) {
+                // 缺少变量值时打开主窗口让用户填写
+                open_item(app, id)?;
+                return Err(e);
+            }
+            Ok(())
+        }
+        "new" => {
+            let name = query.get("name").map(|n| n.trim()).filter(|n| !n.is_empty());
+            let item = TreeItem {
+                id: String::new(),
+                name: name.unwrap_or("Untitled prompt").to_string(),
+                item_type: ItemType::Prompt,
+                children: Vec::new(),
+                parent_id: None,
+                content: Some(query.get("content").map_or("", String::as_str).into()),
+                versions: None,
+                metadata: Default::default(),
+            };
+            let item = app.state::<Store>().add_item(None, item)?;
+            open_item(app, &item.id)
+        }
+        _ => Err(Error::with(ErrorCode::UnsupportedLink, url)),
+    }
+}
+
+fn open_item(app: &AppHandle, id: &str) -> Result<(), Error> {
+    if app.state::<Store>().get_item(id).is_none() {
+        return Err(ErrorCode::ItemNotFound.into());
+    }
+    tray::show_main(app);
+    Ok(app.emit(EVENT_DEEP_LINK_OPEN, id).map_err(|e| e.to_string())?)
 }
 
 fn main() {
+    // 带子命令启动时作为命令行工具运行，不打开窗口
+    if let Some(code) = cli::run_from_args() {
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
+        // 必须最先注册：再次启动时把参数（deep link 由插件转交给 on_open_url）交给已运行的实例后退出
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            let is_link = args.iter().any(|arg| arg.starts_with(DEEP_LINK_SCHEME));
+            if !is_link && !args.iter().any(|arg| arg == startup::MINIMIZED_ARG) {
+                tray::show_main(app);
+            }
+        }))
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_autostart::init(
+            MacosLauncher::LaunchAgent,
+            Some(vec![startup::MINIMIZED_ARG]),
+        ))
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(|app, shortcut, event| {
+                    if event.state() == ShortcutState::Pressed {
+                        if !app.state::<PickerState>().handle(app, shortcut) {
+                            app.state::<CaptureState>().handle(app, shortcut);
+                        }
+                    }
+                })
+                .build(),
+        )
         .setup(|app| {
              let store = Store::new(app.handle());
+             // 每次保存后把快照交给同步模块
+             let handle = app.handle().clone();
+             store.subscribe(move |store, change| {
+                 let sync = handle.state::<SyncState>();
+                 sync.record_change(change);
+                 sync.queue(store, change.summary.clone());
+             });
+             // 通知各个窗口（以及托盘等）库已变化，无需轮询 get_items
+             let events = app.handle().clone();
+             store.subscribe(move |_, change| {
+                 let _ = events.emit(store::EVENT_CHANGED, change);
+             });
+             let errors = app.handle().clone();
+             store.on_save_error(move |error| {
+                 let _ = errors.emit(store::EVENT_SAVE_FAILED, error);
+             });
+             let menu = app.handle().clone();
+             store.subscribe(move |_, _| tray::refresh(&menu));
+             // 新增或修改的 prompt 中出现疑似密钥时提醒用户
+             let scanner = app.handle().clone();
+             store.subscribe(move |store, change| {
+                 if matches!(change.kind, ChangeKind::Added | ChangeKind::Updated) {
+                     let findings = store.scan_secrets(Some(change.ids.as_slice()));
+                     if !findings.is_empty() {
+                         let _ = scanner.emit(secrets::EVENT_DETECTED, &findings);
+                     }
+                 }
+             });
+             let hooks = app.handle().clone();
+             store.subscribe(move |store, change| {
+                 hooks.state::<WebhookState>().dispatch(store, change);
+             });
              app.manage(store);
+             app.manage(AppLock::new(store::data_dir(app.handle())));
+             AppLock::watch_idle(app.handle().clone());
+             let encrypted = app.state::<Store>().encryption_status().enabled;
+             app.manage(RunHistory::new(store::data_dir(app.handle()), encrypted));
+             app.manage(LlmState::new(store::data_dir(app.handle())));
+             app.manage(SyncState::new(app.handle(), store::data_dir(app.handle())));
+             app.manage(LanState::new(app.handle()));
+             app.manage(WebhookState::new(store::data_dir(app.handle())));
+             app.manage(PickerState::new(store::data_dir(app.handle())));
+             app.manage(UsageState::new(store::data_dir(app.handle())));
+             app.manage(CaptureState::new(store::data_dir(app.handle())));
+             app.manage(ApiState::new(app.handle(), store::data_dir(app.handle())));
+             app.manage(StartupState::new(store::data_dir(app.handle())));
+             app.manage(SettingsState::new(store::data_dir(app.handle())));
+             app.manage(UiState::new(store::data_dir(app.handle())));
+             app.manage(Maintenance::new(store::data_dir(app.handle())));
+             Maintenance::start(app.handle().clone());
+             if let Err(e) = app.state::<SyncState>().start_folder_sync(&app.state::<Store>()) {
+                 log_error!("Failed to start folder sync: {}", e);
+             }
+             if let Err(e) = app.state::<SyncState>().watch_store_file(&app.state::<Store>()) {
+                 log_error!("Failed to watch store file: {}", e);
+             }
+             if let Err(e) = app.state::<PickerState>().register(app.handle()) {
+                 log_error!("Failed to register quick picker shortcut: {}", e);
+             }
+             if let Err(e) = app.state::<CaptureState>().start(app.handle()) {
+                 log_error!("Failed to start clipboard capture: {}", e);
+             }
+             let api = app.handle().clone();
+             tauri::async_runtime::spawn(async move {
+                 if let Err(e) = api.state::<ApiState>().start().await {
+                     log_error!("Failed to start local API server: {}", e);
+                 }
+             });
+             tray::create(app.handle())?;
+             if let Some(window) = app.get_webview_window("main") {
+                 app.state::<UiState>().restore_window(&window);
+             }
+             // 主窗口默认不显示，最小化启动时只留托盘图标
+             if !app.state::<StartupState>().start_hidden() {
+                 tray::show_main(app.handle());
+             }
+
+             // 开发模式与未安装时在 Linux、Windows 上需要手动注册协议
+             #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+             if let Err(e) = app.deep_link().register_all() {
+                 log_error!("Failed to register deep link scheme: {}", e);
+             }
+             let links = app.handle().clone();
+             app.deep_link().on_open_url(move |event| {
+                 for url in event.urls() {
+                     if let Err(e) = handle_deep_link(&links, &url) {
+                         log_error!("Failed to handle {}: {}", url, e);
+                     }
+                 }
+             });
+             // 通过链接启动时的链接
+             if let Ok(Some(urls)) = app.deep_link().get_current() {
+                 for url in urls {
+                     if let Err(e) = handle_deep_link(app.handle(), &url) {
+                         log_error!("Failed to handle {}: {}", url, e);
+                     }
+                 }
+             }
              Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
-            get_items,
-            get_item,
-            add_item,
-            update_item,
-            delete_item,
-            search_items,
-            move_item
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .invoke_handler({
+            let handler = tauri::generate_handler![
+                get_items,
+                get_item,
+                get_items_light,
+                get_children_page,
+                get_item_content,
+                reveal_content,
+                get_perf_stats,
+                get_audit_log,
+                get_library_stats,
+                find_duplicates,
+                get_text_stats,
+                check_integrity,
+                export_diagnostics,
+                scan_secrets,
+                add_item,
+                update_item,
+                delete_item,
+                search_items,
+                quick_search,
+                move_item,
+                list_variables,
+                render_prompt,
+                preview_prompt,
+                validate_template,
+                get_dependents,
+                list_snippets,
+                insert_snippet,
+                set_variable,
+                remove_variable,
+                run_prompt,
+                run_comparison,
+                set_model_params,
+                get_global_proxy,
+                set_global_proxy,
+                cancel_run,
+                count_tokens,
+                estimate_cost,
+                get_run_history,
+                clear_run_history,
+                get_history_pruning,
+                set_history_pruning,
+                refresh_models,
+                detect_local_providers,
+                test_model,
+                get_sync_config,
+                set_sync_config,
+                sync_pull,
+                sync_push,
+                sync_now,
+                list_conflicts,
+                resolve_conflict,
+                start_receive,
+                stop_receive,
+                get_receive_session,
+                discover_peers,
+                send_items,
+                list_mounts,
+                add_mount,
+                remove_mount,
+                refresh_mounts,
+                list_webhooks,
+                save_webhook,
+                delete_webhook,
+                test_webhook,
+                sync_status,
+                get_picker_shortcut,
+                set_picker_shortcut,
+                toggle_picker,
+                picker_select,
+                get_paste_options,
+                set_paste_options,
+                get_tray_menu,
+                tray_select,
+                copy_prompt,
+                get_usage,
+                prepare_drag_file,
+                set_favorite,
+                set_pinned,
+                set_sensitive,
+                get_capture_config,
+                set_capture_config,
+                capture_clipboard,
+                get_api_config,
+                set_api_config,
+                get_api_url,
+                list_api_tokens,
+                create_api_token,
+                revoke_api_token,
+                get_startup_config,
+                set_startup_config,
+                get_settings,
+                update_settings,
+                get_ui_prefs,
+                set_expanded_items,
+                set_selected_item,
+                run_maintenance,
+                get_encryption_status,
+                unlock_store,
+                enable_encryption,
+                change_passphrase,
+                disable_encryption,
+                backup_store,
+                get_app_lock_status,
+                unlock_app,
+                lock_app,
+                set_app_lock_passphrase,
+                disable_app_lock,
+                set_app_lock_timeout,
+                set_app_lock_guard_sensitive
+            ];
+            // 应用锁定时拒绝除解锁以外的命令
+            move |invoke: tauri::ipc::Invoke| {
+                if let Some(lock) = invoke.message.webview_ref().try_state::<AppLock>() {
+                    if !lock.allows(invoke.message.command()) {
+                        invoke.resolver.reject(CommandError::from(ErrorCode::AppLocked));
+                        return true;
+                    }
+                }
+                handler(invoke)
+            }
+        })
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| match event {
+            RunEvent::WindowEvent {
+                label,
+                event: WindowEvent::Moved(_) | WindowEvent::Resized(_),
+                ..
+            } if label == "main" => {
+                if let Some(window) = app.get_webview_window(&label) {
+                    app.state::<UiState>().remember_window(&window);
+                }
+            }
+            // 退出前等待后台写完 store.json，并保存窗口位置
+            RunEvent::Exit => {
+                app.state::<Store>().flush();
+                if let Err(e) = app.state::<UiState>().save() {
+                    log_error!("Failed to save UI state: {}", e);
+                }
+            }
+            _ => {}
+        });
 }
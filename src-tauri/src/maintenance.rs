@@ -0,0 +1,144 @@
+//! 后台定期维护：按设置的间隔自动备份 store.json 并只保留最近的几个自动备份，
+//! 清理超出保留策略的自动保存版本，完成后发出 [`EVENT_COMPLETED`]。
+//! 删除的条目不进回收站、搜索也不依赖索引，因此没有对应的维护任务
+
+use crate::log_error;
+use crate::models::{MaintenanceSettings, MaintenanceSummary};
+use crate::settings::SettingsState;
+use crate::store::Store;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 维护完成后发出，负载为 [`MaintenanceSummary`]
+pub const EVENT_COMPLETED: &str = "maintenance://completed";
+/// 多久检查一次是否到了维护时间
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// 自动备份的文件名前缀，手动备份（`store-...`）不会被轮换删除
+const AUTO_BACKUP_PREFIX: &str = "store-auto-";
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct MaintenanceState {
+    last_run: Option<i64>,
+}
+
+/// 上次维护的时间保存在 maintenance.json，重启后不会立即重复运行
+pub struct Maintenance {
+    state: Mutex<MaintenanceState>,
+    path: PathBuf,
+    /// 同一时间只运行一次维护
+    running: Mutex<()>,
+}
+
+impl Maintenance {
+    pub fn new(dir: PathBuf) -> Self {
+        let path = dir.join("maintenance.json");
+        let state = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Maintenance {
+            state: Mutex::new(state),
+            path,
+            running: Mutex::new(()),
+        }
+    }
+
+    /// 在后台定期检查，距上次维护超过设置的间隔时运行
+    pub fn start(app: AppHandle) {
+        std::thread::spawn(move || loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            let config = app.state::<SettingsState>().settings().maintenance;
+            let maintenance = app.state::<Maintenance>();
+            if config.enabled && maintenance.due(&config) {
+                maintenance.run(&app);
+            }
+        });
+    }
+
+    fn due(&self, config: &MaintenanceSettings) -> bool {
+        let interval = config.interval_hours as i64 * 60 * 60 * 1000;
+        match self.state.lock().unwrap().last_run {
+            Some(last) => chrono::Utc::now().timestamp_millis() - last >= interval,
+            None => true,
+        }
+    }
+
+    /// 立即运行一次维护；各项任务互不影响，出错的记录在结果的 errors 中
+    pub fn run(&self, app: &AppHandle) -> MaintenanceSummary {
+        let _running = self.running.lock().unwrap();
+        let config = app.state::<SettingsState>().settings().maintenance;
+        let store = app.state::<Store>();
+        let mut summary = MaintenanceSummary {
+            started_at: chrono::Utc::now().timestamp_millis(),
+            ..Default::default()
+        };
+
+        // 先备份，被清理的版本仍保留在备份中
+        if config.keep_backups > 0 {
+            match rotate_backups(&store, config.keep_backups as usize) {
+                Ok((backup, removed)) => {
+                    summary.backup = Some(backup);
+                    summary.backups_removed = removed;
+                }
+                Err(e) => summary.errors.push(format!("Backup failed: {}", e)),
+            }
+        }
+        let keep = config.keep_auto_versions.map(|k| k as usize);
+        match store.prune_versions(keep, config.auto_version_max_days) {
+            Ok(pruned) => summary.versions_pruned = pruned,
+            Err(e) => summary.errors.push(format!("Pruning versions failed: {}", e)),
+        }
+
+        summary.finished_at = chrono::Utc::now().timestamp_millis();
+        for error in &summary.errors {
+            log_error!("Maintenance: {}", error);
+        }
+        if let Err(e) = self.save(summary.started_at) {
+            log_error!("Failed to save maintenance state: {}", e);
+        }
+        let _ = app.emit(EVENT_COMPLETED, &summary);
+        summary
+    }
+
+    fn save(&self, last_run: i64) -> Result<(), String> {
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        state.last_run = Some(last_run);
+        let content = serde_json::to_string_pretty(&*state).map_err(|e| e.to_string())?;
+        fs::write(&self.path, content).map_err(|e| e.to_string())
+    }
+}
+
+/// 在 backups 目录中创建自动备份，只保留最新的 keep 个；返回新备份的路径与删除的旧备份数
+fn rotate_backups(store: &Store, keep: usize) -> Result<(String, usize), String> {
+    let dir = store.path().parent().ok_or("Invalid store path")?.join("backups");
+    let path = dir.join(format!(
+        "{}{}.json",
+        AUTO_BACKUP_PREFIX,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    store.backup(&path)?;
+
+    // 文件名中的时间可以直接按字典序排序
+    let mut backups: Vec<PathBuf> = fs::read_dir(&dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(AUTO_BACKUP_PREFIX) && n.ends_with(".json"))
+        })
+        .collect();
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep);
+    let removed = backups[..excess]
+        .iter()
+        .filter(|old| fs::remove_file(old).is_ok())
+        .count();
+    Ok((path.to_string_lossy().to_string(), removed))
+}
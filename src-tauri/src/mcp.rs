@@ -0,0 +1,185 @@
+//! Model Context Protocol 服务：把库中的 prompt 作为 MCP prompts 提供给 Claude Desktop、Cursor 等客户端，
+//! 声明的变量（以及未声明的 `{{变量}}`）作为 prompt 的参数。
+//! 通过 stdio（`prompt-manager mcp`）或本地 REST 服务上的 `POST /mcp` 使用；标记为敏感的 prompt 不提供
+
+use crate::models::{ItemType, TreeItem};
+use crate::sensitive;
+use crate::store::Store;
+use crate::template::{self, TemplateSpec};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+
+const PROTOCOL_VERSION: &str = "2025-03-26";
+const SUPPORTED_VERSIONS: &[&str] = &["2024-11-05", "2025-03-26", "2025-06-18"];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpPrompt {
+    /// 条目路径（`文件夹/名称`），重名时为条目 id
+    name: String,
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    arguments: Vec<McpArgument>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpArgument {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    required: bool,
+}
+
+/// 处理一条 JSON-RPC 消息（或批量消息）；通知没有响应，返回 None
+pub fn handle(store: &Store, message: Value) -> Option<Value> {
+    match message {
+        Value::Array(batch) => {
+            let responses: Vec<Value> = batch.into_iter().filter_map(|m| handle(store, m)).collect();
+            (!responses.is_empty()).then_some(Value::Array(responses))
+        }
+        Value::Object(request) => {
+            let id = request.get("id").cloned()?;
+            let Some(method) = request.get("method").and_then(Value::as_str) else {
+                return Some(error(id, INVALID_REQUEST, "Missing method".to_string()));
+            };
+            let params = request.get("params").cloned().unwrap_or(Value::Null);
+            Some(match call(store, method, &params) {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Err((code, message)) => error(id, code, message),
+            })
+        }
+        _ => Some(error(Value::Null, INVALID_REQUEST, "Invalid request".to_string())),
+    }
+}
+
+/// 无法解析的消息的响应
+pub fn parse_error(e: impl std::fmt::Display) -> Value {
+    error(Value::Null, PARSE_ERROR, format!("Parse error: {}", e))
+}
+
+fn error(id: Value, code: i64, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn call(store: &Store, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+    match method {
+        "initialize" => {
+            let requested = params.get("protocolVersion").and_then(Value::as_str);
+            let version = requested
+                .filter(|v| SUPPORTED_VERSIONS.contains(v))
+                .unwrap_or(PROTOCOL_VERSION);
+            Ok(json!({
+                "protocolVersion": version,
+                "capabilities": { "prompts": { "listChanged": false } },
+                "serverInfo": { "name": "prompt-manager", "version": env!("CARGO_PKG_VERSION") },
+            }))
+        }
+        "ping" => Ok(json!({})),
+        "prompts/list" => Ok(json!({ "prompts": list_prompts(store) })),
+        "prompts/get" => get_prompt(store, params),
+        _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+    }
+}
+
+fn list_prompts(store: &Store) -> Vec<McpPrompt> {
+    let mut prompts = Vec::new();
+    let mut used = HashSet::new();
+    collect(store, &store.get_tree(), "", &mut used, &mut prompts);
+    prompts
+}
+
+fn collect(store: &Store, nodes: &[TreeItem], prefix: &str, used: &mut HashSet<String>, prompts: &mut Vec<McpPrompt>) {
+    for node in nodes {
+        let path = if prefix.is_empty() {
+            node.name.clone()
+        } else {
+            format!("{}/{}", prefix, node.name)
+        };
+        if node.item_type == ItemType::Prompt && !sensitive::is_sensitive(node) {
+            let name = if used.insert(path.clone()) {
+                path.clone()
+            } else {
+                node.id.clone()
+            };
+            prompts.push(McpPrompt {
+                name,
+                title: node.name.clone(),
+                description: node.metadata.description.clone().filter(|d| !d.is_empty()),
+                arguments: arguments(store, node),
+            });
+        }
+        collect(store, &node.children, &path, used, prompts);
+    }
+}
+
+/// 声明的变量在前，其后是内容中出现但未声明的变量（必填）
+fn arguments(store: &Store, item: &TreeItem) -> Vec<McpArgument> {
+    let spec = TemplateSpec::of(&item.metadata);
+    let content = store
+        .expand_includes(item)
+        .unwrap_or_else(|_| item.content.as_deref().unwrap_or_default().to_string());
+    let mut arguments: Vec<McpArgument> = spec
+        .declared
+        .iter()
+        .map(|decl| McpArgument {
+            name: decl.name.clone(),
+            description: decl.description.clone().filter(|d| !d.is_empty()),
+            required: decl.required && decl.default_value.as_deref().map_or(true, str::is_empty),
+        })
+        .collect();
+    for variable in template::list_variables(&content) {
+        if !arguments.iter().any(|a| a.name == variable.name) {
+            arguments.push(McpArgument {
+                name: variable.name,
+                description: None,
+                required: true,
+            });
+        }
+    }
+    arguments
+}
+
+fn get_prompt(store: &Store, params: &Value) -> Result<Value, (i64, String)> {
+    let name = params
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or((INVALID_PARAMS, "Missing prompt name".to_string()))?;
+    let item = store
+        .find_by_reference(name)
+        .filter(|item| item.item_type == ItemType::Prompt && !sensitive::is_sensitive(item))
+        .ok_or_else(|| (INVALID_PARAMS, format!("Prompt not found: {}", name)))?;
+    let values: HashMap<String, String> = params
+        .get("arguments")
+        .and_then(Value::as_object)
+        .map(|args| {
+            args.iter()
+                .map(|(k, v)| {
+                    (
+                        k.clone(),
+                        v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string()),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let text = store
+        .expand_includes(&item)
+        .and_then(|content| template::render(&content, &TemplateSpec::of(&item.metadata), &values))
+        .map_err(|e| (INVALID_PARAMS, e.to_string()))?;
+    let mut result = json!({
+        "messages": [{ "role": "user", "content": { "type": "text", "text": text } }],
+    });
+    if let Some(description) = item.metadata.description.filter(|d| !d.is_empty()) {
+        result["description"] = Value::String(description);
+    }
+    Ok(result)
+}
@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -6,6 +8,24 @@ pub enum ItemType {
     Prompt,
     Folder,
     Settings,
+    Provider,
+    Model,
+    /// 可复用的片段（输出格式、角色设定等），不出现在普通的 prompt 列表中，供引用与插入使用
+    Snippet,
+}
+
+impl ItemType {
+    /// 用于提交信息等文本中的类型名
+    pub fn label(&self) -> &'static str {
+        match self {
+            ItemType::Prompt => "prompt",
+            ItemType::Folder => "folder",
+            ItemType::Settings => "settings",
+            ItemType::Provider => "provider",
+            ItemType::Model => "model",
+            ItemType::Snippet => "snippet",
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -13,7 +33,7 @@ pub enum ItemType {
 pub struct PromptVersion {
     pub id: String,
     pub timestamp: i64,
-    pub content: String,
+    pub content: Arc<str>,
     pub label: Option<String>,
 }
 
@@ -28,6 +48,132 @@ pub struct ItemMetadata {
     pub model_name: Option<String>,
     pub base_url: Option<String>,
     pub api_key: Option<String>,
+    // 计价（仅 model 类型使用，单位：美元 / 百万 token）
+    pub input_price: Option<f64>,
+    pub output_price: Option<f64>,
+    // 默认生成参数（仅 model 类型使用）
+    pub generation: Option<GenerationParams>,
+    // 代理（provider / model 类型使用，覆盖全局代理）
+    pub proxy: Option<ProxyConfig>,
+    // 限流与重试（仅 provider 类型使用）
+    pub rate_limit: Option<RateLimit>,
+    // OpenAI 兼容网关的附加配置（provider / model 类型使用）
+    /// 放置 API Key 的请求头，例如 Azure 的 "api-key"；为空时使用 Authorization: Bearer
+    pub auth_header: Option<String>,
+    pub extra_headers: Option<HashMap<String, String>>,
+    /// 附加查询参数，例如 Azure 的 api-version
+    pub query_params: Option<HashMap<String, String>>,
+    // 声明的模板变量（仅 prompt 类型使用）
+    pub variables: Option<Vec<VariableDecl>>,
+    // 模板模式（仅 prompt 类型使用，缺省为简单替换）
+    pub template_mode: Option<TemplateMode>,
+    // 来自只读挂载库的条目，不能修改
+    pub read_only: Option<bool>,
+    // 向量时钟：每台设备对该条目的修改次数，用于多设备同步时判断修改先后
+    pub clock: Option<VectorClock>,
+    // 收藏与置顶（托盘菜单等处显示）
+    pub favorite: Option<bool>,
+    pub pinned: Option<bool>,
+    /// 敏感条目：列表与搜索结果中隐藏内容，需要时再显示
+    pub sensitive: Option<bool>,
+}
+
+/// 设备 id -> 该设备上的修改计数
+pub type VectorClock = BTreeMap<String, u64>;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum TemplateMode {
+    /// 仅做 `{{name}}` 替换；`\{\{` / `\}\}` 与 `{% raw %}` 块中的内容原样输出
+    #[default]
+    Simple,
+    /// Tera 模板：支持条件、循环与过滤器
+    Tera,
+}
+
+/// prompt 中声明的变量
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VariableDecl {
+    pub name: String,
+    pub description: Option<String>,
+    pub default_value: Option<String>,
+    /// 预览时使用的示例值，优先于默认值
+    pub example_value: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+    /// 值的类型，前端据此渲染输入控件
+    #[serde(default, rename = "type")]
+    pub var_type: VariableType,
+    /// enum 类型的可选值
+    pub options: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum VariableType {
+    #[default]
+    String,
+    Number,
+    Boolean,
+    Enum,
+    Multiline,
+}
+
+/// Provider 级别的并发、速率与重试配置
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimit {
+    /// 同时进行的请求数上限
+    pub max_concurrent: u32,
+    /// 每分钟请求数上限（None 表示不限制）
+    pub requests_per_minute: Option<u32>,
+    /// 遇到 429 / 5xx / 连接错误时的最大重试次数
+    pub max_retries: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        RateLimit {
+            max_concurrent: 4,
+            requests_per_minute: None,
+            max_retries: 3,
+        }
+    }
+}
+
+/// HTTP / HTTPS / SOCKS5 代理配置
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyConfig {
+    /// 例如 http://127.0.0.1:7890、socks5://127.0.0.1:1080
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// 逗号分隔的直连地址，例如 "localhost,127.0.0.1"
+    pub no_proxy: Option<String>,
+}
+
+/// 生成参数；Model 上保存默认值，运行时可逐项覆盖
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationParams {
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub max_tokens: Option<u32>,
+    pub system_prompt: Option<String>,
+}
+
+impl GenerationParams {
+    /// 以 overrides 中已设置的字段覆盖自身
+    pub fn merged_with(&self, overrides: &GenerationParams) -> GenerationParams {
+        GenerationParams {
+            temperature: overrides.temperature.or(self.temperature),
+            top_p: overrides.top_p.or(self.top_p),
+            max_tokens: overrides.max_tokens.or(self.max_tokens),
+            system_prompt: overrides.system_prompt.clone().or_else(|| self.system_prompt.clone()),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -40,7 +186,8 @@ pub struct TreeItem {
     #[serde(default)]
     pub children: Vec<TreeItem>,
     pub parent_id: Option<String>,
-    pub content: Option<String>,
+    /// 内容与历史版本用 `Arc<str>` 共享，克隆条目时不复制大段文本
+    pub content: Option<Arc<str>>,
     pub versions: Option<Vec<PromptVersion>>,
     #[serde(default)]
     pub metadata: ItemMetadata,
@@ -69,5 +216,941 @@ pub struct SearchResult {
 #[serde(rename_all = "camelCase")]
 pub struct SearchFilters {
     pub types: Option<Vec<ItemType>>,
-    pub date: Option<String>, // 'any', 'today', 'week', 'month'
+    pub date: Option<String>, // 'any', 'today', 'week', 'month'（自然日、周、月，按用户所在时区）
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+impl TokenUsage {
+    /// 合并分段上报的用量：后到的非零字段覆盖先前的值
+    pub fn merge(self, other: TokenUsage) -> TokenUsage {
+        let prompt_tokens = if other.prompt_tokens > 0 { other.prompt_tokens } else { self.prompt_tokens };
+        let completion_tokens = if other.completion_tokens > 0 {
+            other.completion_tokens
+        } else {
+            self.completion_tokens
+        };
+        TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: other.total_tokens.max(prompt_tokens + completion_tokens),
+        }
+    }
+}
+
+/// `llm://chunk` 事件负载：一次增量输出
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmChunkEvent {
+    pub run_id: String,
+    pub model_id: Option<String>,
+    pub delta: String,
+}
+
+/// `llm://retry` 事件负载：请求失败后即将重试
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmRetryEvent {
+    pub run_id: String,
+    pub model_id: Option<String>,
+    pub attempt: u32,
+    pub delay_ms: u64,
+    pub reason: String,
+}
+
+/// `llm://done` 事件负载：运行结束（正常完成、取消或出错）
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmDoneEvent {
+    pub run_id: String,
+    pub model_id: Option<String>,
+    pub content: String,
+    pub usage: Option<TokenUsage>,
+    pub cancelled: bool,
+    pub error: Option<String>,
+    pub cost: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenCount {
+    pub tokens: usize,
+    pub context_window: usize,
+    pub exceeds_context: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPricing {
+    pub input_price: f64,
+    pub output_price: f64,
+}
+
+impl ModelPricing {
+    pub fn cost(&self, input_tokens: usize, output_tokens: usize) -> f64 {
+        (input_tokens as f64 * self.input_price + output_tokens as f64 * self.output_price) / 1_000_000.0
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CostEstimate {
+    pub model: String,
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+    pub input_cost: f64,
+    pub output_cost: f64,
+    pub total_cost: f64,
+}
+
+/// 一次 `run_prompt` 执行的完整记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunRecord {
+    pub id: String,
+    pub item_id: String,
+    pub timestamp: i64,
+    pub model: String,
+    #[serde(default)]
+    pub parameters: Option<serde_json::Value>,
+    pub input: String,
+    pub response: String,
+    pub latency_ms: u64,
+    pub usage: Option<TokenUsage>,
+    pub cost: Option<f64>,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+/// 运行历史的清理策略
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPruning {
+    /// 每个 prompt 最多保留的记录数
+    pub max_runs_per_item: usize,
+    /// 超过该天数的记录会被删除（None 表示不按时间清理）
+    pub max_age_days: Option<u32>,
+}
+
+impl Default for HistoryPruning {
+    fn default() -> Self {
+        HistoryPruning {
+            max_runs_per_item: 50,
+            max_age_days: None,
+        }
+    }
+}
+
+/// 在本机探测到的推理服务
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalEndpoint {
+    pub name: String,
+    /// 创建 Provider 节点时使用的 provider 字段
+    pub provider: String,
+    pub base_url: String,
+    pub models: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VariablePosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// prompt 内容中出现的变量
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateVariable {
+    pub name: String,
+    pub positions: Vec<VariablePosition>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TemplateIssueKind {
+    /// 使用了未声明的变量
+    UndefinedVariable,
+    /// 声明了但内容中没有使用
+    UnusedVariable,
+    /// 花括号不成对
+    UnbalancedBraces,
+    /// 花括号内不是合法的变量名，渲染时会原样保留
+    InvalidPlaceholder,
+    /// 引用的条目不存在或存在循环引用
+    BrokenInclude,
+    /// Tera 模板语法错误
+    SyntaxError,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum IssueSeverity {
+    Error,
+    Warning,
+}
+
+/// 模板检查发现的问题；与具体位置无关的问题没有行列号
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateIssue {
+    pub kind: TemplateIssueKind,
+    pub severity: IssueSeverity,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+/// 用示例值渲染的预览结果；没有可用值的变量保留为占位符
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptPreview {
+    pub content: String,
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Added,
+    Updated,
+    Deleted,
+    Moved,
+    /// 整个库被替换（例如从远端拉取）
+    Replaced,
+}
+
+/// 一次库变更，`summary` 为可读的描述（用作提交信息）
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StoreChange {
+    pub kind: ChangeKind,
+    pub ids: Vec<String>,
+    pub summary: String,
+}
+
+/// Git 同步配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSyncConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 仓库目录，缺省为数据目录下的 library
+    pub repo_path: Option<String>,
+    /// 远端地址，例如 git@github.com:me/prompts.git
+    pub remote_url: Option<String>,
+    #[serde(default = "default_branch")]
+    pub branch: String,
+}
+
+fn default_branch() -> String {
+    "main".to_string()
+}
+
+/// WebDAV 同步配置（Nextcloud / ownCloud 等）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WebDavConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 目录地址（库文件保存为其中的 prompt-library.json）或以 .json 结尾的文件地址
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// 自动同步间隔（分钟），0 表示仅手动同步
+    #[serde(default = "default_sync_interval")]
+    pub interval_minutes: u32,
+}
+
+fn default_sync_interval() -> u32 {
+    15
+}
+
+/// 与普通目录中的 Markdown 文件双向同步
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderSyncConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConfig {
+    pub git: Option<GitSyncConfig>,
+    pub webdav: Option<WebDavConfig>,
+    pub folder: Option<FolderSyncConfig>,
+}
+
+/// 同步时两侧都修改、无法自动合并的条目；库中暂时保留本地版本
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConflict {
+    pub item_id: String,
+    pub name: String,
+    /// 发现冲突的同步方式：git / webdav / folder
+    pub source: String,
+    pub detected_at: i64,
+    pub base: Option<TreeItem>,
+    /// 为 None 表示该侧删除了条目
+    pub local: Option<TreeItem>,
+    pub remote: Option<TreeItem>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictChoice {
+    Local,
+    Remote,
+    /// 使用调用方提供的内容（手动合并）
+    Custom,
+}
+
+/// 局域网中发现的另一台正在接收的设备
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LanPeer {
+    pub name: String,
+    /// IP 地址
+    pub host: String,
+    pub port: u16,
+}
+
+/// 正在进行的接收会话；发送方需要输入配对码
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LanSession {
+    pub name: String,
+    pub port: u16,
+    pub code: String,
+}
+
+/// 以只读方式挂载的共享库：目录（Markdown 文件布局）或库 JSON 文件
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MountedLibrary {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+}
+
+/// 库变更时调用的外部 webhook
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    /// 为空时保存为新的 webhook
+    #[serde(default)]
+    pub id: String,
+    pub name: Option<String>,
+    pub url: String,
+    /// 设置后请求带有 `X-Prompt-Manager-Signature: sha256=<HMAC>` 签名头
+    pub secret: Option<String>,
+    /// 触发的变更类型，为空表示全部
+    #[serde(default)]
+    pub events: Vec<ChangeKind>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 某个同步目标的状态
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncTargetStatus {
+    /// git / webdav / folder
+    pub target: String,
+    /// 上次成功同步之后修改过的条目数
+    pub pending: usize,
+    pub last_sync: Option<i64>,
+    /// 最近一次同步失败的原因，成功后清除
+    pub last_error: Option<String>,
+}
+
+/// 全部启用的同步目标的汇总状态
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    /// 尚未同步到至少一个目标的条目
+    pub pending_ids: Vec<String>,
+    pub pending: usize,
+    /// 各目标中最早的一次成功同步时间；有目标从未同步时为 None
+    pub last_sync: Option<i64>,
+    pub last_error: Option<String>,
+    pub targets: Vec<SyncTargetStatus>,
+}
+
+/// 快速选择器的搜索结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickMatch {
+    pub item_id: String,
+    pub name: String,
+    pub item_type: ItemType,
+    /// 所在文件夹的路径，例如 `Work/Email`
+    pub path: String,
+    pub tags: Vec<String>,
+    pub score: i64,
+}
+
+/// 条目的使用统计（复制、托盘选择等）
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemUsage {
+    pub count: u64,
+    pub last_used: Option<i64>,
+}
+
+/// 托盘菜单中的一项
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TrayEntry {
+    pub item_id: String,
+    pub name: String,
+}
+
+/// 托盘菜单的内容
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TrayMenuData {
+    pub pinned: Vec<TrayEntry>,
+    pub favorites: Vec<TrayEntry>,
+    /// 最近使用的，最新的在前
+    pub recent: Vec<TrayEntry>,
+}
+
+/// 剪贴板收集的设置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureConfig {
+    /// 是否监听剪贴板并自动收集看起来像 prompt 的文本
+    #[serde(default)]
+    pub enabled: bool,
+    /// 把当前剪贴板内容直接收集进收件箱的全局快捷键
+    pub shortcut: Option<String>,
+    #[serde(default = "default_capture_min_length")]
+    pub min_length: usize,
+    #[serde(default = "default_capture_max_length")]
+    pub max_length: usize,
+    /// 收件箱文件夹；为空或已删除时使用（或新建）根目录下的 "Inbox"
+    pub inbox_id: Option<String>,
+}
+
+fn default_capture_min_length() -> usize {
+    80
+}
+
+fn default_capture_max_length() -> usize {
+    20_000
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        CaptureConfig {
+            enabled: false,
+            shortcut: None,
+            min_length: default_capture_min_length(),
+            max_length: default_capture_max_length(),
+            inbox_id: None,
+        }
+    }
+}
+
+/// 本地 REST API 的设置；只监听 127.0.0.1
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_api_port")]
+    pub port: u16,
+}
+
+fn default_api_port() -> u16 {
+    7321
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        ApiConfig {
+            enabled: false,
+            port: default_api_port(),
+        }
+    }
+}
+
+/// 开机启动与启动时最小化到托盘
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupConfig {
+    /// 登录系统时自动启动（注册到系统的启动项）
+    #[serde(default)]
+    pub autostart: bool,
+    /// 启动时不显示主窗口，只保留托盘图标与全局快捷键
+    #[serde(default)]
+    pub start_minimized: bool,
+}
+
+/// 在快速选择器中选中 prompt 后如何送到之前的窗口
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum PasteMode {
+    /// 只复制到剪贴板
+    #[default]
+    Off,
+    /// 复制后模拟粘贴快捷键
+    Paste,
+    /// 逐字模拟键入（不支持粘贴的程序）
+    Type,
+}
+
+/// 模拟的粘贴快捷键
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum PasteKeys {
+    /// macOS 上为 ⌘V，其他系统为 Ctrl+V
+    #[default]
+    Default,
+    /// Linux 终端常用的 Ctrl+Shift+V
+    CtrlShiftV,
+    /// X11 等环境的 Shift+Insert（macOS 不支持）
+    ShiftInsert,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PasteOptions {
+    #[serde(default)]
+    pub mode: PasteMode,
+    #[serde(default)]
+    pub keys: PasteKeys,
+    /// 选择器关闭后等待焦点回到之前窗口的时间，缺省 150 毫秒
+    pub delay_ms: Option<u64>,
+}
+
+/// 树中一个条目的轻量表示：不含内容与历史版本，子节点只给出 id
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LightItem {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub item_type: ItemType,
+    pub parent_id: Option<String>,
+    pub children: Vec<String>,
+    pub metadata: ItemSummary,
+}
+
+/// 树中显示所需的元数据
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemSummary {
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub last_modified: Option<i64>,
+    pub favorite: Option<bool>,
+    pub pinned: Option<bool>,
+    pub read_only: Option<bool>,
+    pub sensitive: Option<bool>,
+    pub variable_count: usize,
+    pub version_count: usize,
+}
+
+/// 按需加载的条目内容
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemContent {
+    pub id: String,
+    pub content: Option<Arc<str>>,
+    pub versions: Option<Vec<PromptVersion>>,
+    /// 条目标记为敏感
+    pub sensitive: bool,
+}
+
+/// 子节点列表的排序方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ChildSort {
+    /// 库中的顺序
+    #[default]
+    Manual,
+    Name,
+    /// 最近修改的在前
+    Modified,
+    /// 文件夹在前，再按名称
+    Type,
+}
+
+/// 一页子节点
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChildrenPage {
+    pub items: Vec<LightItem>,
+    /// 子节点总数
+    pub total: usize,
+    pub offset: usize,
+}
+
+/// 某种操作最近若干次的耗时（毫秒）
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationStats {
+    pub operation: String,
+    /// 统计的样本数
+    pub count: usize,
+    pub last_ms: f64,
+    pub avg_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+/// 性能统计，用户反馈卡顿时可以附上
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PerfStats {
+    pub operations: Vec<OperationStats>,
+    pub item_count: usize,
+    pub prompt_count: usize,
+    pub folder_count: usize,
+    pub mounted_item_count: usize,
+    /// store.json 的字节数
+    pub store_size: u64,
+}
+
+/// store.json 的加密状态
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    /// 已加密但还没有输入口令
+    pub locked: bool,
+}
+
+/// 应用锁的状态
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AppLockStatus {
+    pub enabled: bool,
+    pub locked: bool,
+    pub idle_timeout_minutes: Option<u32>,
+    /// 显示敏感条目的内容前是否需要输入主密码
+    pub guard_sensitive: bool,
+}
+
+/// prompt 内容中一处疑似密钥
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretFinding {
+    pub item_id: String,
+    pub item_name: String,
+    /// 出现在历史版本中时为版本 id
+    pub version_id: Option<String>,
+    /// 例如 "OpenAI API key"、"Private key"
+    pub kind: String,
+    pub line: usize,
+    pub column: usize,
+    /// 打码后的片段
+    pub preview: String,
+}
+
+/// 修改记录中的一条
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub timestamp: i64,
+    /// 做出修改的设备 id
+    pub device: String,
+    pub kind: ChangeKind,
+    pub ids: Vec<String>,
+    pub summary: String,
+}
+
+/// 查询修改记录的条件，均为可选
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditFilters {
+    /// 只看涉及该条目的记录
+    pub item_id: Option<String>,
+    pub kinds: Option<Vec<ChangeKind>>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    /// 在摘要中搜索
+    pub query: Option<String>,
+    /// 最多返回的条数，缺省 500
+    pub limit: Option<usize>,
+}
+
+/// 本地 API 令牌的权限
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ApiTokenScope {
+    Read,
+    ReadWrite,
+}
+
+/// 本地 API 令牌（不含令牌本身，只保存其哈希）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiTokenInfo {
+    pub id: String,
+    pub name: String,
+    pub scope: ApiTokenScope,
+    pub created_at: i64,
+    /// 本次运行中最近一次使用的时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<i64>,
+}
+
+/// 新建的令牌；令牌只在创建时返回这一次
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NewApiToken {
+    pub info: ApiTokenInfo,
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum Theme {
+    Dark,
+    Light,
+    /// 跟随系统
+    #[default]
+    System,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum Language {
+    #[default]
+    En,
+    Zh,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum TitleBarStyle {
+    #[default]
+    Native,
+    /// 隐藏系统标题栏，使用应用自绘的标题栏
+    Custom,
+}
+
+/// 应用设置，保存在 settings.json；缺少的字段取默认值
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppSettings {
+    pub theme: Theme,
+    pub language: Language,
+    pub editor_font_size: u32,
+    pub editor_font_family: String,
+    pub editor_word_wrap: bool,
+    pub title_bar_style: TitleBarStyle,
+    /// IANA 时区名（例如 `Asia/Shanghai`），用于“今天”“本周”等日期过滤；为空时使用系统时区
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    pub maintenance: MaintenanceSettings,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        AppSettings {
+            theme: Theme::System,
+            language: Language::En,
+            editor_font_size: 14,
+            editor_font_family: "'Menlo', 'Monaco', 'Courier New', monospace".to_string(),
+            editor_word_wrap: true,
+            title_bar_style: TitleBarStyle::Native,
+            timezone: None,
+            maintenance: MaintenanceSettings::default(),
+        }
+    }
+}
+
+/// 后台定期维护：自动备份并轮换，清理自动保存的历史版本
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MaintenanceSettings {
+    pub enabled: bool,
+    /// 两次维护之间的间隔（小时）
+    pub interval_hours: u32,
+    /// 保留多少个自动备份，0 表示不自动备份
+    pub keep_backups: u32,
+    /// 每个条目保留的自动保存版本（没有标签的版本）数，为空表示不限
+    pub keep_auto_versions: Option<u32>,
+    /// 删除早于多少天的自动保存版本，为空表示不限
+    pub auto_version_max_days: Option<u32>,
+}
+
+impl Default for MaintenanceSettings {
+    fn default() -> Self {
+        MaintenanceSettings {
+            enabled: true,
+            interval_hours: 24,
+            keep_backups: 10,
+            keep_auto_versions: None,
+            auto_version_max_days: None,
+        }
+    }
+}
+
+/// 对设置的部分修改，只包含要改的字段
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsPatch {
+    pub theme: Option<Theme>,
+    pub language: Option<Language>,
+    pub editor_font_size: Option<u32>,
+    pub editor_font_family: Option<String>,
+    pub editor_word_wrap: Option<bool>,
+    pub title_bar_style: Option<TitleBarStyle>,
+    /// 空字符串表示改回系统时区
+    pub timezone: Option<String>,
+    pub maintenance: Option<MaintenanceSettings>,
+}
+
+/// 主窗口的位置与大小（物理像素）
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+/// 界面状态，保存在 ui_state.json，下次启动时恢复
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UiPrefs {
+    pub window: Option<WindowGeometry>,
+    /// 展开的文件夹；可能包含已被删除的条目
+    pub expanded_ids: Vec<String>,
+    pub selected_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// 某一周（用户所在时区的周一 0 点起）内修改过的条目数
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WeeklyCount {
+    pub week_start: i64,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptSize {
+    pub id: String,
+    pub name: String,
+    /// 所在文件夹的路径
+    pub path: String,
+    /// 内容的字符数
+    pub length: usize,
+}
+
+/// 库的统计信息（含挂载库）
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryStats {
+    /// 类型名 -> 条目数
+    pub counts: BTreeMap<String, usize>,
+    /// prompt 与片段内容的总字符数
+    pub total_content_length: usize,
+    pub average_content_length: usize,
+    /// 按使用次数从多到少
+    pub tags: Vec<TagCount>,
+    /// 最近几周每周修改的条目数，从早到晚，没有修改的周计为 0
+    pub weekly_modified: Vec<WeeklyCount>,
+    pub largest_prompts: Vec<PromptSize>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateItem {
+    pub id: String,
+    pub name: String,
+    /// 所在文件夹的路径
+    pub path: String,
+}
+
+/// 内容相同或相近的一组 prompt
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    /// 组内内容（忽略空白与大小写后）完全相同
+    pub exact: bool,
+    /// 把条目连成一组的相似度中最低的一个，0 到 1
+    pub similarity: f64,
+    pub items: Vec<DuplicateItem>,
+}
+
+/// prompt 的文字统计；文件夹为其下全部 prompt 与片段之和
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TextStats {
+    pub characters: usize,
+    /// 不含空白的字符数
+    pub characters_no_spaces: usize,
+    /// 按空白分词，中日韩文字每个字计为一个词
+    pub words: usize,
+    pub lines: usize,
+    /// 估计的阅读时间（秒）
+    pub reading_seconds: u64,
+    /// 统计了多少个 prompt 与片段
+    pub item_count: usize,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum IntegrityIssueKind {
+    /// store.json 无法读取或解析
+    StoreFile,
+    /// 条目的 parentId 与它在树中的实际位置不一致
+    ParentMismatch,
+    /// 不应有子节点的条目（prompt、片段等）有子节点
+    UnexpectedChildren,
+    DuplicateVersionId,
+    /// 修改时间晚于当前时间（多半是设备时钟不准）
+    FutureTimestamp,
+}
+
+/// 完整性检查发现的问题；只含 id，不含条目名称与内容，可以直接附在问题反馈中
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityIssue {
+    pub kind: IntegrityIssueKind,
+    pub item_id: Option<String>,
+    pub message: String,
+}
+
+/// 一次维护的结果，完成后随事件发出
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceSummary {
+    pub started_at: i64,
+    pub finished_at: i64,
+    /// 本次创建的自动备份
+    pub backup: Option<String>,
+    pub backups_removed: usize,
+    pub versions_pruned: usize,
+    pub errors: Vec<String>,
 }
@@ -0,0 +1,73 @@
+use crate::encryption;
+use crate::models::{ItemMetadata, ItemType, MountedLibrary, TreeItem};
+use crate::store;
+use std::fs;
+use std::path::Path;
+
+/// 挂载库条目 id 的前缀：`mount:<挂载 id>:<原 id>`，挂载根节点为 `mount:<挂载 id>`
+const PREFIX: &str = "mount:";
+
+pub fn is_mounted(id: &str) -> bool {
+    id.starts_with(PREFIX)
+}
+
+pub fn load_config(path: &Path) -> Vec<MountedLibrary> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_config(path: &Path, mounts: &[MountedLibrary]) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(mounts).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| e.to_string())
+}
+
+/// 读取挂载库，返回其根节点；读取失败时根节点没有子节点，错误写在描述中
+pub fn load(mount: &MountedLibrary) -> TreeItem {
+    let root_id = format!("{}{}", PREFIX, mount.id);
+    let (mut children, description) = match read_library(Path::new(&mount.path)) {
+        Ok(items) => (items, Some(mount.path.clone())),
+        Err(e) => (Vec::new(), Some(format!("Failed to load {}: {}", mount.path, e))),
+    };
+    let prefix = format!("{}:", root_id);
+    mark_read_only(&mut children, &prefix, &root_id);
+
+    TreeItem {
+        id: root_id,
+        name: mount.name.clone(),
+        item_type: ItemType::Folder,
+        children,
+        parent_id: None,
+        content: None,
+        versions: None,
+        metadata: ItemMetadata {
+            description,
+            read_only: Some(true),
+            ..Default::default()
+        },
+    }
+}
+
+/// 目录按同步使用的文件布局读取，文件按 store.json 读取（兼容旧的条目数组格式）
+fn read_library(path: &Path) -> Result<Vec<TreeItem>, String> {
+    if path.is_dir() {
+        return crate::sync::import_layout(path);
+    }
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    if encryption::is_encrypted(&content) {
+        return Err("Encrypted libraries cannot be mounted".to_string());
+    }
+    Ok(store::parse_store_file(&content)?.items)
+}
+
+/// 给 id 加上挂载前缀（避免与本地条目重复）并标记为只读
+fn mark_read_only(items: &mut [TreeItem], prefix: &str, parent_id: &str) {
+    for item in items {
+        item.id = format!("{}{}", prefix, item.id);
+        item.parent_id = Some(parent_id.to_string());
+        item.metadata.read_only = Some(true);
+        let id = item.id.clone();
+        mark_read_only(&mut item.children, prefix, &id);
+    }
+}
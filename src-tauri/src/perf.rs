@@ -0,0 +1,60 @@
+use crate::models::OperationStats;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 每种操作保留的最近耗时样本数
+const SAMPLE_LIMIT: usize = 100;
+
+/// 最近操作（加载、保存、搜索、读取树）的耗时，用于排查卡顿
+#[derive(Default)]
+pub struct PerfLog {
+    samples: Mutex<BTreeMap<&'static str, VecDeque<Duration>>>,
+}
+
+impl PerfLog {
+    pub fn record(&self, operation: &'static str, elapsed: Duration) {
+        let Ok(mut samples) = self.samples.lock() else {
+            return;
+        };
+        let recent = samples.entry(operation).or_default();
+        if recent.len() == SAMPLE_LIMIT {
+            recent.pop_front();
+        }
+        recent.push_back(elapsed);
+    }
+
+    /// 执行 f 并记录耗时
+    pub fn time<T>(&self, operation: &'static str, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = f();
+        self.record(operation, started.elapsed());
+        result
+    }
+
+    pub fn stats(&self) -> Vec<OperationStats> {
+        let samples = self.samples.lock().unwrap();
+        samples
+            .iter()
+            .filter(|(_, recent)| !recent.is_empty())
+            .map(|(operation, recent)| {
+                let mut sorted: Vec<Duration> = recent.iter().copied().collect();
+                sorted.sort();
+                let total: Duration = sorted.iter().sum();
+                let p95 = sorted[(sorted.len() * 95).div_ceil(100) - 1];
+                OperationStats {
+                    operation: operation.to_string(),
+                    count: sorted.len(),
+                    last_ms: millis(*recent.back().unwrap()),
+                    avg_ms: millis(total) / sorted.len() as f64,
+                    p95_ms: millis(p95),
+                    max_ms: millis(*sorted.last().unwrap()),
+                }
+            })
+            .collect()
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
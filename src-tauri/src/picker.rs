@@ -0,0 +1,210 @@
+use crate::clipboard;
+use crate::log_error;
+use crate::models::{PasteKeys, PasteMode, PasteOptions};
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+/// 快速选择器窗口的 label
+pub const WINDOW: &str = "picker";
+const DEFAULT_SHORTCUT: &str = "CommandOrControl+Shift+Space";
+const DEFAULT_PASTE_DELAY: u64 = 150;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PickerConfig {
+    shortcut: String,
+    #[serde(default)]
+    paste: PasteOptions,
+}
+
+impl Default for PickerConfig {
+    fn default() -> Self {
+        PickerConfig {
+            shortcut: DEFAULT_SHORTCUT.to_string(),
+            paste: PasteOptions::default(),
+        }
+    }
+}
+
+/// 快速选择器：全局快捷键与自动粘贴的设置保存在 picker.json
+pub struct PickerState {
+    config: Mutex<PickerConfig>,
+    path: PathBuf,
+}
+
+impl PickerState {
+    pub fn new(dir: PathBuf) -> Self {
+        let path = dir.join("picker.json");
+        let config = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        PickerState {
+            config: Mutex::new(config),
+            path,
+        }
+    }
+
+    pub fn shortcut(&self) -> String {
+        self.config.lock().unwrap().shortcut.clone()
+    }
+
+    /// 注册当前配置的快捷键（启动时调用）
+    pub fn register(&self, app: &AppHandle) -> Result<(), String> {
+        let shortcut = parse(&self.shortcut())?;
+        app.global_shortcut().register(shortcut).map_err(|e| e.to_string())
+    }
+
+    /// 更换快捷键：新快捷键注册成功后才注销旧的并保存
+    pub fn set_shortcut(&self, app: &AppHandle, shortcut: String) -> Result<String, String> {
+        let new = parse(&shortcut)?;
+        let mut config = self.config.lock().map_err(|e| e.to_string())?;
+        let old = parse(&config.shortcut).ok();
+        if old == Some(new) {
+            return Ok(config.shortcut.clone());
+        }
+        app.global_shortcut()
+            .register(new)
+            .map_err(|e| format!("Failed to register shortcut {}: {}", shortcut, e))?;
+        if let Some(old) = old {
+            let _ = app.global_shortcut().unregister(old);
+        }
+
+        config.shortcut = shortcut.trim().to_string();
+        self.save(&config)?;
+        Ok(config.shortcut.clone())
+    }
+
+    fn save(&self, config: &PickerConfig) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+        fs::write(&self.path, content).map_err(|e| e.to_string())
+    }
+
+    pub fn paste_options(&self) -> PasteOptions {
+        self.config.lock().unwrap().paste.clone()
+    }
+
+    pub fn set_paste_options(&self, options: PasteOptions) -> Result<PasteOptions, String> {
+        if cfg!(target_os = "macos") && options.keys == PasteKeys::ShiftInsert {
+            return Err("Shift+Insert is not available on macOS".to_string());
+        }
+        let mut config = self.config.lock().map_err(|e| e.to_string())?;
+        config.paste = options;
+        self.save(&config)?;
+        Ok(config.paste.clone())
+    }
+
+    /// 全局快捷键触发时调用；不是选择器的快捷键时返回 false
+    pub fn handle(&self, app: &AppHandle, shortcut: &Shortcut) -> bool {
+        if parse(&self.shortcut()).ok().as_ref() != Some(shortcut) {
+            return false;
+        }
+        if let Err(e) = toggle(app) {
+            log_error!("Failed to open quick picker: {}", e);
+        }
+        true
+    }
+}
+
+pub fn parse(shortcut: &str) -> Result<Shortcut, String> {
+    shortcut
+        .trim()
+        .parse()
+        .map_err(|e| format!("Invalid shortcut '{}': {}", shortcut, e))
+}
+
+/// 显示（首次使用时创建）或隐藏选择器窗口
+pub fn toggle(app: &AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(WINDOW) {
+        if window.is_visible().unwrap_or(false) {
+            return window.hide().map_err(|e| e.to_string());
+        }
+        window.center().map_err(|e| e.to_string())?;
+        window.show().map_err(|e| e.to_string())?;
+        return window.set_focus().map_err(|e| e.to_string());
+    }
+
+    let window = WebviewWindowBuilder::new(app, WINDOW, WebviewUrl::App("index.html#/picker".into()))
+        .title("Quick Picker")
+        .inner_size(640.0, 420.0)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .center()
+        .focused(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    // 失去焦点时自动隐藏，像系统的启动器一样
+    let handle = window.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Focused(false) = event {
+            let _ = handle.hide();
+        }
+    });
+    Ok(())
+}
+
+/// 选中 prompt：复制到剪贴板并关闭选择器；开启了自动粘贴时，
+/// 等焦点回到之前的窗口后模拟粘贴快捷键或逐字键入（macOS 需要授予辅助功能权限）
+pub fn select(app: &AppHandle, item_id: &str, values: &HashMap<String, String>) -> Result<(), String> {
+    let text = clipboard::copy_prompt(app, item_id, values, false)?;
+    if let Some(window) = app.get_webview_window(WINDOW) {
+        window.hide().map_err(|e| e.to_string())?;
+    }
+
+    let options = app.state::<PickerState>().paste_options();
+    if options.mode == PasteMode::Off {
+        return Ok(());
+    }
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(options.delay_ms.unwrap_or(DEFAULT_PASTE_DELAY)));
+        if let Err(e) = send_to_active_window(&options, &text) {
+            log_error!("Failed to paste into the active window: {}", e);
+        }
+    });
+    Ok(())
+}
+
+fn send_to_active_window(options: &PasteOptions, text: &str) -> Result<(), String> {
+    let mut enigo = Enigo::new(&Settings::default()).map_err(|e| e.to_string())?;
+    match options.mode {
+        PasteMode::Off => Ok(()),
+        PasteMode::Type => enigo.text(text).map_err(|e| e.to_string()),
+        PasteMode::Paste => {
+            let (modifiers, key) = paste_keys(options.keys)?;
+            for modifier in &modifiers {
+                enigo.key(*modifier, Direction::Press).map_err(|e| e.to_string())?;
+            }
+            let result = enigo.key(key, Direction::Click);
+            // 无论成功与否都松开修饰键，避免卡住
+            for modifier in modifiers.iter().rev() {
+                let _ = enigo.key(*modifier, Direction::Release);
+            }
+            result.map_err(|e| e.to_string())
+        }
+    }
+}
+
+fn paste_keys(keys: PasteKeys) -> Result<(Vec<Key>, Key), String> {
+    let command = if cfg!(target_os = "macos") {
+        Key::Meta
+    } else {
+        Key::Control
+    };
+    match keys {
+        PasteKeys::Default => Ok((vec![command], Key::Unicode('v'))),
+        PasteKeys::CtrlShiftV => Ok((vec![Key::Control, Key::Shift], Key::Unicode('v'))),
+        #[cfg(not(target_os = "macos"))]
+        PasteKeys::ShiftInsert => Ok((vec![Key::Shift], Key::Insert)),
+        #[cfg(target_os = "macos")]
+        PasteKeys::ShiftInsert => Err("Shift+Insert is not available on macOS".to_string()),
+    }
+}
@@ -0,0 +1,43 @@
+use crate::models::{ItemMetadata, ModelPricing};
+
+/// 内置的默认价格表（美元 / 百万 token），按模型名前缀匹配，越具体的前缀越靠前
+const DEFAULT_PRICING: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4-turbo", 10.0, 30.0),
+    ("gpt-4", 30.0, 60.0),
+    ("gpt-3.5-turbo", 0.5, 1.5),
+    ("o1-mini", 3.0, 12.0),
+    ("o1", 15.0, 60.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3.5-sonnet", 3.0, 15.0),
+    ("claude-3-opus", 15.0, 75.0),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("gemini-1.5-flash", 0.075, 0.3),
+    ("gemini-1.5-pro", 1.25, 5.0),
+    ("deepseek-chat", 0.27, 1.1),
+    ("deepseek-reasoner", 0.55, 2.19),
+];
+
+pub fn default_pricing(model_name: &str) -> Option<ModelPricing> {
+    let lower = model_name.to_lowercase();
+    DEFAULT_PRICING
+        .iter()
+        .find(|(prefix, _, _)| lower.starts_with(prefix))
+        .map(|&(_, input_price, output_price)| ModelPricing {
+            input_price,
+            output_price,
+        })
+}
+
+/// Model 配置中填写的价格优先，缺省部分回退到内置价格表
+pub fn resolve(model_name: &str, metadata: &ItemMetadata) -> Option<ModelPricing> {
+    let default = default_pricing(model_name);
+    let input_price = metadata.input_price.or(default.map(|p| p.input_price))?;
+    let output_price = metadata.output_price.or(default.map(|p| p.output_price))?;
+    Some(ModelPricing {
+        input_price,
+        output_price,
+    })
+}
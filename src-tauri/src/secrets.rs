@@ -0,0 +1,80 @@
+use crate::models::{SecretFinding, TreeItem};
+use crate::template;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// 保存后在 prompt 中发现疑似密钥时发出，负载为 [`SecretFinding`] 列表
+pub const EVENT_DETECTED: &str = "secrets://detected";
+
+/// 疑似密钥的模式：（类型, 正则）
+const PATTERNS: &[(&str, &str)] = &[
+    ("Private key", r"-----BEGIN (?:[A-Z0-9]+ )*PRIVATE KEY(?: BLOCK)?-----"),
+    ("Anthropic API key", r"\bsk-ant-[A-Za-z0-9_\-]{20,}"),
+    ("OpenAI API key", r"\bsk-(?:proj-|svcacct-)?[A-Za-z0-9_\-]{20,}"),
+    ("AWS access key", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+    (
+        "GitHub token",
+        r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})",
+    ),
+    ("Slack token", r"\bxox[abposr]-[A-Za-z0-9\-]{10,}"),
+    ("Google API key", r"\bAIza[0-9A-Za-z_\-]{35}"),
+    ("Stripe secret key", r"\b[rs]k_live_[0-9A-Za-z]{20,}"),
+    ("Bearer token", r"(?i)\bbearer\s+[A-Za-z0-9_\-.=+/]{20,}"),
+];
+
+fn patterns() -> &'static [(&'static str, Regex)] {
+    static COMPILED: OnceLock<Vec<(&'static str, Regex)>> = OnceLock::new();
+    COMPILED.get_or_init(|| {
+        PATTERNS
+            .iter()
+            .map(|(kind, pattern)| (*kind, Regex::new(pattern).expect("invalid secret pattern")))
+            .collect()
+    })
+}
+
+/// 检查条目内容与历史版本中的疑似密钥；同一位置只报告最先匹配的类型
+pub fn scan_item(item: &TreeItem) -> Vec<SecretFinding> {
+    let mut findings = scan(item, None, item.content.as_deref().unwrap_or_default());
+    for version in item.versions.iter().flatten() {
+        findings.extend(scan(item, Some(&version.id), &version.content));
+    }
+    findings
+}
+
+fn scan(item: &TreeItem, version_id: Option<&str>, content: &str) -> Vec<SecretFinding> {
+    let mut found: Vec<(usize, usize, &str)> = Vec::new();
+    for (kind, regex) in patterns() {
+        for m in regex.find_iter(content) {
+            if !found.iter().any(|(start, end, _)| m.start() < *end && *start < m.end()) {
+                found.push((m.start(), m.end(), kind));
+            }
+        }
+    }
+    found.sort_by_key(|(start, _, _)| *start);
+    found
+        .into_iter()
+        .map(|(start, end, kind)| {
+            let (line, column) = template::line_column(content, start);
+            SecretFinding {
+                item_id: item.id.clone(),
+                item_name: item.name.clone(),
+                version_id: version_id.map(str::to_string),
+                kind: kind.to_string(),
+                line,
+                column,
+                preview: mask(&content[start..end]),
+            }
+        })
+        .collect()
+}
+
+/// 只保留开头与结尾几个字符，避免在结果中再次暴露密钥
+fn mask(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() <= 10 {
+        return "*".repeat(chars.len());
+    }
+    let head: String = chars[..6].iter().collect();
+    let tail: String = chars[chars.len() - 2..].iter().collect();
+    format!("{}…{}", head, tail)
+}
@@ -0,0 +1,73 @@
+use crate::models::{ItemContent, SearchResult, TreeItem};
+use std::collections::HashSet;
+
+/// 敏感条目在列表与搜索结果中代替内容的占位文本
+pub const REDACTED: &str = "••••••••";
+
+pub fn is_sensitive(item: &TreeItem) -> bool {
+    item.metadata.sensitive == Some(true)
+}
+
+/// 把敏感条目（含子树中的）的内容与历史版本替换为占位文本，防止屏幕共享时泄露；
+/// 需要时用 reveal_content 读取原文
+pub fn redact(item: &mut TreeItem) {
+    if is_sensitive(item) {
+        if item.content.is_some() {
+            item.content = Some(REDACTED.into());
+        }
+        for version in item.versions.iter_mut().flatten() {
+            version.content = REDACTED.into();
+        }
+    }
+    for child in &mut item.children {
+        redact(child);
+    }
+}
+
+/// 命令返回条目前调用，与 get_item 一致
+pub fn redacted(mut item: TreeItem) -> TreeItem {
+    redact(&mut item);
+    item
+}
+
+pub fn redacted_all(mut items: Vec<TreeItem>) -> Vec<TreeItem> {
+    items.iter_mut().for_each(redact);
+    items
+}
+
+pub fn redact_content(content: &mut ItemContent) {
+    if content.content.is_some() {
+        content.content = Some(REDACTED.into());
+    }
+    for version in content.versions.iter_mut().flatten() {
+        version.content = REDACTED.into();
+    }
+}
+
+/// 敏感条目的搜索结果只保留名称，命中的行替换为占位文本
+pub fn redact_results(results: &mut [SearchResult], sensitive: &HashSet<String>) {
+    for result in results.iter_mut().filter(|r| sensitive.contains(&r.item_id)) {
+        for m in &mut result.matches {
+            m.line_content = REDACTED.to_string();
+            m.start_column = 1;
+            m.end_column = 1;
+        }
+    }
+}
+
+/// 界面保存的仍是占位文本时保留原内容与历史版本，避免覆盖
+pub fn restore(existing: &TreeItem, updates: &mut TreeItem) {
+    if !is_sensitive(existing) {
+        return;
+    }
+    if updates.content.as_deref() == Some(REDACTED) {
+        updates.content = existing.content.clone();
+    }
+    for version in updates.versions.iter_mut().flatten() {
+        if &*version.content == REDACTED {
+            if let Some(original) = existing.versions.iter().flatten().find(|v| v.id == version.id) {
+                version.content = original.content.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::errors::{Error, ErrorCode};
+use crate::locale;
+use crate::models::{AppSettings, SettingsPatch};
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// 设置修改后发出，负载为新的 [`AppSettings`]，供其他窗口同步
+pub const EVENT_CHANGED: &str = "settings://changed";
+const FONT_SIZE_RANGE: RangeInclusive<u32> = 8..=48;
+/// 维护间隔的范围（小时），最长 30 天
+const MAINTENANCE_INTERVAL_RANGE: RangeInclusive<u32> = 1..=720;
+
+/// 应用设置，保存在 settings.json
+pub struct SettingsState {
+    settings: Mutex<AppSettings>,
+    path: PathBuf,
+}
+
+impl SettingsState {
+    pub fn new(dir: PathBuf) -> Self {
+        let path = dir.join("settings.json");
+        let settings: AppSettings = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        locale::apply(settings.language, settings.timezone.as_deref());
+        SettingsState {
+            settings: Mutex::new(settings),
+            path,
+        }
+    }
+
+    pub fn settings(&self) -> AppSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// 合并修改并保存；任一字段无效时整体不生效
+    pub fn update(&self, patch: SettingsPatch) -> Result<AppSettings, Error> {
+        let mut current = self.settings.lock().map_err(|e| e.to_string())?;
+        let mut settings = current.clone();
+        if let Some(theme) = patch.theme {
+            settings.theme = theme;
+        }
+        if let Some(language) = patch.language {
+            settings.language = language;
+        }
+        if let Some(size) = patch.editor_font_size {
+            if !FONT_SIZE_RANGE.contains(&size) {
+                return Err(Error::new(
+                    ErrorCode::FontSizeOutOfRange,
+                    vec![FONT_SIZE_RANGE.start().to_string(), FONT_SIZE_RANGE.end().to_string()],
+                ));
+            }
+            settings.editor_font_size = size;
+        }
+        if let Some(family) = patch.editor_font_family {
+            let family = family.trim();
+            if family.is_empty() {
+                return Err(ErrorCode::FontFamilyRequired.into());
+            }
+            settings.editor_font_family = family.to_string();
+        }
+        if let Some(wrap) = patch.editor_word_wrap {
+            settings.editor_word_wrap = wrap;
+        }
+        if let Some(style) = patch.title_bar_style {
+            settings.title_bar_style = style;
+        }
+        if let Some(timezone) = patch.timezone {
+            let timezone = timezone.trim();
+            settings.timezone = if timezone.is_empty() {
+                None
+            } else {
+                locale::parse_timezone(timezone)?;
+                Some(timezone.to_string())
+            };
+        }
+        if let Some(maintenance) = patch.maintenance {
+            if !MAINTENANCE_INTERVAL_RANGE.contains(&maintenance.interval_hours) {
+                return Err(Error::new(
+                    ErrorCode::MaintenanceIntervalOutOfRange,
+                    vec![
+                        MAINTENANCE_INTERVAL_RANGE.start().to_string(),
+                        MAINTENANCE_INTERVAL_RANGE.end().to_string(),
+                    ],
+                ));
+            }
+            if maintenance.keep_auto_versions == Some(0) {
+                return Err(ErrorCode::NoAutoVersionsKept.into());
+            }
+            settings.maintenance = maintenance;
+        }
+
+        let content = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+        fs::write(&self.path, content).map_err(|e| e.to_string())?;
+        locale::apply(settings.language, settings.timezone.as_deref());
+        *current = settings.clone();
+        Ok(settings)
+    }
+}
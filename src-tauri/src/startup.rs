@@ -0,0 +1,61 @@
+use crate::models::StartupConfig;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_autostart::ManagerExt;
+
+/// 开机自动启动时传给应用的参数，启动时据此最小化到托盘
+pub const MINIMIZED_ARG: &str = "--minimized";
+
+/// 启动选项，保存在 startup.json；开机启动同时注册到系统
+pub struct StartupState {
+    config: Mutex<StartupConfig>,
+    path: PathBuf,
+}
+
+impl StartupState {
+    pub fn new(dir: PathBuf) -> Self {
+        let path = dir.join("startup.json");
+        let config = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        StartupState {
+            config: Mutex::new(config),
+            path,
+        }
+    }
+
+    /// 当前设置；开机启动以系统中的实际状态为准（用户可能在系统设置里关掉）
+    pub fn config(&self, app: &AppHandle) -> StartupConfig {
+        let mut config = self.config.lock().unwrap().clone();
+        if let Ok(enabled) = app.autolaunch().is_enabled() {
+            config.autostart = enabled;
+        }
+        config
+    }
+
+    pub fn set_config(&self, app: &AppHandle, config: StartupConfig) -> Result<StartupConfig, String> {
+        let autolaunch = app.autolaunch();
+        if autolaunch.is_enabled().ok() != Some(config.autostart) {
+            let result = if config.autostart {
+                autolaunch.enable()
+            } else {
+                autolaunch.disable()
+            };
+            result.map_err(|e| format!("Failed to update autostart: {}", e))?;
+        }
+
+        let mut current = self.config.lock().map_err(|e| e.to_string())?;
+        *current = config.clone();
+        let content = serde_json::to_string_pretty(&*current).map_err(|e| e.to_string())?;
+        fs::write(&self.path, content).map_err(|e| e.to_string())?;
+        Ok(config)
+    }
+
+    /// 启动时是否隐藏主窗口：设置了启动最小化，或由开机启动拉起
+    pub fn start_hidden(&self) -> bool {
+        self.config.lock().unwrap().start_minimized || std::env::args().any(|arg| arg == MINIMIZED_ARG)
+    }
+}
@@ -0,0 +1,125 @@
+use crate::locale;
+use crate::models::{ItemType, LibraryStats, PromptSize, TagCount, TextStats, WeeklyCount};
+use crate::tree::Tree;
+use std::collections::{BTreeMap, HashMap};
+
+/// 统计最近多少周的修改
+const WEEKS: i64 = 12;
+const LARGEST_COUNT: usize = 10;
+const WEEK_MILLIS: i64 = 7 * 24 * 60 * 60 * 1000;
+/// 估计阅读时间用的阅读速度（词 / 分钟）
+const WORDS_PER_MINUTE: usize = 230;
+
+pub fn library_stats(trees: &[&Tree]) -> LibraryStats {
+    let mut counts = BTreeMap::new();
+    let mut tags: HashMap<&str, usize> = HashMap::new();
+    let mut lengths = Vec::new();
+    let mut modified = Vec::new();
+    let mut prompts = Vec::new();
+
+    for tree in trees {
+        tree.walk(|node, path| {
+            *counts.entry(node.item_type.label().to_string()).or_insert(0) += 1;
+            for tag in node.metadata.tags.iter().flatten() {
+                *tags.entry(tag.as_str()).or_insert(0) += 1;
+            }
+            if let Some(timestamp) = node.metadata.last_modified {
+                modified.push(timestamp);
+            }
+            if !matches!(node.item_type, ItemType::Prompt | ItemType::Snippet) {
+                return;
+            }
+            let length = node.content.as_deref().unwrap_or_default().chars().count();
+            lengths.push(length);
+            if node.item_type == ItemType::Prompt {
+                prompts.push(PromptSize {
+                    id: node.id.clone(),
+                    name: node.name.clone(),
+                    path: path.to_string(),
+                    length,
+                });
+            }
+        });
+    }
+
+    let mut tags: Vec<TagCount> = tags
+        .into_iter()
+        .map(|(tag, count)| TagCount {
+            tag: tag.to_string(),
+            count,
+        })
+        .collect();
+    tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+
+    prompts.sort_by(|a, b| b.length.cmp(&a.length));
+    prompts.truncate(LARGEST_COUNT);
+
+    let total: usize = lengths.iter().sum();
+    LibraryStats {
+        counts,
+        total_content_length: total,
+        average_content_length: if lengths.is_empty() { 0 } else { total / lengths.len() },
+        tags,
+        weekly_modified: weekly(&modified),
+        largest_prompts: prompts,
+    }
+}
+
+/// 按周汇总修改时间，最后一周为本周
+fn weekly(timestamps: &[i64]) -> Vec<WeeklyCount> {
+    let current = locale::period_start("week").unwrap_or_default();
+    let first = current - (WEEKS - 1) * WEEK_MILLIS;
+
+    let mut weeks: Vec<WeeklyCount> = (0..WEEKS)
+        .map(|i| WeeklyCount {
+            week_start: first + i * WEEK_MILLIS,
+            count: 0,
+        })
+        .collect();
+    for timestamp in timestamps {
+        if *timestamp >= first {
+            let index = ((timestamp - first) / WEEK_MILLIS).min(WEEKS - 1) as usize;
+            weeks[index].count += 1;
+        }
+    }
+    weeks
+}
+
+/// 条目的文字统计；文件夹统计其下全部 prompt 与片段
+pub fn text_stats(tree: &Tree, id: &str) -> Option<TextStats> {
+    tree.node(id)?;
+    let mut stats = TextStats::default();
+    for id in tree.subtree_ids(id) {
+        let Some(node) = tree.node(&id) else { continue };
+        if matches!(node.item_type, ItemType::Prompt | ItemType::Snippet) {
+            count_text(&mut stats, node.content.as_deref().unwrap_or_default());
+            stats.item_count += 1;
+        }
+    }
+    stats.reading_seconds = (stats.words * 60).div_ceil(WORDS_PER_MINUTE) as u64;
+    Some(stats)
+}
+
+fn count_text(stats: &mut TextStats, content: &str) {
+    stats.characters += content.chars().count();
+    stats.characters_no_spaces += content.chars().filter(|c| !c.is_whitespace()).count();
+    stats.lines += content.lines().count();
+    for word in content.split_whitespace() {
+        let cjk = word.chars().filter(|c| is_cjk(*c)).count();
+        // 除中日韩文字外还有字母或数字（例如夹在中文里的英文单词）时另计一个词
+        let rest = word.chars().any(|c| !is_cjk(c) && c.is_alphanumeric());
+        stats.words += cjk + rest as usize;
+    }
+}
+
+/// 假名、汉字（含扩展 A 与兼容汉字）与韩文
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3040}'..='\u{30ff}'
+            | '\u{3400}'..='\u{4dbf}'
+            | '\u{4e00}'..='\u{9fff}'
+            | '\u{ac00}'..='\u{d7af}'
+            | '\u{f900}'..='\u{faff}'
+    )
+}
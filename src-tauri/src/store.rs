@@ -1,112 +1,844 @@
-use crate::models::{ItemType, SearchFilters, SearchMatch, SearchResult, TreeItem};
+use crate::audit::AuditLog;
+use crate::encryption::{self, Key};
+use crate::errors::{Error, ErrorCode};
+use crate::log_error;
+use crate::models::{
+    AuditEntry, AuditFilters, ChangeKind, ChildSort, ChildrenPage, DuplicateGroup, EncryptionStatus, IntegrityIssue,
+    IntegrityIssueKind, ItemContent, ItemMetadata, ItemSummary, ItemType, LibraryStats, LightItem, MountedLibrary,
+    PerfStats, PromptVersion, QuickMatch, SearchFilters, SearchMatch, SearchResult, SecretFinding, StoreChange,
+    TemplateIssue, TextStats, TreeItem, VectorClock,
+};
+use crate::perf::PerfLog;
+use crate::sync::crdt;
+use crate::tree::Tree;
+use crate::{diagnostics, duplicates, locale, mounts, secrets, sensitive, stats, template};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
 use tauri::{AppHandle, Manager};
 
+/// 应用数据目录（不存在时自动创建）
+pub fn data_dir(app_handle: &AppHandle) -> PathBuf {
+    let app_data_path = app_handle
+        .path()
+        .app_data_dir()
+        .expect("Failed to resolve app data dir");
+    ensure_dir(app_data_path.join("com.prompt-manager.app"))
+}
+
+/// 不启动窗口时（命令行）的数据目录，与 [`data_dir`] 相同
+pub fn default_data_dir() -> PathBuf {
+    let app_data_path = dirs::data_dir()
+        .expect("Failed to resolve app data dir")
+        .join("com.prompt-manager.app");
+    ensure_dir(app_data_path.join("com.prompt-manager.app"))
+}
+
+fn ensure_dir(store_dir: PathBuf) -> PathBuf {
+    // Ensure directory exists
+    if !store_dir.exists() {
+        fs::create_dir_all(&store_dir).expect("Failed to create app data directory");
+    }
+    store_dir
+}
+
+/// 本机的设备 id（保存在数据目录中，不随 store.json 同步），用于向量时钟
+fn device_id(dir: &Path) -> String {
+    let path = dir.join("device_id");
+    if let Some(id) = fs::read_to_string(&path).ok().map(|id| id.trim().to_string()).filter(|id| !id.is_empty()) {
+        return id;
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    let _ = fs::write(&path, &id);
+    id
+}
+
+/// store.json 的内容：条目树与已删除条目的墓碑
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StoreFile {
+    pub items: Vec<TreeItem>,
+    #[serde(default)]
+    pub tombstones: BTreeMap<String, VectorClock>,
+}
+
+/// 兼容旧版本只保存条目数组的格式
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoreFormat {
+    Current(StoreFile),
+    Legacy(Vec<TreeItem>),
+}
+
+pub fn parse_store_file(content: &str) -> Result<StoreFile, String> {
+    match serde_json::from_str(content).map_err(|e| e.to_string())? {
+        StoreFormat::Current(file) => Ok(file),
+        StoreFormat::Legacy(items) => Ok(StoreFile {
+            items,
+            tombstones: BTreeMap::new(),
+        }),
+    }
+}
+
+/// 收集子树中全部条目的 id（先父后子）
+fn collect_ids(nodes: &[TreeItem], ids: &mut impl Extend<String>) {
+    for node in nodes {
+        ids.extend([node.id.clone()]);
+        collect_ids(&node.children, ids);
+    }
+}
+
+/// 每次库变更后发出的事件，负载为 [`StoreChange`]
+pub const EVENT_CHANGED: &str = "items://changed";
+/// 后台写入 store.json 失败时发出的事件，负载为错误信息
+pub const EVENT_SAVE_FAILED: &str = "store://save-failed";
+/// 并行搜索时每个任务至少处理的条目数，避免小库拆分过细
+const SEARCH_CHUNK: usize = 256;
+
+type ChangeListener = Box<dyn Fn(&Store, &StoreChange) + Send + Sync>;
+type SaveErrorHandler = Box<dyn Fn(&str) + Send + Sync>;
+
+enum WriteRequest {
+    Save(StoreFile),
+    /// 写完之前排队的内容后回复
+    Flush(Sender<()>),
+}
+
+/// 写入 store.json 的后台线程：保存请求排队期间只写最后一份快照，
+/// 先写临时文件再替换，避免写到一半时被读取
+fn spawn_writer(
+    path: PathBuf,
+    last_written: Arc<Mutex<String>>,
+    on_error: Arc<Mutex<Option<SaveErrorHandler>>>,
+    key: Arc<Mutex<Option<Key>>>,
+    perf: Arc<PerfLog>,
+) -> Sender<WriteRequest> {
+    let (sender, receiver) = mpsc::channel::<WriteRequest>();
+    std::thread::spawn(move || {
+        while let Ok(first) = receiver.recv() {
+            let mut latest = None;
+            let mut waiters = Vec::new();
+            for request in std::iter::once(first).chain(receiver.try_iter()) {
+                match request {
+                    WriteRequest::Save(file) => latest = Some(file),
+                    WriteRequest::Flush(ack) => waiters.push(ack),
+                }
+            }
+            if let Some(file) = latest {
+                if let Err(e) = perf.time("save", || write_file(&path, &file, &last_written, &key)) {
+                    log_error!("Failed to save store: {}", e);
+                    if let Ok(handler) = on_error.lock() {
+                        if let Some(handler) = handler.as_ref() {
+                            handler(&e);
+                        }
+                    }
+                }
+            }
+            for ack in waiters {
+                let _ = ack.send(());
+            }
+        }
+    });
+    sender
+}
+
+fn write_file(
+    path: &Path,
+    file: &StoreFile,
+    last_written: &Mutex<String>,
+    key: &Mutex<Option<Key>>,
+) -> Result<(), String> {
+    let content = encode(file, key)?;
+    // 先记下内容，监听到这次写入时不会当作外部修改
+    *last_written.lock().map_err(|e| e.to_string())? = content.clone();
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, &content).map_err(|e| e.to_string())?;
+    fs::rename(&temp, path).map_err(|e| e.to_string())
+}
+
+/// store.json 的文本；启用了加密时为加密后的内容
+fn encode(file: &StoreFile, key: &Mutex<Option<Key>>) -> Result<String, String> {
+    let content = serde_json::to_string_pretty(file).map_err(|e| e.to_string())?;
+    match key.lock().map_err(|e| e.to_string())?.as_ref() {
+        Some(key) => key.encrypt(content.as_bytes()),
+        None => Ok(content),
+    }
+}
+
 pub struct Store {
-    pub data: Mutex<Vec<TreeItem>>,
+    /// 按 id 索引的条目树；保存时还原为嵌套的 store.json。
+    /// 读写锁：多个窗口的读取（获取、搜索）可以并行，只有修改互斥
+    data: RwLock<Tree>,
     path: PathBuf,
+    device: String,
+    /// 已删除条目 id -> 删除时的向量时钟，合并其他设备的修改时避免条目复活
+    tombstones: Mutex<BTreeMap<String, VectorClock>>,
+    /// 最近一次写入 store.json 的内容，用于区分外部修改与自己的写入
+    last_written: Arc<Mutex<String>>,
+    writer: Mutex<Sender<WriteRequest>>,
+    on_save_error: Arc<Mutex<Option<SaveErrorHandler>>>,
+    /// 每次变更保存后调用（同步、事件等）
+    listeners: Mutex<Vec<ChangeListener>>,
+    /// 只读挂载库的配置，保存在 mounts.json
+    mounts: Mutex<Vec<MountedLibrary>>,
+    mounts_path: PathBuf,
+    /// 挂载库（各自的根节点在树根下），不写入 store.json，也不参与同步
+    mounted: RwLock<Tree>,
+    perf: Arc<PerfLog>,
+    /// 启用加密时由口令派生的密钥，写入 store.json 前用它加密
+    key: Arc<Mutex<Option<Key>>>,
+    /// store.json 已加密但还没有输入口令：库为空，且不允许保存，避免覆盖加密的内容
+    locked: Mutex<bool>,
+    audit: AuditLog,
 }
 
 impl Store {
     pub fn new(app_handle: &AppHandle) -> Self {
-        let app_data_path = app_handle
-            .path()
-            .app_data_dir()
-            .expect("Failed to resolve app data dir");
-        let store_dir = app_data_path.join("com.prompt-manager.app");
+        Self::open(&data_dir(app_handle))
+    }
+
+    /// 打开数据目录中的 store.json
+    pub fn open(dir: &Path) -> Self {
+        let path = dir.join("store.json");
+        let device = device_id(dir);
+        let mounts_path = dir.join("mounts.json");
+        let mount_list = mounts::load_config(&mounts_path);
+        let mounted = mount_list.iter().map(mounts::load).collect();
+
+        let perf = Arc::new(PerfLog::default());
+        // Load initial data；加密的 store.json 要在 unlock 之后才能读取
+        let (file, locked) = perf.time("load", || {
+            let content = fs::read_to_string(&path).unwrap_or_else(|_| "[]".to_string());
+            if encryption::is_encrypted(&content) {
+                (StoreFile::default(), true)
+            } else {
+                (parse_store_file(&content).unwrap_or_default(), false)
+            }
+        });
+
+        let last_written = Arc::new(Mutex::new(String::new()));
+        let on_save_error = Arc::new(Mutex::new(None));
+        let key = Arc::new(Mutex::new(None));
+        let writer = spawn_writer(
+            path.clone(),
+            last_written.clone(),
+            on_save_error.clone(),
+            key.clone(),
+            perf.clone(),
+        );
 
-        // Ensure directory exists
-        if !store_dir.exists() {
-            fs::create_dir_all(&store_dir).expect("Failed to create app data directory");
+        Store {
+            data: RwLock::new(Tree::new(file.items)),
+            path,
+            device,
+            tombstones: Mutex::new(file.tombstones),
+            last_written,
+            writer: Mutex::new(writer),
+            on_save_error,
+            listeners: Mutex::new(Vec::new()),
+            mounts: Mutex::new(mount_list),
+            mounts_path,
+            mounted: RwLock::new(Tree::new(mounted)),
+            perf,
+            key,
+            locked: Mutex::new(locked),
+            audit: AuditLog::new(dir),
         }
+    }
 
-        let path = store_dir.join("store.json");
+    pub fn subscribe(&self, listener: impl Fn(&Store, &StoreChange) + Send + Sync + 'static) {
+        self.listeners.lock().unwrap().push(Box::new(listener));
+    }
 
-        // Load initial data
-        let data = if path.exists() {
-            let content = fs::read_to_string(&path).unwrap_or_else(|_| "[]".to_string());
-            serde_json::from_str(&content).unwrap_or_else(|_| Vec::new())
+    /// 后台写入失败时调用
+    pub fn on_save_error(&self, handler: impl Fn(&str) + Send + Sync + 'static) {
+        *self.on_save_error.lock().unwrap() = Some(Box::new(handler));
+    }
+
+    /// 保存并通知监听者
+    fn commit(&self, kind: ChangeKind, ids: Vec<String>, summary: String) -> Result<(), Error> {
+        self.save()?;
+        let change = StoreChange { kind, ids, summary };
+        if let Err(e) = self.audit.record(&self.device, &change) {
+            log_error!("Failed to write audit log: {}", e);
+        }
+        for listener in self.listeners.lock().map_err(|e| e.to_string())?.iter() {
+            listener(self, &change);
+        }
+        Ok(())
+    }
+
+    /// 用新的条目树替换整个库（例如同步拉取之后）
+    pub fn replace_all(&self, items: Vec<TreeItem>, summary: String) -> Result<(), Error> {
+        self.ensure_writable(&[])?;
+        let mut data = self.data.write().map_err(|e| e.to_string())?;
+        let mut kept = HashSet::new();
+        collect_ids(&items, &mut kept);
+        let mut tombstones = self.tombstones.lock().map_err(|e| e.to_string())?;
+        // 子节点可能被移到了别处，逐个判断
+        for removed in data.items().filter(|item| !kept.contains(&item.id)) {
+            crdt::bury(removed, &self.device, &mut tombstones);
+        }
+        drop(tombstones);
+        *data = Tree::new(items);
+        drop(data);
+        self.commit(ChangeKind::Replaced, Vec::new(), summary)
+    }
+
+    /// 把当前快照交给后台线程写入；写入失败通过 [`Store::on_save_error`] 报告
+    pub fn save(&self) -> Result<(), Error> {
+        let file = self.snapshot()?;
+        self.writer
+            .lock()
+            .map_err(|e| e.to_string())?
+            .send(WriteRequest::Save(file))
+            .map_err(|_| "Store writer has stopped".into())
+    }
+
+    fn snapshot(&self) -> Result<StoreFile, Error> {
+        if self.is_locked() {
+            return Err(ErrorCode::StoreLocked.into());
+        }
+        let data = self.data.read().map_err(|e| e.to_string())?;
+        Ok(StoreFile {
+            items: data.to_items(),
+            tombstones: self.tombstones.lock().map_err(|e| e.to_string())?.clone(),
+        })
+    }
+
+    /// 等待排队中的保存写入磁盘（退出前调用）
+    pub fn flush(&self) {
+        let (ack, done) = mpsc::channel();
+        let sent = self.writer.lock().map(|w| w.send(WriteRequest::Flush(ack)).is_ok());
+        if sent.unwrap_or(false) {
+            let _ = done.recv();
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 修改记录，最新的在前
+    pub fn audit_log(&self, filters: &AuditFilters) -> Vec<AuditEntry> {
+        self.audit.query(filters)
+    }
+
+    /// 最近操作的耗时、条目数量与 store.json 大小
+    pub fn perf_stats(&self) -> PerfStats {
+        let data = self.data.read().unwrap();
+        let count = |item_type: ItemType| data.items().filter(|i| i.item_type == item_type).count();
+        PerfStats {
+            operations: self.perf.stats(),
+            item_count: data.items().count(),
+            prompt_count: count(ItemType::Prompt),
+            folder_count: count(ItemType::Folder),
+            mounted_item_count: self.mounted.read().unwrap().items().count(),
+            store_size: fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0),
+        }
+    }
+
+    /// 条目数量、内容长度、标签分布与最近的修改情况（含挂载库）
+    pub fn library_stats(&self) -> LibraryStats {
+        let data = self.data.read().unwrap();
+        let mounted = self.mounted.read().unwrap();
+        stats::library_stats(&[&data, &mounted])
+    }
+
+    pub fn text_stats(&self, id: &str) -> Option<TextStats> {
+        stats::text_stats(&self.tree_of(id), id)
+    }
+
+    /// 检查 store.json 能否读取解析，以及本地库与挂载库的结构
+    pub fn check_integrity(&self) -> Vec<IntegrityIssue> {
+        let mut issues = Vec::new();
+        let file_error = match fs::read_to_string(&self.path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => Some(e.to_string()),
+            Ok(content) if encryption::is_encrypted(&content) => match &*self.key.lock().unwrap() {
+                Some(key) => key.decrypt(&content).err().map(|e| e.to_string()),
+                None => Some("store.json is encrypted and the store is locked; contents not checked".to_string()),
+            },
+            Ok(content) => parse_store_file(&content).err(),
+        };
+        if let Some(message) = file_error {
+            issues.push(IntegrityIssue {
+                kind: IntegrityIssueKind::StoreFile,
+                item_id: None,
+                message,
+            });
+        }
+        issues.extend(diagnostics::check_tree(&self.data.read().unwrap()));
+        issues.extend(diagnostics::check_tree(&self.mounted.read().unwrap()));
+        issues
+    }
+
+    /// 清理自动保存的历史版本（没有标签的版本）：每个条目只保留最新的 keep 个，并删除早于 max_age_days 天的；
+    /// 有标签的版本始终保留。返回删除的版本数
+    pub fn prune_versions(&self, keep: Option<usize>, max_age_days: Option<u32>) -> Result<usize, Error> {
+        if keep.is_none() && max_age_days.is_none() {
+            return Ok(0);
+        }
+        if self.is_locked() {
+            return Err(ErrorCode::StoreLocked.into());
+        }
+        let cutoff = max_age_days.map(|days| chrono::Utc::now().timestamp_millis() - days as i64 * 86_400_000);
+        let mut data = self.data.write().map_err(|e| e.to_string())?;
+        let ids: Vec<String> = data
+            .items()
+            .filter(|i| i.versions.as_ref().is_some_and(|v| !v.is_empty()))
+            .map(|i| i.id.clone())
+            .collect();
+
+        let mut pruned = 0;
+        let mut changed = Vec::new();
+        for id in ids {
+            let Some(node) = data.node_mut(&id) else { continue };
+            let Some(versions) = node.versions.as_mut() else { continue };
+            let mut auto: Vec<&PromptVersion> = versions
+                .iter()
+                .filter(|v| !v.label.as_deref().is_some_and(|l| !l.trim().is_empty()))
+                .collect();
+            auto.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+            let remove: HashSet<String> = auto
+                .into_iter()
+                .enumerate()
+                .filter(|(i, v)| keep.is_some_and(|k| *i >= k) || cutoff.is_some_and(|c| v.timestamp < c))
+                .map(|(_, v)| v.id.clone())
+                .collect();
+            if remove.is_empty() {
+                continue;
+            }
+            versions.retain(|v| !remove.contains(&v.id));
+            pruned += remove.len();
+            crdt::tick(node, &self.device);
+            changed.push(id);
+        }
+
+        drop(data);
+        if !changed.is_empty() {
+            self.commit(ChangeKind::Updated, changed, format!("Prune {} auto-saved versions", pruned))?;
+        }
+        Ok(pruned)
+    }
+
+    /// 内容相同或相近的 prompt 分组（不含挂载库）；threshold 为 0 到 1 之间的相似度下限
+    pub fn find_duplicates(&self, threshold: f64) -> Result<Vec<DuplicateGroup>, Error> {
+        if threshold.is_nan() || threshold <= 0.0 || threshold > 1.0 {
+            return Err(ErrorCode::InvalidThreshold.into());
+        }
+        let data = self.data.read().map_err(|e| e.to_string())?;
+        Ok(duplicates::find_duplicates(&data, threshold))
+    }
+
+    /// 把另一份 store.json（网盘同步来的新版本或冲突副本）按向量时钟合并进当前库
+    pub fn merge_file(&self, path: &Path) -> Result<(), Error> {
+        // 锁定时无法合并，返回错误使冲突副本保留到解锁之后
+        if self.is_locked() {
+            return Err(ErrorCode::StoreLocked.into());
+        }
+        // 文件可能正在被替换，下一次事件时再读取
+        let Ok(content) = fs::read_to_string(path) else {
+            return Ok(());
+        };
+        if *self.last_written.lock().map_err(|e| e.to_string())? == content {
+            return Ok(());
+        }
+        let remote = if encryption::is_encrypted(&content) {
+            let key = self.key.lock().map_err(|e| e.to_string())?;
+            let key = key.as_ref().ok_or_else(|| "store.json is encrypted".to_string())?;
+            parse_store_file(&String::from_utf8_lossy(&key.decrypt(&content)?))?
         } else {
-            // Seed data if empty (could be moved to a separate init function)
-            Vec::new()
+            parse_store_file(&content)?
         };
 
-        Store {
-            data: Mutex::new(data),
-            path,
+        let mut data = self.data.write().map_err(|e| e.to_string())?;
+        let mut tombstones = self.tombstones.lock().map_err(|e| e.to_string())?;
+        let local = StoreFile {
+            items: data.to_items(),
+            tombstones: tombstones.clone(),
+        };
+        let merged = crdt::merge(&local, &remote);
+        let changed = serde_json::to_value(&merged.items).ok() != serde_json::to_value(&local.items).ok();
+        *data = Tree::new(merged.items);
+        *tombstones = merged.tombstones;
+        drop(tombstones);
+        drop(data);
+
+        if changed {
+            self.commit(ChangeKind::Replaced, Vec::new(), "Merge external changes to store.json".to_string())
+        } else {
+            // 本地已包含对方的全部修改，写回使文件也包含本地的修改
+            self.save()
+        }
+    }
+
+    /// store.json 已加密但还没有解锁
+    pub fn is_locked(&self) -> bool {
+        *self.locked.lock().unwrap()
+    }
+
+    pub fn encryption_status(&self) -> EncryptionStatus {
+        EncryptionStatus {
+            enabled: self.is_locked() || self.key.lock().unwrap().is_some(),
+            locked: self.is_locked(),
+        }
+    }
+
+    /// 用口令解密并载入 store.json
+    pub fn unlock(&self, passphrase: &str) -> Result<(), Error> {
+        let mut locked = self.locked.lock().map_err(|e| e.to_string())?;
+        if !*locked {
+            return Ok(());
         }
+        let content = fs::read_to_string(&self.path).map_err(|e| e.to_string())?;
+        let (key, plaintext) = encryption::open(passphrase, &content)?;
+        let file = parse_store_file(&String::from_utf8_lossy(&plaintext))?;
+        *self.data.write().map_err(|e| e.to_string())? = Tree::new(file.items);
+        *self.tombstones.lock().map_err(|e| e.to_string())? = file.tombstones;
+        *self.last_written.lock().map_err(|e| e.to_string())? = content;
+        *self.key.lock().map_err(|e| e.to_string())? = Some(key);
+        *locked = false;
+        Ok(())
     }
 
-    pub fn save(&self) -> Result<(), String> {
-        let data = self.data.lock().map_err(|e| e.to_string())?;
-        let content = serde_json::to_string_pretty(&*data).map_err(|e| e.to_string())?;
-        fs::write(&self.path, content).map_err(|e| e.to_string())?;
+    /// 启用加密：之后 store.json 只以加密形式写入磁盘
+    pub fn enable_encryption(&self, passphrase: &str) -> Result<(), Error> {
+        if self.encryption_status().enabled {
+            return Err(ErrorCode::EncryptionEnabled.into());
+        }
+        *self.key.lock().map_err(|e| e.to_string())? = Some(Key::generate(passphrase)?);
+        self.save()?;
+        self.flush();
+        Ok(())
+    }
+
+    /// 更换口令：用新口令派生的密钥重新加密 store.json
+    pub fn change_passphrase(&self, current: &str, new: &str) -> Result<(), Error> {
+        self.replace_key(current, Some(new))
+    }
+
+    /// 关闭加密，store.json 重新以明文保存
+    pub fn disable_encryption(&self, passphrase: &str) -> Result<(), Error> {
+        self.replace_key(passphrase, None)
+    }
+
+    fn replace_key(&self, current: &str, new: Option<&str>) -> Result<(), Error> {
+        if self.is_locked() {
+            return Err(ErrorCode::StoreLocked.into());
+        }
+        let mut key = self.key.lock().map_err(|e| e.to_string())?;
+        match key.as_ref() {
+            None => return Err(ErrorCode::EncryptionDisabled.into()),
+            Some(key) if !key.verify(current) => return Err(ErrorCode::WrongPassphrase.into()),
+            Some(_) => {}
+        }
+        *key = new.map(Key::generate).transpose()?;
+        drop(key);
+        self.save()?;
+        self.flush();
+        Ok(())
+    }
+
+    /// 把当前库写入备份文件；启用了加密时备份同样是加密的
+    pub fn backup(&self, destination: &Path) -> Result<(), Error> {
+        let content = encode(&self.snapshot()?, &self.key)?;
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(destination, content).map_err(|e| e.to_string())?;
         Ok(())
     }
 
     pub fn get_all(&self) -> Vec<TreeItem> {
-        self.data.lock().unwrap().clone()
+        self.data.read().unwrap().to_items()
     }
 
-    pub fn get_item(&self, id: &str) -> Option<TreeItem> {
-        let data = self.data.lock().unwrap();
-        Self::find_node_recursive(&data, id).cloned()
+    /// 界面显示的完整树：本地库之后是各个只读挂载库
+    pub fn get_tree(&self) -> Vec<TreeItem> {
+        self.perf.time("tree-fetch", || {
+            let mut items = self.get_all();
+            items.extend(self.mounted.read().unwrap().to_items());
+            items
+        })
     }
 
-    fn find_node_recursive<'a>(nodes: &'a [TreeItem], id: &str) -> Option<&'a TreeItem> {
-        for node in nodes {
-            if node.id == id {
-                return Some(node);
+    /// 轻量的完整树（先序展开，本地库在前），供界面刷新树时使用；内容通过 [`Store::get_item_content`] 按需读取
+    pub fn get_items_light(&self) -> Vec<LightItem> {
+        self.perf.time("tree-fetch-light", || {
+            let mut items = Vec::new();
+            for tree in [&*self.data.read().unwrap(), &*self.mounted.read().unwrap()] {
+                tree.walk(|node, _| items.push(light_item(tree, node)));
             }
-            if let Some(found) = Self::find_node_recursive(&node.children, id) {
-                return Some(found);
+            items
+        })
+    }
+
+    /// 文件夹（parent_id 为 None 时为根）的一页子节点，供树视图虚拟滚动时按需加载
+    pub fn get_children_page(
+        &self,
+        parent_id: Option<&str>,
+        offset: usize,
+        limit: usize,
+        sort: ChildSort,
+    ) -> Result<ChildrenPage, Error> {
+        self.perf.time("children-page", || self.children_page(parent_id, offset, limit, sort))
+    }
+
+    fn children_page(
+        &self,
+        parent_id: Option<&str>,
+        offset: usize,
+        limit: usize,
+        sort: ChildSort,
+    ) -> Result<ChildrenPage, Error> {
+        let data = self.data.read().unwrap();
+        let mounted = self.mounted.read().unwrap();
+        let mut entries: Vec<(&Tree, &TreeItem)> = match parent_id {
+            Some(parent_id) => {
+                let tree: &Tree = if mounts::is_mounted(parent_id) { &mounted } else { &data };
+                if !tree.contains(parent_id) {
+                    return Err(ErrorCode::ParentNotFound.into());
+                }
+                tree.children(parent_id)
+                    .iter()
+                    .filter_map(|id| tree.node(id))
+                    .map(|item| (tree, item))
+                    .collect()
             }
+            None => [&*data, &*mounted]
+                .into_iter()
+                .flat_map(|tree| {
+                    tree.roots()
+                        .iter()
+                        .filter_map(move |id| tree.node(id))
+                        .map(move |item| (tree, item))
+                })
+                .collect(),
+        };
+
+        // 只比较借用的条目，排序后只克隆当前页
+        match sort {
+            ChildSort::Manual => {}
+            ChildSort::Name => entries.sort_by_cached_key(|(_, item)| item.name.to_lowercase()),
+            ChildSort::Modified => entries.sort_by_key(|(_, item)| std::cmp::Reverse(item.metadata.last_modified)),
+            ChildSort::Type => entries.sort_by_cached_key(|(_, item)| {
+                (item.item_type != ItemType::Folder, item.name.to_lowercase())
+            }),
         }
-        None
+        let total = entries.len();
+        let items = entries
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .map(|(tree, item)| light_item(tree, item))
+            .collect();
+        Ok(ChildrenPage { items, total, offset })
     }
 
-    pub fn add_item(&self, parent_id: Option<String>, mut item: TreeItem) -> Result<TreeItem, String> {
-        let mut data = self.data.lock().map_err(|e| e.to_string())?;
+    pub fn get_item_content(&self, id: &str) -> Option<ItemContent> {
+        let tree = self.tree_of(id);
+        let node = tree.node(id)?;
+        Some(ItemContent {
+            id: node.id.clone(),
+            content: node.content.clone(),
+            versions: node.versions.clone(),
+            sensitive: node.metadata.sensitive == Some(true),
+        })
+    }
 
-        // Generate ID and Timestamp
-        item.id = uuid::Uuid::new_v4().to_string();
-        item.metadata.last_modified = Some(chrono::Utc::now().timestamp_millis());
+    /// 本地库或挂载库（按 id 前缀区分）
+    fn tree_of(&self, id: &str) -> RwLockReadGuard<'_, Tree> {
+        if mounts::is_mounted(id) {
+            self.mounted.read().unwrap()
+        } else {
+            self.data.read().unwrap()
+        }
+    }
 
-        if let Some(p_id) = parent_id {
-            if let Some(parent) = Self::find_node_mut_recursive(&mut data, &p_id) {
-                parent.children.push(item.clone());
-            } else {
-                return Err("Parent not found".to_string());
-            }
+    pub fn get_item(&self, id: &str) -> Option<TreeItem> {
+        self.tree_of(id).subtree(id)
+    }
+
+    /// 获取条目的父节点（根节点返回 None）
+    pub fn get_parent(&self, id: &str) -> Option<TreeItem> {
+        let tree = self.tree_of(id);
+        tree.parent(id).and_then(|parent| tree.subtree(parent))
+    }
+
+    /// 锁定时不能修改（否则改动只存在于内存，解锁后被覆盖），挂载库中的条目也不能修改
+    fn ensure_writable(&self, ids: &[&str]) -> Result<(), Error> {
+        if self.is_locked() {
+            Err(ErrorCode::StoreLocked.into())
+        } else if ids.iter().any(|id| mounts::is_mounted(id)) {
+            Err(ErrorCode::ReadOnly.into())
         } else {
-            data.push(item.clone());
+            Ok(())
         }
+    }
 
-        // Release lock to save
-        drop(data);
-        self.save()?;
+    pub fn list_mounts(&self) -> Vec<MountedLibrary> {
+        self.mounts.lock().unwrap().clone()
+    }
 
-        Ok(item)
+    /// 挂载一个只读库，返回其根节点
+    pub fn add_mount(&self, name: String, path: String) -> Result<TreeItem, Error> {
+        if !std::path::Path::new(&path).exists() {
+            return Err(Error::with(ErrorCode::PathNotFound, &path));
+        }
+        let mount = MountedLibrary {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            path,
+        };
+        let root = mounts::load(&mount);
+
+        let mut list = self.mounts.lock().map_err(|e| e.to_string())?;
+        list.push(mount);
+        mounts::save_config(&self.mounts_path, &list)?;
+        self.mounted.write().map_err(|e| e.to_string())?.insert(None, root.clone())?;
+        Ok(root)
+    }
+
+    pub fn remove_mount(&self, id: &str) -> Result<(), Error> {
+        let mut list = self.mounts.lock().map_err(|e| e.to_string())?;
+        list.retain(|m| m.id != id);
+        mounts::save_config(&self.mounts_path, &list)?;
+        *self.mounted.write().map_err(|e| e.to_string())? = Tree::new(list.iter().map(mounts::load).collect());
+        Ok(())
+    }
+
+    /// 重新读取全部挂载库（共享目录中的内容可能已被他人更新），返回完整树
+    pub fn refresh_mounts(&self) -> Vec<TreeItem> {
+        let roots = self.mounts.lock().unwrap().iter().map(mounts::load).collect();
+        *self.mounted.write().unwrap() = Tree::new(roots);
+        self.get_tree()
+    }
+
+    /// 按 id 或 `文件夹/名称` 形式的路径查找条目
+    pub fn find_by_reference(&self, reference: &str) -> Option<TreeItem> {
+        let id = self.resolve_reference(reference)?;
+        self.tree_of(&id).subtree(&id)
+    }
+
+    /// 引用指向的条目 id；只需要 id 时不必克隆整个子树
+    fn resolve_reference(&self, reference: &str) -> Option<String> {
+        let data = self.data.read().unwrap();
+        let mounted = self.mounted.read().unwrap();
+        if data.contains(reference) || mounted.contains(reference) {
+            return Some(reference.to_string());
+        }
+        // 挂载库以其根节点名称作为路径的第一段
+        let segments: Vec<&str> = reference.split('/').map(str::trim).filter(|s| !s.is_empty()).collect();
+        data.find_by_path(&segments)
+            .or_else(|| mounted.find_by_path(&segments))
+            .map(str::to_string)
+    }
+
+    /// 展开 prompt 内容中的 `{{> prompt:...}}` 引用
+    pub fn expand_includes(&self, item: &TreeItem) -> Result<String, Error> {
+        template::expand_includes(item, &|reference| self.find_by_reference(reference))
+    }
+
+    /// 检查 prompt 模板中的问题
+    pub fn validate_template(&self, item: &TreeItem) -> Vec<TemplateIssue> {
+        template::validate(item, &|reference| self.find_by_reference(reference))
+    }
+
+    /// 检查 prompt 与片段中的疑似密钥；ids 为空时检查整个库
+    pub fn scan_secrets(&self, ids: Option<&[String]>) -> Vec<SecretFinding> {
+        let mut findings = Vec::new();
+        for tree in [&*self.data.read().unwrap(), &*self.mounted.read().unwrap()] {
+            tree.walk(|node, _| {
+                let selected = ids.map_or(true, |ids| ids.contains(&node.id));
+                if selected && matches!(node.item_type, ItemType::Prompt | ItemType::Snippet) {
+                    findings.extend(secrets::scan_item(node));
+                }
+            });
+        }
+        findings
     }
 
-    fn find_node_mut_recursive<'a>(nodes: &'a mut [TreeItem], id: &str) -> Option<&'a mut TreeItem> {
-        for node in nodes {
-            if node.id == id {
-                return Some(node);
+    /// 片段库：树中全部片段（不含子节点）
+    pub fn list_snippets(&self) -> Vec<TreeItem> {
+        let mut items = Vec::new();
+        Self::collect_prompts(&self.data.read().unwrap(), &mut items);
+        Self::collect_prompts(&self.mounted.read().unwrap(), &mut items);
+        items.retain(|i| i.item_type == ItemType::Snippet);
+        items
+    }
+
+    /// 直接或间接引用了指定条目的全部 prompt
+    pub fn get_dependents(&self, id: &str) -> Vec<TreeItem> {
+        let mut prompts = Vec::new();
+        Self::collect_prompts(&self.data.read().unwrap(), &mut prompts);
+        Self::collect_prompts(&self.mounted.read().unwrap(), &mut prompts);
+
+        // 被引用条目 id -> 引用它的 prompt id
+        let mut included_by: HashMap<String, Vec<String>> = HashMap::new();
+        for prompt in &prompts {
+            for include in template::parse_includes(prompt.content.as_deref().unwrap_or_default()) {
+                if let Some(target) = self.resolve_reference(&include.reference) {
+                    included_by.entry(target).or_default().push(prompt.id.clone());
+                }
             }
-            if let Some(found) = Self::find_node_mut_recursive(&mut node.children, id) {
-                return Some(found);
+        }
+
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut queue = vec![id.to_string()];
+        while let Some(current) = queue.pop() {
+            for dependent in included_by.get(&current).into_iter().flatten() {
+                if dependent != id && seen.insert(dependent.clone()) {
+                    queue.push(dependent.clone());
+                }
             }
         }
-        None
+
+        prompts.into_iter().filter(|p| seen.contains(&p.id)).collect()
     }
 
-    pub fn update_item(&self, id: String, updates: TreeItem) -> Result<TreeItem, String> {
-        let mut data = self.data.lock().map_err(|e| e.to_string())?;
+    /// 收集全部 prompt 与片段（不含子节点）
+    fn collect_prompts(tree: &Tree, prompts: &mut Vec<TreeItem>) {
+        tree.walk(|node, _| {
+            if matches!(node.item_type, ItemType::Prompt | ItemType::Snippet) {
+                prompts.push(node.clone());
+            }
+        });
+    }
+
+    pub fn add_item(&self, parent_id: Option<String>, mut item: TreeItem) -> Result<TreeItem, Error> {
+        self.ensure_writable(&[parent_id.as_deref().unwrap_or_default()])?;
+        let mut data = self.data.write().map_err(|e| e.to_string())?;
 
-        if let Some(node) = Self::find_node_mut_recursive(&mut data, &id) {
+        // Generate ID and Timestamp
+        item.id = uuid::Uuid::new_v4().to_string();
+        item.metadata.last_modified = Some(chrono::Utc::now().timestamp_millis());
+        item.metadata.clock = None;
+        crdt::tick(&mut item, &self.device);
+
+        data.insert(parent_id.as_deref(), item.clone())?;
+
+        // Release lock to save
+        drop(data);
+        self.commit(
+            ChangeKind::Added,
+            vec![item.id.clone()],
+            format!("Add {} '{}'", item.item_type.label(), item.name),
+        )?;
+
+        Ok(item)
+    }
+
+    pub fn update_item(&self, id: String, mut updates: TreeItem) -> Result<TreeItem, Error> {
+        self.ensure_writable(&[&id])?;
+        let mut data = self.data.write().map_err(|e| e.to_string())?;
+
+        if let Some(node) = data.node_mut(&id) {
+            sensitive::restore(node, &mut updates);
             // 应用更新（简单合并）
             node.name = updates.name;
             node.content = updates.content;
@@ -121,192 +853,468 @@ impl Store {
             node.metadata.model_name = updates.metadata.model_name.or(node.metadata.model_name.clone());
             node.metadata.base_url = updates.metadata.base_url.or(node.metadata.base_url.clone());
             node.metadata.api_key = updates.metadata.api_key.or(node.metadata.api_key.clone());
+            // 计价字段合并
+            node.metadata.input_price = updates.metadata.input_price.or(node.metadata.input_price);
+            node.metadata.output_price = updates.metadata.output_price.or(node.metadata.output_price);
+            node.metadata.generation = updates.metadata.generation.or(node.metadata.generation.clone());
+            node.metadata.proxy = updates.metadata.proxy.or(node.metadata.proxy.clone());
+            node.metadata.rate_limit = updates.metadata.rate_limit.or(node.metadata.rate_limit.clone());
+            node.metadata.auth_header = updates.metadata.auth_header.or(node.metadata.auth_header.clone());
+            node.metadata.extra_headers = updates.metadata.extra_headers.or(node.metadata.extra_headers.clone());
+            node.metadata.query_params = updates.metadata.query_params.or(node.metadata.query_params.clone());
+            node.metadata.variables = updates.metadata.variables.or(node.metadata.variables.clone());
+            node.metadata.template_mode = updates.metadata.template_mode.or(node.metadata.template_mode);
+            node.metadata.favorite = updates.metadata.favorite.or(node.metadata.favorite);
+            node.metadata.pinned = updates.metadata.pinned.or(node.metadata.pinned);
+            node.metadata.sensitive = updates.metadata.sensitive.or(node.metadata.sensitive);
+            crdt::tick(node, &self.device);
 
-            let updated_node = node.clone();
+            let updated_node = data.subtree(&id).ok_or(ErrorCode::ItemNotFound)?;
             drop(data);
-            self.save()?;
+            self.commit(
+                ChangeKind::Updated,
+                vec![id],
+                format!("Update {} '{}'", updated_node.item_type.label(), updated_node.name),
+            )?;
 
             return Ok(updated_node);
         }
-        Err("Item not found".to_string())
+        Err(ErrorCode::ItemNotFound.into())
     }
 
-    pub fn delete_item(&self, id: String) -> Result<(), String> {
-        let mut data = self.data.lock().map_err(|e| e.to_string())?;
-        Self::delete_node_recursive(&mut data, &id);
+    /// 设置收藏 / 置顶 / 敏感标记（为 None 的保持不变）
+    pub fn set_flags(
+        &self,
+        id: &str,
+        favorite: Option<bool>,
+        pinned: Option<bool>,
+        sensitive: Option<bool>,
+    ) -> Result<TreeItem, Error> {
+        self.ensure_writable(&[id])?;
+        let mut data = self.data.write().map_err(|e| e.to_string())?;
+        let node = data.node_mut(id).ok_or(ErrorCode::ItemNotFound)?;
+        if favorite.is_some() {
+            node.metadata.favorite = favorite.filter(|&f| f);
+        }
+        if pinned.is_some() {
+            node.metadata.pinned = pinned.filter(|&p| p);
+        }
+        if sensitive.is_some() {
+            node.metadata.sensitive = sensitive.filter(|&s| s);
+        }
+        node.metadata.last_modified = Some(chrono::Utc::now().timestamp_millis());
+        crdt::tick(node, &self.device);
+        let item = data.subtree(id).ok_or(ErrorCode::ItemNotFound)?;
+
         drop(data);
-        self.save()?;
-        Ok(())
+        self.commit(
+            ChangeKind::Updated,
+            vec![item.id.clone()],
+            format!("Update {} '{}'", item.item_type.label(), item.name),
+        )?;
+        Ok(item)
     }
 
-    fn delete_node_recursive(nodes: &mut Vec<TreeItem>, id: &str) {
-        if let Some(pos) = nodes.iter().position(|x| x.id == id) {
-            nodes.remove(pos);
-            return;
-        }
-        for node in nodes.iter_mut() {
-            Self::delete_node_recursive(&mut node.children, id);
-        }
+    /// 标记为敏感的条目 id（不复制条目）
+    pub fn sensitive_ids(&self) -> HashSet<String> {
+        let data = self.data.read().unwrap();
+        let mounted = self.mounted.read().unwrap();
+        [&*data, &*mounted]
+            .into_iter()
+            .flat_map(|tree| tree.items())
+            .filter(|item| item.metadata.sensitive == Some(true))
+            .map(|item| item.id.clone())
+            .collect()
     }
 
-    /// 从原位置取出节点（递归查找并移除）
-    fn extract_node_recursive(nodes: &mut Vec<TreeItem>, id: &str) -> Option<TreeItem> {
-        if let Some(pos) = nodes.iter().position(|x| x.id == id) {
-            return Some(nodes.remove(pos));
-        }
-        for node in nodes.iter_mut() {
-            if let Some(found) = Self::extract_node_recursive(&mut node.children, id) {
-                return Some(found);
-            }
-        }
-        None
+    /// 带有指定标记的 prompt 与片段（按树中的顺序）
+    pub fn flagged(&self, flag: impl Fn(&ItemMetadata) -> bool) -> Vec<TreeItem> {
+        let mut items = Vec::new();
+        Self::collect_prompts(&self.data.read().unwrap(), &mut items);
+        Self::collect_prompts(&self.mounted.read().unwrap(), &mut items);
+        items.retain(|i| flag(&i.metadata));
+        items
     }
 
-    /// 检查 ancestor_id 是否是 descendant_id 的祖先（防止把文件夹拖入自身子树）
-    fn is_ancestor(nodes: &[TreeItem], ancestor_id: &str, descendant_id: &str) -> bool {
-        for node in nodes {
-            if node.id == ancestor_id {
-                // 在 ancestor 的子树中查找 descendant
-                return Self::find_node_recursive(&node.children, descendant_id).is_some();
+    /// 按 id 写入条目（保留已有的子节点与位置）；不存在时插入到 parent_id 指向的节点下，找不到父节点时放在根下
+    pub fn upsert_item(&self, mut item: TreeItem) -> Result<TreeItem, Error> {
+        self.ensure_writable(&[&item.id, item.parent_id.as_deref().unwrap_or_default()])?;
+        let mut data = self.data.write().map_err(|e| e.to_string())?;
+        let kind = match data.node_mut(&item.id) {
+            Some(node) => {
+                let clock = node.metadata.clock.clone().unwrap_or_default();
+                item.metadata.clock = Some(crdt::join(&clock, &item.metadata.clock.unwrap_or_default()));
+                crdt::tick(&mut item, &self.device);
+                // 保留已有的子节点与位置
+                item.children.clear();
+                item.parent_id = node.parent_id.clone();
+                *node = item.clone();
+                item = data.subtree(&item.id).unwrap_or(item);
+                ChangeKind::Updated
             }
-            if Self::is_ancestor(&node.children, ancestor_id, descendant_id) {
-                return true;
+            None => {
+                crdt::tick(&mut item, &self.device);
+                if !item.parent_id.as_deref().is_some_and(|pid| data.contains(pid)) {
+                    item.parent_id = None;
+                }
+                data.insert(item.parent_id.as_deref(), item.clone())?;
+                ChangeKind::Added
             }
-        }
-        false
+        };
+
+        drop(data);
+        let verb = if kind == ChangeKind::Added { "Add" } else { "Update" };
+        self.commit(
+            kind,
+            vec![item.id.clone()],
+            format!("{} {} '{}'", verb, item.item_type.label(), item.name),
+        )?;
+        Ok(item)
     }
 
-    pub fn move_item(&self, item_id: String, new_parent_id: Option<String>) -> Result<TreeItem, String> {
-        let mut data = self.data.lock().map_err(|e| e.to_string())?;
+    pub fn delete_item(&self, id: String) -> Result<(), Error> {
+        self.ensure_writable(&[&id])?;
+        let mut data = self.data.write().map_err(|e| e.to_string())?;
+        // 变更中包含被一起删除的子孙条目
+        let (ids, summary) = match data.node(&id) {
+            Some(node) => {
+                let summary = format!("Delete {} '{}'", node.item_type.label(), node.name);
+                let ids = data.subtree_ids(&id);
+                let mut tombstones = self.tombstones.lock().map_err(|e| e.to_string())?;
+                for removed in ids.iter().filter_map(|i| data.node(i)) {
+                    crdt::bury(removed, &self.device, &mut tombstones);
+                }
+                (ids, summary)
+            }
+            None => (vec![id.clone()], "Delete item".to_string()),
+        };
+        data.remove(&id);
+        drop(data);
+        self.commit(ChangeKind::Deleted, ids, summary)?;
+        Ok(())
+    }
+
+    pub fn move_item(&self, item_id: String, new_parent_id: Option<String>) -> Result<TreeItem, Error> {
+        self.ensure_writable(&[&item_id, new_parent_id.as_deref().unwrap_or_default()])?;
+        let mut data = self.data.write().map_err(|e| e.to_string())?;
+        if !data.contains(&item_id) {
+            return Err(ErrorCode::ItemNotFound.into());
+        }
 
-        // 防止将文件夹移动到自身子树中
         if let Some(ref pid) = new_parent_id {
+            // 防止将文件夹移动到自身子树中
             if *pid == item_id {
-                return Err("Cannot move item into itself".to_string());
+                return Err(ErrorCode::MoveIntoSelf.into());
             }
-            if Self::is_ancestor(&data, &item_id, pid) {
-                return Err("Cannot move folder into its own subtree".to_string());
+            if data.is_ancestor(&item_id, pid) {
+                return Err(ErrorCode::MoveIntoSubtree.into());
+            }
+            let parent = data.node(pid).ok_or(ErrorCode::TargetParentNotFound)?;
+            if parent.item_type != ItemType::Folder {
+                return Err(ErrorCode::NotFolder.into());
             }
         }
 
-        // 取出节点
-        let mut item = Self::extract_node_recursive(&mut data, &item_id)
-            .ok_or_else(|| "Item not found".to_string())?;
-
+        // 取出节点并放入新位置
+        let mut item = data.remove(&item_id).ok_or(ErrorCode::ItemNotFound)?;
         item.parent_id = new_parent_id.clone();
+        crdt::tick(&mut item, &self.device);
+        data.insert(new_parent_id.as_deref(), item.clone())?;
 
-        // 放入新位置
-        if let Some(pid) = new_parent_id {
-            let parent = Self::find_node_mut_recursive(&mut data, &pid)
-                .ok_or_else(|| "Target parent not found".to_string())?;
-            if parent.item_type != ItemType::Folder {
-                // 目标不是文件夹，放回原位再报错
-                data.push(item);
-                return Err("Target is not a folder".to_string());
+        drop(data);
+        self.commit(
+            ChangeKind::Moved,
+            vec![item.id.clone()],
+            format!("Move {} '{}'", item.item_type.label(), item.name),
+        )?;
+        Ok(item)
+    }
+
+    /// 用查询到的模型名同步 Provider 下的 Model 子节点：已存在的保留（含计价等配置），缺少的新建
+    pub fn sync_models(&self, provider_id: &str, model_names: Vec<String>) -> Result<Vec<TreeItem>, Error> {
+        self.ensure_writable(&[provider_id])?;
+        let mut data = self.data.write().map_err(|e| e.to_string())?;
+        let provider = data.node(provider_id).ok_or(ErrorCode::ProviderNotFound)?;
+        if provider.item_type != ItemType::Provider {
+            return Err(ErrorCode::NotProvider.into());
+        }
+        let summary = format!("Sync models of provider '{}'", provider.name);
+
+        let now = chrono::Utc::now().timestamp_millis();
+        for model_name in model_names {
+            let existing = data
+                .children(provider_id)
+                .iter()
+                .find(|c| {
+                    data.node(c).is_some_and(|c| {
+                        c.item_type == ItemType::Model
+                            && c.metadata.model_name.as_deref().unwrap_or(&c.name) == model_name
+                    })
+                })
+                .cloned();
+            match existing.and_then(|id| data.node_mut(&id)) {
+                Some(model) => {
+                    model.metadata.model_name = Some(model_name);
+                }
+                None => {
+                    let mut model = TreeItem {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        name: model_name.clone(),
+                        item_type: ItemType::Model,
+                        children: Vec::new(),
+                        parent_id: Some(provider_id.to_string()),
+                        content: None,
+                        versions: None,
+                        metadata: ItemMetadata {
+                            model_name: Some(model_name),
+                            last_modified: Some(now),
+                            ..Default::default()
+                        },
+                    };
+                    crdt::tick(&mut model, &self.device);
+                    data.insert(Some(provider_id), model)?;
+                }
             }
-            parent.children.push(item.clone());
-        } else {
-            data.push(item.clone());
         }
+        if let Some(provider) = data.node_mut(provider_id) {
+            provider.metadata.last_modified = Some(now);
+            crdt::tick(provider, &self.device);
+        }
+        let models = data
+            .children(provider_id)
+            .iter()
+            .filter_map(|c| data.subtree(c))
+            .collect();
 
         drop(data);
-        self.save()?;
-        Ok(item)
+        self.commit(ChangeKind::Updated, vec![provider_id.to_string()], summary)?;
+        Ok(models)
+    }
+
+    /// 快速选择器使用的模糊搜索：按名称、标签与所在路径匹配 prompt 与片段，得分高的在前。
+    /// 空格分隔的每个词都要命中
+    pub fn quick_search(&self, query: &str, limit: usize) -> Vec<QuickMatch> {
+        self.perf.time("quick-search", || self.run_quick_search(query, limit))
+    }
+
+    fn run_quick_search(&self, query: &str, limit: usize) -> Vec<QuickMatch> {
+        let tokens: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        let data = self.data.read().unwrap();
+        let mounted = self.mounted.read().unwrap();
+        let mut candidates = Vec::new();
+        for tree in [&*data, &*mounted] {
+            tree.walk(|node, path| {
+                if matches!(node.item_type, ItemType::Prompt | ItemType::Snippet) {
+                    candidates.push((node, path.to_string()));
+                }
+            });
+        }
+
+        let mut results: Vec<(QuickMatch, Option<i64>)> = candidates
+            .par_iter()
+            .with_min_len(SEARCH_CHUNK)
+            .fold(Vec::new, |mut found, (node, path)| {
+                found.extend(Self::quick_match(node, path, &tokens));
+                found
+            })
+            .reduce(Vec::new, merge);
+        results.sort_by(|(a, a_modified), (b, b_modified)| {
+            b.score.cmp(&a.score).then_with(|| b_modified.cmp(a_modified))
+        });
+        results.into_iter().take(limit).map(|(m, _)| m).collect()
+    }
+
+    fn quick_match(node: &TreeItem, path: &str, tokens: &[String]) -> Option<(QuickMatch, Option<i64>)> {
+        let tags = node.metadata.tags.clone().unwrap_or_default();
+        let score = tokens.iter().try_fold(0, |total, token| {
+            let name = fuzzy_score(token, &node.name).map(|s| s * 2);
+            let tag = tags.iter().filter_map(|t| tag_score(token, t)).max();
+            let folder = fuzzy_score(token, path);
+            [name, tag, folder].into_iter().flatten().max().map(|s| total + s)
+        })?;
+        Some((
+            QuickMatch {
+                item_id: node.id.clone(),
+                name: node.name.clone(),
+                item_type: node.item_type.clone(),
+                path: path.to_string(),
+                tags,
+                score,
+            },
+            node.metadata.last_modified,
+        ))
     }
 
     pub fn search(&self, query: String, filters: Option<SearchFilters>) -> Vec<SearchResult> {
-        let data = self.data.lock().unwrap();
+        self.perf.time("search", || self.run_search(query, filters))
+    }
+
+    fn run_search(&self, query: String, filters: Option<SearchFilters>) -> Vec<SearchResult> {
+        let data = self.data.read().unwrap();
         let mut results = Vec::new();
         if query.trim().is_empty() {
             return results;
         }
 
         let lower_query = query.to_lowercase();
+        let since = filters.as_ref().and_then(|f| f.date.as_deref()).and_then(locale::period_start);
+        let mounted = self.mounted.read().unwrap();
+        let mut nodes = Vec::new();
+        for tree in [&*data, &*mounted] {
+            tree.walk(|node, _| nodes.push(node));
+        }
 
-        Self::search_recursive(&data, &lower_query, &filters, &mut results);
-
+        // 各线程把命中结果收集到自己的缓冲区，最后合并排序：名称命中的在前，其次按内容命中次数，再按树中顺序
+        let mut ranked: Vec<(usize, SearchResult)> = nodes
+            .par_iter()
+            .enumerate()
+            .with_min_len(SEARCH_CHUNK)
+            .fold(Vec::new, |mut found, (index, node)| {
+                found.extend(Self::search_node(node, &lower_query, &filters, since).map(|r| (index, r)));
+                found
+            })
+            .reduce(Vec::new, merge);
+        ranked.sort_by_cached_key(|(index, r)| {
+            (
+                !r.item_name.to_lowercase().contains(&lower_query),
+                std::cmp::Reverse(r.matches.len()),
+                *index,
+            )
+        });
+        results.extend(ranked.into_iter().map(|(_, r)| r));
         results
     }
 
-    fn search_recursive(nodes: &[TreeItem], query: &str, filters: &Option<SearchFilters>, results: &mut Vec<SearchResult>) {
-        for node in nodes {
-            let mut is_match = false;
-            let mut matches = Vec::new();
+    fn search_node(
+        node: &TreeItem,
+        query: &str,
+        filters: &Option<SearchFilters>,
+        since: Option<i64>,
+    ) -> Option<SearchResult> {
+        let mut is_match = false;
+        let mut matches = Vec::new();
 
-            // 1. Type Filter
-            let type_match = if let Some(f) = filters {
-                if let Some(types) = &f.types {
-                    types.is_empty() || types.contains(&node.item_type)
-                } else {
-                    true
-                }
+        // 1. Type Filter（片段只在显式按类型筛选时出现）
+        let type_match = if let Some(f) = filters {
+            if let Some(types) = &f.types {
+                types.contains(&node.item_type) || (types.is_empty() && node.item_type != ItemType::Snippet)
             } else {
-                true
-            };
-
-            // 2. 日期过滤
-            let date_match = if let Some(f) = filters {
-                if let Some(date) = &f.date {
-                    if let Some(last_mod) = node.metadata.last_modified {
-                        let now = chrono::Utc::now().timestamp_millis();
-                        let one_day = 86_400_000i64;
-                        match date.as_str() {
-                            "today" => now - last_mod <= one_day,
-                            "week" => now - last_mod <= one_day * 7,
-                            "month" => now - last_mod <= one_day * 30,
-                            _ => true, // "any"
-                        }
-                    } else {
-                        true // 无 lastModified 的项不被日期过滤排除
-                    }
-                } else {
-                    true
-                }
-            } else {
-                true
-            };
+                node.item_type != ItemType::Snippet
+            }
+        } else {
+            node.item_type != ItemType::Snippet
+        };
 
-            if type_match && date_match {
-                // Name match
-                if node.name.to_lowercase().contains(query) {
-                    is_match = true;
-                }
+        // 2. 日期过滤（起点按用户所在时区计算）
+        let date_match = match (since, node.metadata.last_modified) {
+            (Some(since), Some(last_mod)) => last_mod >= since,
+            _ => true, // "any"，或无 lastModified 的项不被日期过滤排除
+        };
 
-                // Content match (for Prompts)
-                if node.item_type == ItemType::Prompt {
-                    if let Some(content) = &node.content {
-                        for (i, line) in content.lines().enumerate() {
-                            let lower_line = line.to_lowercase();
-                            let mut start_idx = 0;
-                            while let Some(idx) = lower_line[start_idx..].find(query) {
-                                let absolute_idx = start_idx + idx;
-                                matches.push(SearchMatch {
-                                    line_content: line.to_string(),
-                                    line_number: i + 1,
-                                    start_column: absolute_idx + 1,
-                                    end_column: absolute_idx + 1 + query.len(),
-                                });
-                                start_idx = absolute_idx + 1;
-                            }
-                        }
-                        if !matches.is_empty() {
-                            is_match = true;
+        if type_match && date_match {
+            // Name match
+            if node.name.to_lowercase().contains(query) {
+                is_match = true;
+            }
+
+            // Content match (for Prompts and Snippets)
+            if matches!(node.item_type, ItemType::Prompt | ItemType::Snippet) {
+                if let Some(content) = &node.content {
+                    for (i, line) in content.lines().enumerate() {
+                        let lower_line = line.to_lowercase();
+                        let mut start_idx = 0;
+                        while let Some(idx) = lower_line[start_idx..].find(query) {
+                            let absolute_idx = start_idx + idx;
+                            matches.push(SearchMatch {
+                                line_content: line.to_string(),
+                                line_number: i + 1,
+                                start_column: absolute_idx + 1,
+                                end_column: absolute_idx + 1 + query.len(),
+                            });
+                            start_idx = absolute_idx + 1;
                         }
                     }
+                    if !matches.is_empty() {
+                        is_match = true;
+                    }
                 }
+            }
 
-                if is_match {
-                    results.push(SearchResult {
-                        item_id: node.id.clone(),
-                        item_name: node.name.clone(),
-                        item_type: node.item_type.clone(),
-                        matches,
-                        last_modified: node.metadata.last_modified,
-                    });
-                }
+            if is_match {
+                return Some(SearchResult {
+                    item_id: node.id.clone(),
+                    item_name: node.name.clone(),
+                    item_type: node.item_type.clone(),
+                    matches,
+                    last_modified: node.metadata.last_modified,
+                });
             }
+        }
+        None
+    }
+}
+
+fn merge<T>(mut a: Vec<T>, mut b: Vec<T>) -> Vec<T> {
+    a.append(&mut b);
+    a
+}
 
-            Self::search_recursive(&node.children, query, filters, results);
+/// 子序列模糊匹配：query（小写）的字符依次出现在 text 中即命中；
+/// 连续命中、命中单词开头与前缀匹配得分更高，跳过的字符越多得分越低
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0i64;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for c in query.chars() {
+        let position = (next..text.len()).find(|&i| text[i] == c)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == position) {
+            score += 5;
         }
+        if position == 0 || !text[position - 1].is_alphanumeric() {
+            score += 3;
+        }
+        score -= (position - next).min(3) as i64;
+        previous = Some(position);
+        next = position + 1;
+    }
+    if text.iter().collect::<String>().starts_with(query) {
+        score += 10;
+    }
+    Some(score)
+}
+
+/// 标签完全相同时得分最高，其次是前缀
+fn tag_score(query: &str, tag: &str) -> Option<i64> {
+    let tag = tag.to_lowercase();
+    if tag == query {
+        Some(30)
+    } else if tag.starts_with(query) {
+        Some(15)
+    } else {
+        None
+    }
+}
+
+/// 条目的轻量表示（不含内容与历史版本）
+fn light_item(tree: &Tree, item: &TreeItem) -> LightItem {
+    LightItem {
+        id: item.id.clone(),
+        name: item.name.clone(),
+        item_type: item.item_type.clone(),
+        parent_id: tree.parent(&item.id).map(str::to_string),
+        children: tree.children(&item.id).to_vec(),
+        metadata: ItemSummary {
+            description: item.metadata.description.clone(),
+            tags: item.metadata.tags.clone(),
+            last_modified: item.metadata.last_modified,
+            favorite: item.metadata.favorite,
+            pinned: item.metadata.pinned,
+            read_only: item.metadata.read_only,
+            sensitive: item.metadata.sensitive,
+            variable_count: item.metadata.variables.as_ref().map_or(0, Vec::len),
+            version_count: item.versions.as_ref().map_or(0, Vec::len),
+        },
     }
 }
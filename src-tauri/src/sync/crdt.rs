@@ -0,0 +1,385 @@
+//! 通过 Dropbox 等网盘同步 store.json 时的多设备合并。
+//! 每个条目带有向量时钟，删除的条目留下墓碑（删除时的时钟）；
+//! 一侧的时钟包含另一侧时取较新的一侧，并发修改时按确定的规则选出胜者，
+//! 另一侧的内容保存为历史版本，两台设备合并同样的两个文件总能得到相同的结果
+
+use super::merge::{build_tree, flatten, Flat};
+use super::SyncState;
+use crate::log_error;
+use crate::models::{PromptVersion, TreeItem, VectorClock};
+use crate::store::{Store, StoreFile};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// 两个时钟的先后关系
+#[derive(Debug, PartialEq)]
+pub enum Causality {
+    Equal,
+    /// 前者发生在后者之前（后者包含了前者的全部修改）
+    Before,
+    After,
+    Concurrent,
+}
+
+pub fn compare(a: &VectorClock, b: &VectorClock) -> Causality {
+    let (mut less, mut greater) = (false, false);
+    for device in a.keys().chain(b.keys()) {
+        let (x, y) = (a.get(device).copied().unwrap_or(0), b.get(device).copied().unwrap_or(0));
+        less |= x < y;
+        greater |= x > y;
+    }
+    match (less, greater) {
+        (false, false) => Causality::Equal,
+        (true, false) => Causality::Before,
+        (false, true) => Causality::After,
+        (true, true) => Causality::Concurrent,
+    }
+}
+
+/// 逐设备取较大值
+pub fn join(a: &VectorClock, b: &VectorClock) -> VectorClock {
+    let mut joined = a.clone();
+    for (device, &count) in b {
+        let entry = joined.entry(device.clone()).or_insert(0);
+        *entry = (*entry).max(count);
+    }
+    joined
+}
+
+/// 记录本设备的一次修改
+pub fn tick(item: &mut TreeItem, device: &str) {
+    *item
+        .metadata
+        .clock
+        .get_or_insert_with(VectorClock::new)
+        .entry(device.to_string())
+        .or_insert(0) += 1;
+}
+
+fn clock_of(item: &TreeItem) -> VectorClock {
+    item.metadata.clock.clone().unwrap_or_default()
+}
+
+/// 合并两份 store.json 的内容；结果与参数顺序无关
+pub fn merge(local: &StoreFile, remote: &StoreFile) -> StoreFile {
+    let mut tombstones = local.tombstones.clone();
+    for (id, clock) in &remote.tombstones {
+        let joined = join(tombstones.get(id).unwrap_or(&VectorClock::new()), clock);
+        tombstones.insert(id.clone(), joined);
+    }
+
+    let (mut local_map, mut remote_map) = (HashMap::new(), HashMap::new());
+    let (mut local_order, mut remote_order) = (Vec::new(), Vec::new());
+    flatten(&local.items, None, &mut local_map, &mut local_order);
+    flatten(&remote.items, None, &mut remote_map, &mut remote_order);
+    let mut seen = HashSet::new();
+    let ids: Vec<String> = local_order
+        .into_iter()
+        .chain(remote_order)
+        .filter(|id| seen.insert(id.clone()))
+        .collect();
+
+    let mut merged = Vec::new();
+    for id in ids {
+        let (item, winner) = match (local_map.get(&id), remote_map.get(&id)) {
+            (Some(l), Some(r)) => merge_pair(l, r),
+            (Some(f), None) | (None, Some(f)) => (f.item.clone(), f),
+            (None, None) => continue,
+        };
+        // 删除之后（或与删除并发）没有新的修改时保持删除；并发的修改优先于删除
+        if let Some(dead) = tombstones.get(&id) {
+            if matches!(compare(&clock_of(&item), dead), Causality::Before | Causality::Equal) {
+                continue;
+            }
+        }
+        merged.push((id, item, winner.parent.clone(), winner.index));
+    }
+
+    // 同级条目按位置排序，位置相同时按 id，保证两台设备得到相同的顺序
+    merged.sort_by(|a, b| a.3.cmp(&b.3).then_with(|| a.0.cmp(&b.0)));
+    let flat = merged
+        .into_iter()
+        .enumerate()
+        .map(|(rank, (id, item, parent, _))| (id, item, parent, (0, rank)))
+        .collect();
+    StoreFile {
+        items: build_tree(flat),
+        tombstones,
+    }
+}
+
+/// 同一条目在两侧都存在：返回合并后的条目与决定位置的一侧
+fn merge_pair<'a>(local: &'a Flat, remote: &'a Flat) -> (TreeItem, &'a Flat) {
+    let (lc, rc) = (clock_of(&local.item), clock_of(&remote.item));
+    match compare(&lc, &rc) {
+        Causality::After => return (local.item.clone(), local),
+        Causality::Before => return (remote.item.clone(), remote),
+        Causality::Equal if same(&local.item, &remote.item) && local.parent == remote.parent => {
+            return (local.item.clone(), local)
+        }
+        // 时钟相同但内容不同（例如没有时钟的旧数据）按并发处理
+        _ => {}
+    }
+
+    let (winner, loser) = if precedes(&remote.item, &local.item) {
+        (local, remote)
+    } else {
+        (remote, local)
+    };
+    let mut item = winner.item.clone();
+    item.metadata.clock = Some(join(&lc, &rc));
+    item.metadata.last_modified = winner
+        .item
+        .metadata
+        .last_modified
+        .max(loser.item.metadata.last_modified);
+
+    // 两侧的历史版本取并集，落选一侧不同的内容另存为一个版本
+    let mut versions: Vec<PromptVersion> = Vec::new();
+    for version in winner.item.versions.iter().chain(&loser.item.versions).flatten() {
+        if !versions.iter().any(|v| v.id == version.id) {
+            versions.push(version.clone());
+        }
+    }
+    if let Some(content) = loser
+        .item
+        .content
+        .as_ref()
+        .filter(|c| Some(*c) != winner.item.content.as_ref())
+    {
+        let id = format!("concurrent-{}", clock_key(&clock_of(&loser.item)));
+        if !versions.iter().any(|v| v.id == id) {
+            versions.push(PromptVersion {
+                id,
+                timestamp: loser.item.metadata.last_modified.unwrap_or_default(),
+                content: content.clone(),
+                label: Some("Concurrent edit".to_string()),
+            });
+        }
+    }
+    versions.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.id.cmp(&b.id)));
+    item.versions = (!versions.is_empty()).then_some(versions);
+    (item, winner)
+}
+
+/// 并发修改时的确定顺序：修改时间较新的胜出，其次比较时钟与内容
+fn precedes(a: &TreeItem, b: &TreeItem) -> bool {
+    let key = |item: &TreeItem| (item.metadata.last_modified, clock_key(&clock_of(item)));
+    match key(a).cmp(&key(b)) {
+        Ordering::Equal => serialized(a) < serialized(b),
+        ordering => ordering == Ordering::Less,
+    }
+}
+
+fn clock_key(clock: &VectorClock) -> String {
+    clock
+        .iter()
+        .map(|(device, count)| format!("{}={}", device, count))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn serialized(item: &TreeItem) -> String {
+    serde_json::to_string(item).unwrap_or_default()
+}
+
+fn same(a: &TreeItem, b: &TreeItem) -> bool {
+    serialized(a) == serialized(b)
+}
+
+/// 删除条目时留下墓碑：删除本身也算一次修改
+pub fn bury(item: &TreeItem, device: &str, tombstones: &mut BTreeMap<String, VectorClock>) {
+    let mut clock = clock_of(item);
+    *clock.entry(device.to_string()).or_insert(0) += 1;
+    tombstones.insert(item.id.clone(), clock);
+}
+
+/// 网盘客户端在两台设备同时修改时生成的冲突副本，例如 `store (conflicted copy 2024-01-01).json`
+fn is_conflicted_copy(path: &Path, store_path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    path != store_path && name.starts_with("store") && name.contains("conflict") && name.ends_with(".json")
+}
+
+/// 合并 store.json 的外部修改与目录中的冲突副本（合并后删除副本）
+pub(super) fn merge_external(store: &Store) -> Result<(), String> {
+    let store_path = store.path().to_path_buf();
+    store.merge_file(&store_path)?;
+
+    let dir = store_path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let copies: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_conflicted_copy(path, &store_path))
+        .collect();
+    for copy in copies {
+        store.merge_file(&copy)?;
+        std::fs::remove_file(&copy).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 监听 store.json 所在目录；store.json 被网盘客户端替换或出现冲突副本时合并进当前库
+pub(super) fn watch(store_path: &Path, app: AppHandle) -> Result<RecommendedWatcher, String> {
+    let (sender, receiver) = mpsc::channel::<()>();
+    let watched = store_path.to_path_buf();
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        let Ok(event) = result else {
+            return;
+        };
+        let relevant = !matches!(event.kind, EventKind::Access(_))
+            && event
+                .paths
+                .iter()
+                .any(|p| *p == watched || is_conflicted_copy(p, &watched));
+        if relevant {
+            let _ = sender.send(());
+        }
+    })
+    .map_err(|e| e.to_string())?;
+    let dir = store_path.parent().ok_or("Invalid store path")?;
+    watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(|e| e.to_string())?;
+
+    std::thread::spawn(move || {
+        while receiver.recv().is_ok() {
+            while receiver.recv_timeout(Duration::from_millis(500)).is_ok() {}
+            if let Err(e) = merge_external(&app.state::<Store>()) {
+                log_error!("Failed to merge external store changes: {}", e);
+            }
+        }
+    });
+    Ok(watcher)
+}
+
+impl SyncState {
+    /// 合并应用关闭期间的冲突副本，并开始监听 store.json 的外部修改
+    pub fn watch_store_file(&self, store: &Store) -> Result<(), String> {
+        merge_external(store)?;
+        let watcher = watch(store.path(), self.app.clone())?;
+        *self.store_watcher.lock().map_err(|e| e.to_string())? = Some(watcher);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ItemMetadata, ItemType};
+
+    fn clock(entries: &[(&str, u64)]) -> VectorClock {
+        entries
+            .iter()
+            .map(|(device, count)| (device.to_string(), *count))
+            .collect()
+    }
+
+    fn prompt(id: &str, content: &str, entries: &[(&str, u64)], modified: i64) -> TreeItem {
+        TreeItem {
+            id: id.to_string(),
+            name: id.to_string(),
+            item_type: ItemType::Prompt,
+            children: Vec::new(),
+            parent_id: None,
+            content: Some(content.into()),
+            versions: None,
+            metadata: ItemMetadata {
+                last_modified: Some(modified),
+                clock: Some(clock(entries)),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn file(items: Vec<TreeItem>) -> StoreFile {
+        StoreFile {
+            items,
+            tombstones: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn compare_orders_clocks() {
+        let a = clock(&[("a", 1)]);
+        assert_eq!(compare(&a, &a), Causality::Equal);
+        assert_eq!(compare(&a, &clock(&[("a", 2)])), Causality::Before);
+        assert_eq!(compare(&clock(&[("a", 1), ("b", 1)]), &a), Causality::After);
+        assert_eq!(compare(&a, &clock(&[("b", 1)])), Causality::Concurrent);
+    }
+
+    #[test]
+    fn merge_takes_the_later_side() {
+        let old = file(vec![prompt("p", "old", &[("a", 1)], 10)]);
+        let new = file(vec![prompt("p", "new", &[("a", 2)], 20)]);
+        for merged in [merge(&old, &new), merge(&new, &old)] {
+            assert_eq!(merged.items[0].content.as_deref(), Some("new"));
+            assert!(merged.items[0].versions.is_none());
+        }
+    }
+
+    #[test]
+    fn merge_pair_keeps_concurrent_loser_as_version() {
+        let local = file(vec![prompt("p", "local", &[("a", 1)], 10)]);
+        let remote = file(vec![prompt("p", "remote", &[("b", 1)], 20)]);
+        let merged = merge(&local, &remote);
+
+        let item = &merged.items[0];
+        assert_eq!(item.content.as_deref(), Some("remote"));
+        assert_eq!(item.metadata.clock, Some(clock(&[("a", 1), ("b", 1)])));
+        let versions = item.versions.as_ref().unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(&*versions[0].content, "local");
+    }
+
+    #[test]
+    fn merge_is_independent_of_argument_order() {
+        let mut local = file(vec![
+            prompt("p", "local", &[("a", 2)], 10),
+            prompt("only-local", "x", &[("a", 1)], 10),
+        ]);
+        bury(&prompt("gone", "", &[("a", 1)], 5), "a", &mut local.tombstones);
+        let remote = file(vec![
+            prompt("p", "remote", &[("a", 1), ("b", 1)], 10),
+            prompt("only-remote", "y", &[("b", 1)], 10),
+            prompt("gone", "", &[("a", 1)], 5),
+        ]);
+
+        let forward = serde_json::to_value(merge(&local, &remote)).unwrap();
+        let backward = serde_json::to_value(merge(&remote, &local)).unwrap();
+        assert_eq!(forward, backward);
+    }
+
+    #[test]
+    fn tombstone_removes_items_it_has_seen() {
+        let edited = prompt("p", "edited", &[("a", 1)], 10);
+        let mut local = file(Vec::new());
+        bury(&edited, "a", &mut local.tombstones);
+        let remote = file(vec![edited]);
+
+        for merged in [merge(&local, &remote), merge(&remote, &local)] {
+            assert!(merged.items.is_empty());
+            assert_eq!(merged.tombstones["p"], clock(&[("a", 2)]));
+        }
+    }
+
+    #[test]
+    fn concurrent_edit_survives_delete() {
+        let mut local = file(Vec::new());
+        bury(&prompt("p", "old", &[("a", 1)], 10), "a", &mut local.tombstones);
+        let remote = file(vec![prompt("p", "edited", &[("a", 1), ("b", 1)], 20)]);
+
+        for merged in [merge(&local, &remote), merge(&remote, &local)] {
+            assert_eq!(merged.items.len(), 1);
+            assert_eq!(merged.items[0].content.as_deref(), Some("edited"));
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use super::SyncState;
+use crate::log_error;
+use crate::store::Store;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// 监听同步目录的变化；连续的事件合并后触发一次导入。watcher 被丢弃时后台线程随之退出
+pub(super) fn watch(path: &Path, app: AppHandle) -> Result<RecommendedWatcher, String> {
+    let (sender, receiver) = mpsc::channel::<()>();
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        let Ok(event) = result else {
+            return;
+        };
+        let relevant = !matches!(event.kind, EventKind::Access(_))
+            && event
+                .paths
+                .iter()
+                .any(|p| !p.components().any(|c| c.as_os_str() == ".git"));
+        if relevant {
+            let _ = sender.send(());
+        }
+    })
+    .map_err(|e| e.to_string())?;
+    watcher
+        .watch(path, RecursiveMode::Recursive)
+        .map_err(|e| e.to_string())?;
+
+    std::thread::spawn(move || {
+        while receiver.recv().is_ok() {
+            while receiver.recv_timeout(Duration::from_millis(500)).is_ok() {}
+            let sync = app.state::<SyncState>();
+            let result = sync.import_folder(&app.state::<Store>());
+            if let Err(e) = &result {
+                log_error!("Folder sync failed: {}", e);
+                sync.report("folder", 0, &result);
+            }
+        }
+    });
+    Ok(watcher)
+}
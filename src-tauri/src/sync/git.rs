@@ -0,0 +1,134 @@
+use crate::models::GitSyncConfig;
+use std::path::Path;
+use std::process::Command;
+
+/// 在仓库目录中执行 git 命令，失败时返回 stderr
+fn git(repo: &Path, args: &[&str]) -> Result<String, String> {
+    git_raw(repo, args).map(|output| output.trim().to_string())
+}
+
+/// 同 [`git`]，但保留输出的原样内容（用于读取文件）
+fn git_raw(repo: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(repo)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        Err(format!("git {} failed: {}", args.first().unwrap_or(&""), stderr))
+    }
+}
+
+/// 确保仓库已初始化、位于配置的分支上并指向配置的远端
+pub(super) fn ensure_repo(repo: &Path, config: &GitSyncConfig) -> Result<(), String> {
+    std::fs::create_dir_all(repo).map_err(|e| e.to_string())?;
+    if !repo.join(".git").exists() {
+        git(repo, &["init"])?;
+        git(
+            repo,
+            &["symbolic-ref", "HEAD", &format!("refs/heads/{}", config.branch)],
+        )?;
+    }
+
+    if let Some(url) = config.remote_url.as_deref().filter(|u| !u.trim().is_empty()) {
+        let remotes = git(repo, &["remote"])?;
+        if remotes.lines().any(|r| r == "origin") {
+            git(repo, &["remote", "set-url", "origin", url])?;
+        } else {
+            git(repo, &["remote", "add", "origin", url])?;
+        }
+    }
+    Ok(())
+}
+
+/// 提交工作区的全部变更；没有变更时返回 false
+pub(super) fn commit_all(repo: &Path, message: &str) -> Result<bool, String> {
+    git(repo, &["add", "-A"])?;
+    if git(repo, &["status", "--porcelain"])?.is_empty() {
+        return Ok(false);
+    }
+    commit(repo, message)?;
+    Ok(true)
+}
+
+fn commit(repo: &Path, message: &str) -> Result<(), String> {
+    // 未配置提交身份时使用应用自己的身份，避免提交失败
+    let has_identity = git(repo, &["config", "user.email"]).is_ok_and(|email| !email.is_empty());
+    let mut args = Vec::new();
+    if !has_identity {
+        args.extend([
+            "-c",
+            "user.name=Prompt Manager",
+            "-c",
+            "user.email=prompt-manager@localhost",
+        ]);
+    }
+    args.extend(["commit", "-q", "-m", message]);
+    git(repo, &args)?;
+    Ok(())
+}
+
+fn require_remote(config: &GitSyncConfig) -> Result<(), String> {
+    match config.remote_url.as_deref() {
+        Some(url) if !url.trim().is_empty() => Ok(()),
+        _ => Err("No git remote configured".to_string()),
+    }
+}
+
+/// 从远端拉取并合并，返回有冲突的文件（此时合并仍在进行中，需要解决后调用 [`finish_merge`]）；
+/// 远端分支还不存在时什么也不做
+pub(super) fn pull(repo: &Path, config: &GitSyncConfig) -> Result<Vec<String>, String> {
+    require_remote(config)?;
+    git(repo, &["fetch", "origin"])?;
+    let remote_branch = format!("origin/{}", config.branch);
+    if git(repo, &["rev-parse", "--verify", "--quiet", &remote_branch]).is_err() {
+        return Ok(Vec::new());
+    }
+    if git(
+        repo,
+        &["merge", "--no-edit", "--allow-unrelated-histories", &remote_branch],
+    )
+    .is_ok()
+    {
+        return Ok(Vec::new());
+    }
+
+    // 关闭路径转义，保留中文等非 ASCII 文件名
+    let conflicted: Vec<String> = git(
+        repo,
+        &["-c", "core.quotepath=false", "diff", "--name-only", "--diff-filter=U"],
+    )?
+    .lines()
+    .map(str::to_string)
+    .collect();
+    if conflicted.is_empty() {
+        // 不是冲突导致的失败，放弃合并，保持工作区干净
+        abort_merge(repo);
+        return Err("git merge failed".to_string());
+    }
+    Ok(conflicted)
+}
+
+/// 冲突文件在合并中的某一版本：1 为共同祖先，2 为本地，3 为远端；该版本中不存在时返回 None
+pub(super) fn show_stage(repo: &Path, stage: u8, path: &str) -> Option<String> {
+    git_raw(repo, &["show", &format!(":{}:{}", stage, path)]).ok()
+}
+
+pub(super) fn abort_merge(repo: &Path) {
+    let _ = git(repo, &["merge", "--abort"]);
+}
+
+/// 冲突文件已写回工作区后完成合并提交
+pub(super) fn finish_merge(repo: &Path, message: &str) -> Result<(), String> {
+    git(repo, &["add", "-A"])?;
+    commit(repo, message)
+}
+
+pub(super) fn push(repo: &Path, config: &GitSyncConfig) -> Result<(), String> {
+    require_remote(config)?;
+    git(repo, &["push", "-u", "origin", &config.branch])?;
+    Ok(())
+}
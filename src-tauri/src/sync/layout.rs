@@ -0,0 +1,482 @@
+use crate::errors::{Error, ErrorCode};
+use crate::models::{ItemMetadata, ItemType, TreeItem};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// 目录条目（文件夹、带子节点的 Provider 等）的元数据文件
+const ITEM_FILE: &str = ".item.json";
+/// 记录同一目录下条目的顺序（id 列表）
+const ORDER_FILE: &str = ".order.json";
+/// 上次导出写入的文件列表（以 `/` 分隔的相对路径），也标记目录由本应用导出
+const MANIFEST_FILE: &str = ".prompt-manager.json";
+
+/// 将条目树写入目录：文件夹与带子节点的条目为子目录，元数据在 `.item.json`；
+/// 其余条目为 Markdown 文件，元数据放在 JSON front matter 中。
+/// 只写入有变化的文件，只删除上次导出写入、现在已不需要的文件；API Key 等凭据不会写出。
+/// 目录不为空又没有导出清单时拒绝写入，以免覆盖或删除用户自己的文件
+pub fn export(items: &[TreeItem], dir: &Path) -> Result<(), Error> {
+    let mut files = BTreeMap::new();
+    render_level(items, Path::new(""), &mut files);
+
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    for relative in read_manifest(dir)? {
+        if !files.contains_key(&relative) {
+            remove_written(dir, &relative)?;
+        }
+    }
+    for (relative, content) in &files {
+        let path = dir.join(relative);
+        if fs::read_to_string(&path).ok().as_deref() == Some(content.as_str()) {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(&path, content).map_err(|e| e.to_string())?;
+    }
+    let written: Vec<String> = files.keys().map(|path| manifest_path(path)).collect();
+    fs::write(dir.join(MANIFEST_FILE), to_json(&written)).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 检查能否导出到目录：目录为空或由本应用导出过
+pub fn check_target(dir: &Path) -> Result<(), Error> {
+    read_manifest(dir).map(|_| ())
+}
+
+/// 读取导出清单；没有清单时目录中只能有 `.git` 等隐藏文件
+fn read_manifest(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let Ok(content) = fs::read_to_string(dir.join(MANIFEST_FILE)) else {
+        let occupied = fs::read_dir(dir)
+            .map_err(|e| e.to_string())?
+            .flatten()
+            .any(|entry| !entry.file_name().to_string_lossy().starts_with('.'));
+        // 旧版本导出的目录没有清单，但根目录有 `.order.json`：继续使用，这次不删除任何文件
+        if occupied && !dir.join(ORDER_FILE).exists() {
+            return Err(Error::with(ErrorCode::SyncDirNotEmpty, dir.display()));
+        }
+        return Ok(Vec::new());
+    };
+    let paths: Vec<String> = serde_json::from_str(&content).map_err(|e| format!("Invalid {}: {}", MANIFEST_FILE, e))?;
+    // 清单可能来自远端仓库，只接受目录内的相对路径
+    Ok(paths
+        .iter()
+        .map(|path| path.split('/').collect::<PathBuf>())
+        .filter(|path| !path.as_os_str().is_empty() && path.components().all(|c| matches!(c, Component::Normal(_))))
+        .collect())
+}
+
+/// 清单中的路径统一用 `/` 分隔，在不同系统之间同步时保持一致
+fn manifest_path(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 删除上次导出写入的文件，以及因此变空的目录
+fn remove_written(root: &Path, relative: &Path) -> Result<(), String> {
+    let path = root.join(relative);
+    if path.is_file() {
+        fs::remove_file(&path).map_err(|e| e.to_string())?;
+    }
+    let mut dir = path.parent();
+    // 目录中还有其他文件时 remove_dir 失败，到此为止
+    while let Some(current) = dir.filter(|d| d.starts_with(root) && *d != root) {
+        if fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+    Ok(())
+}
+
+fn render_level(items: &[TreeItem], relative: &Path, files: &mut BTreeMap<PathBuf, String>) {
+    let mut used = HashSet::new();
+    let mut order = Vec::new();
+
+    for item in items {
+        order.push(item.id.clone());
+        if is_directory(item) {
+            let path = relative.join(unique_name(&file_name(&item.name), "", &mut used));
+            files.insert(path.join(ITEM_FILE), render_item_file(item));
+            render_level(&item.children, &path, files);
+        } else {
+            let path = relative.join(unique_name(&file_name(&item.name), ".md", &mut used));
+            files.insert(path, render_markdown(item));
+        }
+    }
+
+    if !order.is_empty() {
+        files.insert(relative.join(ORDER_FILE), to_json(&order));
+    }
+}
+
+/// 写入文件的条目头：不含子节点、父节点与凭据
+fn header(item: &TreeItem) -> TreeItem {
+    let mut header = item.clone();
+    header.children.clear();
+    header.parent_id = None;
+    Secrets::take(&mut header.metadata);
+    header
+}
+
+fn render_item_file(item: &TreeItem) -> String {
+    to_json(&header(item))
+}
+
+fn render_markdown(item: &TreeItem) -> String {
+    let mut header = header(item);
+    let content = header.content.take();
+    format!("---\n{}\n---\n{}", to_json(&header), content.as_deref().unwrap_or_default())
+}
+
+/// 是否为保存单个条目的文件（`.item.json` 或 Markdown）
+pub fn is_item_file(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name == ITEM_FILE) || path.extension().is_some_and(|ext| ext == "md")
+}
+
+/// 解析单个条目文件；不是条目文件时返回 None
+pub fn parse_file(path: &Path, text: &str) -> Option<TreeItem> {
+    if !is_item_file(path) {
+        None
+    } else if path.file_name().is_some_and(|name| name == ITEM_FILE) {
+        serde_json::from_str(text).ok()
+    } else {
+        Some(parse_markdown(text, path))
+    }
+}
+
+/// 与 [`parse_file`] 对应，把条目写回同一种文件格式
+pub fn render_file(path: &Path, item: &TreeItem) -> String {
+    if path.file_name().is_some_and(|name| name == ITEM_FILE) {
+        render_item_file(item)
+    } else {
+        render_markdown(item)
+    }
+}
+
+fn is_directory(item: &TreeItem) -> bool {
+    item.item_type == ItemType::Folder || !item.children.is_empty()
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+/// 把条目名转换为合法的文件名
+pub fn file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('.').trim();
+    if cleaned.is_empty() {
+        "untitled".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+/// 同一目录下重名时追加序号
+fn unique_name(base: &str, extension: &str, used: &mut HashSet<String>) -> String {
+    let mut candidate = format!("{}{}", base, extension);
+    let mut n = 2;
+    while !used.insert(candidate.to_lowercase()) {
+        candidate = format!("{} ({}){}", base, n, extension);
+        n += 1;
+    }
+    candidate
+}
+
+/// 从目录读回条目树；没有 front matter 的 Markdown 文件作为新的 prompt 导入
+pub fn import(dir: &Path) -> Result<Vec<TreeItem>, String> {
+    read_level(dir, None)
+}
+
+fn read_level(dir: &Path, parent_id: Option<&str>) -> Result<Vec<TreeItem>, String> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .collect();
+    entries.sort_by_key(|e| e.file_name());
+
+    let mut items = Vec::new();
+    for entry in entries {
+        let path = entry.path();
+        let mut item = if path.is_dir() {
+            let mut item = fs::read_to_string(path.join(ITEM_FILE))
+                .ok()
+                .and_then(|content| serde_json::from_str::<TreeItem>(&content).ok())
+                .unwrap_or_else(|| new_item(&entry.file_name().to_string_lossy(), ItemType::Folder, &path));
+            item.children = read_level(&path, Some(&item.id))?;
+            item
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            let text = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+            parse_markdown(&text, &path)
+        } else {
+            continue;
+        };
+        item.parent_id = parent_id.map(str::to_string);
+        items.push(item);
+    }
+
+    let order: Vec<String> = fs::read_to_string(dir.join(ORDER_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    let position: HashMap<&str, usize> = order.iter().enumerate().map(|(i, id)| (id.as_str(), i)).collect();
+    // 不在顺序表中的新条目排在最后，保持文件名顺序
+    items.sort_by_key(|item| position.get(item.id.as_str()).copied().unwrap_or(usize::MAX));
+    Ok(items)
+}
+
+fn parse_markdown(text: &str, path: &Path) -> TreeItem {
+    let normalized = text.replace("\r\n", "\n");
+    let front_matter = normalized.strip_prefix("---\n").and_then(|rest| {
+        let end = rest.find("\n---\n").map(|i| (i, i + 5)).or_else(|| {
+            // front matter 之后没有正文
+            rest.strip_suffix("\n---").map(|header| (header.len(), rest.len()))
+        })?;
+        let header: TreeItem = serde_json::from_str(&rest[..end.0]).ok()?;
+        Some((header, rest[end.1..].to_string()))
+    });
+
+    match front_matter {
+        Some((mut item, content)) => {
+            item.content = Some(content.into());
+            item
+        }
+        None => {
+            let name = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let mut item = new_item(&name, ItemType::Prompt, path);
+            item.content = Some(normalized.into());
+            item
+        }
+    }
+}
+
+fn new_item(name: &str, item_type: ItemType, path: &Path) -> TreeItem {
+    let modified = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .map(|time| chrono::DateTime::<chrono::Utc>::from(time).timestamp_millis());
+    TreeItem {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        item_type,
+        children: Vec::new(),
+        parent_id: None,
+        content: None,
+        versions: None,
+        metadata: ItemMetadata {
+            last_modified: modified,
+            ..Default::default()
+        },
+    }
+}
+
+/// 不离开本机的凭据：API Key、代理的用户名与密码、认证请求头与附加请求头
+#[derive(Default)]
+struct Secrets {
+    api_key: Option<String>,
+    proxy_username: Option<String>,
+    proxy_password: Option<String>,
+    auth_header: Option<String>,
+    extra_headers: Option<HashMap<String, String>>,
+}
+
+impl Secrets {
+    /// 从元数据中取出凭据，代理地址保留
+    fn take(metadata: &mut ItemMetadata) -> Self {
+        let (proxy_username, proxy_password) = match metadata.proxy.as_mut() {
+            Some(proxy) => (proxy.username.take(), proxy.password.take()),
+            None => (None, None),
+        };
+        Secrets {
+            api_key: metadata.api_key.take(),
+            proxy_username,
+            proxy_password,
+            auth_header: metadata.auth_header.take(),
+            extra_headers: metadata.extra_headers.take(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.api_key.is_none()
+            && self.proxy_username.is_none()
+            && self.proxy_password.is_none()
+            && self.auth_header.is_none()
+            && self.extra_headers.is_none()
+    }
+
+    /// 写回导入的元数据；代理凭据只在导入的条目仍配置了代理时恢复
+    fn restore(&self, metadata: &mut ItemMetadata) {
+        if self.api_key.is_some() {
+            metadata.api_key = self.api_key.clone();
+        }
+        if let Some(proxy) = metadata.proxy.as_mut() {
+            if self.proxy_username.is_some() {
+                proxy.username = self.proxy_username.clone();
+            }
+            if self.proxy_password.is_some() {
+                proxy.password = self.proxy_password.clone();
+            }
+        }
+        if self.auth_header.is_some() {
+            metadata.auth_header = self.auth_header.clone();
+        }
+        if self.extra_headers.is_some() {
+            metadata.extra_headers = self.extra_headers.clone();
+        }
+    }
+}
+
+/// 导出时去掉了凭据，导入后按 id 从本地的条目中恢复
+pub fn restore_secrets(items: &mut [TreeItem], local: &[TreeItem]) {
+    let mut secrets = HashMap::new();
+    collect_secrets(local, &mut secrets);
+    apply_secrets(items, &secrets);
+}
+
+fn collect_secrets(items: &[TreeItem], secrets: &mut HashMap<String, Secrets>) {
+    for item in items {
+        let found = Secrets::take(&mut item.metadata.clone());
+        if !found.is_empty() {
+            secrets.insert(item.id.clone(), found);
+        }
+        collect_secrets(&item.children, secrets);
+    }
+}
+
+fn apply_secrets(items: &mut [TreeItem], secrets: &HashMap<String, Secrets>) {
+    for item in items {
+        if let Some(found) = secrets.get(&item.id) {
+            found.restore(&mut item.metadata);
+        }
+        apply_secrets(&mut item.children, secrets);
+    }
+}
+
+/// 去掉凭据后的副本，用于上传到同步目标、局域网发送与导出
+pub fn strip_secrets(items: &[TreeItem]) -> Vec<TreeItem> {
+    let mut items = items.to_vec();
+    clear_secrets(&mut items);
+    items
+}
+
+fn clear_secrets(items: &mut [TreeItem]) {
+    for item in items {
+        Secrets::take(&mut item.metadata);
+        clear_secrets(&mut item.children);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(id: &str) -> TreeItem {
+        TreeItem {
+            id: id.to_string(),
+            name: id.to_string(),
+            item_type: ItemType::Prompt,
+            children: Vec::new(),
+            parent_id: None,
+            content: Some(format!("content of {}", id).into()),
+            versions: None,
+            metadata: ItemMetadata::default(),
+        }
+    }
+
+    fn folder(id: &str, children: Vec<TreeItem>) -> TreeItem {
+        let mut item = prompt(id);
+        item.item_type = ItemType::Folder;
+        item.content = None;
+        item.children = children;
+        item
+    }
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("prompt-manager-layout-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn refuses_non_empty_directory_without_manifest() {
+        let dir = temp_dir();
+        fs::write(dir.join("notes.md"), "my notes").unwrap();
+
+        assert!(export(&[prompt("a")], &dir).is_err());
+        assert_eq!(fs::read_to_string(dir.join("notes.md")).unwrap(), "my notes");
+        assert!(!dir.join("a.md").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn hidden_files_do_not_block_the_first_export() {
+        let dir = temp_dir();
+        fs::create_dir(dir.join(".git")).unwrap();
+
+        export(&[prompt("a")], &dir).unwrap();
+        assert!(dir.join("a.md").exists());
+        assert!(dir.join(MANIFEST_FILE).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn only_removes_files_written_by_the_previous_export() {
+        let dir = temp_dir();
+        export(&[prompt("a"), folder("f", vec![prompt("b")])], &dir).unwrap();
+        assert!(dir.join("f").join("b.md").exists());
+
+        // 导出之后用户自己放进来的目录与 Markdown 文件
+        fs::create_dir(dir.join("notes")).unwrap();
+        fs::write(dir.join("notes").join("todo.txt"), "todo").unwrap();
+        fs::write(dir.join("readme.md"), "readme").unwrap();
+        fs::write(dir.join("f").join("draft.md"), "draft").unwrap();
+
+        export(&[folder("f", Vec::new())], &dir).unwrap();
+        assert!(!dir.join("a.md").exists());
+        assert!(!dir.join("f").join("b.md").exists());
+        assert_eq!(fs::read_to_string(dir.join("notes").join("todo.txt")).unwrap(), "todo");
+        assert_eq!(fs::read_to_string(dir.join("readme.md")).unwrap(), "readme");
+        assert_eq!(fs::read_to_string(dir.join("f").join("draft.md")).unwrap(), "draft");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn removes_directories_left_empty() {
+        let dir = temp_dir();
+        export(&[folder("f", vec![prompt("b")])], &dir).unwrap();
+
+        export(&[prompt("a")], &dir).unwrap();
+        assert!(!dir.join("f").exists());
+        assert!(dir.join("a.md").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn ignores_manifest_paths_outside_the_directory() {
+        let dir = temp_dir();
+        let outside = dir.join("outside.md");
+        let root = dir.join("library");
+        fs::create_dir(&root).unwrap();
+        fs::write(&outside, "keep").unwrap();
+        fs::write(root.join(MANIFEST_FILE), to_json(&["../outside.md"])).unwrap();
+
+        export(&[prompt("a")], &root).unwrap();
+        assert!(outside.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
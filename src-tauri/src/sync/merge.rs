@@ -0,0 +1,494 @@
+use super::crdt;
+use crate::models::{ItemMetadata, PromptVersion, TreeItem, VectorClock};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+/// 单个条目三方合并的结果；`conflict` 为 true 时冲突部分保留了本地版本
+pub struct ItemMerge {
+    pub item: Option<TreeItem>,
+    pub conflict: bool,
+}
+
+/// 冲突条目的三个版本
+pub struct Conflict {
+    pub id: String,
+    pub base: Option<TreeItem>,
+    pub local: Option<TreeItem>,
+    pub remote: Option<TreeItem>,
+}
+
+/// 三个值中选出合并结果：只有一侧修改时取修改的一侧，两侧改成相同时取其一，否则冲突
+fn pick<T: PartialEq + Clone>(base: &T, local: &T, remote: &T) -> Option<T> {
+    if local == remote || remote == base {
+        Some(local.clone())
+    } else if local == base {
+        Some(remote.clone())
+    } else {
+        None
+    }
+}
+
+/// 合并单个条目（不含子节点）；任一侧不存在表示该侧删除或新增了此条目
+pub fn merge_item(base: Option<&TreeItem>, local: Option<&TreeItem>, remote: Option<&TreeItem>) -> ItemMerge {
+    let (local, remote) = match (local, remote) {
+        (Some(local), Some(remote)) => (local, remote),
+        // 一侧删除：另一侧未修改时删除，修改过时保留修改并视为冲突
+        (Some(kept), None) | (None, Some(kept)) => {
+            let unchanged = base.is_some_and(|b| same(b, kept));
+            return match base {
+                Some(_) if unchanged => ItemMerge {
+                    item: None,
+                    conflict: false,
+                },
+                Some(_) => ItemMerge {
+                    item: Some(kept.clone()),
+                    conflict: true,
+                },
+                // 只在一侧新增
+                None => ItemMerge {
+                    item: Some(kept.clone()),
+                    conflict: false,
+                },
+            };
+        }
+        (None, None) => {
+            return ItemMerge {
+                item: None,
+                conflict: false,
+            }
+        }
+    };
+
+    // 两侧都新增了同一个 id 时以空条目为基准
+    let empty = TreeItem {
+        id: local.id.clone(),
+        name: String::new(),
+        item_type: local.item_type.clone(),
+        children: Vec::new(),
+        parent_id: None,
+        content: None,
+        versions: None,
+        metadata: ItemMetadata::default(),
+    };
+    let base = base.unwrap_or(&empty);
+    let mut conflict = false;
+    let mut merged = local.clone();
+
+    merged.name = pick(&base.name, &local.name, &remote.name).unwrap_or_else(|| {
+        conflict = true;
+        local.name.clone()
+    });
+    merged.item_type = pick(&base.item_type, &local.item_type, &remote.item_type).unwrap_or_else(|| {
+        conflict = true;
+        local.item_type.clone()
+    });
+    merged.content = match pick(&base.content, &local.content, &remote.content) {
+        Some(content) => content,
+        None => {
+            let text = merge_text(
+                base.content.as_deref().unwrap_or_default(),
+                local.content.as_deref().unwrap_or_default(),
+                remote.content.as_deref().unwrap_or_default(),
+            );
+            if text.is_none() {
+                conflict = true;
+            }
+            text.map(Into::into).or_else(|| local.content.clone())
+        }
+    };
+    merged.versions = merge_versions(&local.versions, &remote.versions);
+
+    let (metadata, metadata_conflict) = merge_metadata(&base.metadata, &local.metadata, &remote.metadata);
+    if let Some(metadata) = metadata {
+        merged.metadata = metadata;
+    }
+    conflict |= metadata_conflict;
+
+    ItemMerge {
+        item: Some(merged),
+        conflict,
+    }
+}
+
+fn same(a: &TreeItem, b: &TreeItem) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// 历史版本只会追加，按 id 取并集
+fn merge_versions(
+    local: &Option<Vec<PromptVersion>>,
+    remote: &Option<Vec<PromptVersion>>,
+) -> Option<Vec<PromptVersion>> {
+    let (Some(local), Some(remote)) = (local, remote) else {
+        return local.clone().or_else(|| remote.clone());
+    };
+    let mut versions = local.clone();
+    versions.extend(remote.iter().filter(|v| !local.iter().any(|l| l.id == v.id)).cloned());
+    Some(versions)
+}
+
+/// 按字段合并 metadata，lastModified 取较新的一侧
+fn merge_metadata(base: &ItemMetadata, local: &ItemMetadata, remote: &ItemMetadata) -> (Option<ItemMetadata>, bool) {
+    let to_map = |m: &ItemMetadata| match serde_json::to_value(m) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+    let (base, local, remote) = (to_map(base), to_map(local), to_map(remote));
+
+    let mut conflict = false;
+    let mut merged = Map::new();
+    let keys: HashSet<&String> = base.keys().chain(local.keys()).chain(remote.keys()).collect();
+    for key in keys {
+        let value = |map: &Map<String, Value>| map.get(key).cloned().unwrap_or(Value::Null);
+        let (b, l, r) = (value(&base), value(&local), value(&remote));
+        let chosen = if key == "lastModified" {
+            if l.as_i64() >= r.as_i64() {
+                l
+            } else {
+                r
+            }
+        } else if key == "clock" && !(l.is_null() && r.is_null()) {
+            let clock = |v: Value| {
+                serde_json::from_value::<Option<VectorClock>>(v)
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default()
+            };
+            serde_json::to_value(crdt::join(&clock(l), &clock(r))).unwrap_or(Value::Null)
+        } else {
+            pick(&b, &l, &r).unwrap_or_else(|| {
+                conflict = true;
+                l
+            })
+        };
+        merged.insert(key.clone(), chosen);
+    }
+    (serde_json::from_value(Value::Object(merged)).ok(), conflict)
+}
+
+/// 行级三方合并：两侧修改了不重叠的区域时自动合并，修改区域重叠且内容不同时返回 None
+pub fn merge_text(base: &str, local: &str, remote: &str) -> Option<String> {
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let local: Vec<&str> = local.split_inclusive('\n').collect();
+    let remote: Vec<&str> = remote.split_inclusive('\n').collect();
+    let to_local = matching_lines(&base, &local)?;
+    let to_remote = matching_lines(&base, &remote)?;
+
+    let mut output = String::new();
+    let (mut b, mut l, mut r) = (0, 0, 0);
+    loop {
+        // 下一个两侧都没有改动的基准行
+        let anchor = (b..base.len()).find_map(|k| match (to_local[k], to_remote[k]) {
+            (Some(lk), Some(rk)) if lk >= l && rk >= r => Some((k, lk, rk)),
+            _ => None,
+        });
+        let (bk, lk, rk) = anchor.unwrap_or((base.len(), local.len(), remote.len()));
+
+        let (base_chunk, local_chunk, remote_chunk) = (&base[b..bk], &local[l..lk], &remote[r..rk]);
+        let chunk = if local_chunk == remote_chunk || remote_chunk == base_chunk {
+            local_chunk
+        } else if local_chunk == base_chunk {
+            remote_chunk
+        } else {
+            return None;
+        };
+        output.extend(chunk.iter().copied());
+        if anchor.is_none() {
+            break;
+        }
+        output.push_str(base[bk]);
+        (b, l, r) = (bk + 1, lk + 1, rk + 1);
+    }
+    Some(output)
+}
+
+/// 基于最长公共子序列，返回 a 的每一行在 b 中对应的行；内容过长时放弃（返回 None）
+fn matching_lines(a: &[&str], b: &[&str]) -> Option<Vec<Option<usize>>> {
+    let mut result = vec![None; a.len()];
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    for (i, slot) in result.iter_mut().enumerate().take(prefix) {
+        *slot = Some(i);
+    }
+    for k in 0..suffix {
+        result[a.len() - 1 - k] = Some(b.len() - 1 - k);
+    }
+
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+    let (n, m) = (a_mid.len(), b_mid.len());
+    if n * m > 4_000_000 {
+        return None;
+    }
+    // lengths[i][j]：a_mid[i..] 与 b_mid[j..] 的最长公共子序列长度
+    let mut lengths = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if a_mid[i] == b_mid[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a_mid[i] == b_mid[j] {
+            result[prefix + i] = Some(prefix + j);
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    Some(result)
+}
+
+/// 展平后的条目：不含子节点，记录父节点与在兄弟中的位置
+pub(super) struct Flat {
+    pub item: TreeItem,
+    pub parent: Option<String>,
+    pub index: usize,
+}
+
+pub(super) fn flatten(
+    items: &[TreeItem],
+    parent: Option<&str>,
+    out: &mut HashMap<String, Flat>,
+    order: &mut Vec<String>,
+) {
+    for (index, item) in items.iter().enumerate() {
+        let mut flat = item.clone();
+        flat.children = Vec::new();
+        order.push(item.id.clone());
+        out.insert(
+            item.id.clone(),
+            Flat {
+                item: flat,
+                parent: parent.map(str::to_string),
+                index,
+            },
+        );
+        flatten(&item.children, Some(&item.id), out, order);
+    }
+}
+
+/// 合并整个条目树；返回合并结果与未能自动合并的条目
+pub fn merge_trees(base: &[TreeItem], local: &[TreeItem], remote: &[TreeItem]) -> (Vec<TreeItem>, Vec<Conflict>) {
+    let (mut base_map, mut local_map, mut remote_map) = (HashMap::new(), HashMap::new(), HashMap::new());
+    let (mut local_order, mut remote_order) = (Vec::new(), Vec::new());
+    flatten(base, None, &mut base_map, &mut Vec::new());
+    flatten(local, None, &mut local_map, &mut local_order);
+    flatten(remote, None, &mut remote_map, &mut remote_order);
+
+    // 本地顺序在前，其后是只在远端出现的条目；已删除的条目也要参与判断
+    let mut ids = local_order.clone();
+    let mut seen: HashSet<String> = local_order.into_iter().collect();
+    for id in remote_order.into_iter().chain(base_map.keys().cloned()) {
+        if seen.insert(id.clone()) {
+            ids.push(id);
+        }
+    }
+
+    let mut conflicts = Vec::new();
+    let mut merged: Vec<(String, TreeItem, Option<String>, (usize, usize))> = Vec::new();
+    for (position, id) in ids.iter().enumerate() {
+        let (b, l, r) = (base_map.get(id), local_map.get(id), remote_map.get(id));
+        let result = merge_item(b.map(|f| &f.item), l.map(|f| &f.item), r.map(|f| &f.item));
+        if result.conflict {
+            conflicts.push(Conflict {
+                id: id.clone(),
+                base: b.map(|f| f.item.clone()),
+                local: l.map(|f| f.item.clone()),
+                remote: r.map(|f| f.item.clone()),
+            });
+        }
+        let Some(item) = result.item else {
+            continue;
+        };
+
+        let parent_of = |f: Option<&Flat>| f.map(|f| f.parent.clone());
+        let parent = match (parent_of(b), parent_of(l), parent_of(r)) {
+            (Some(bp), Some(lp), Some(rp)) => pick(&bp, &lp, &rp).unwrap_or(lp),
+            (_, Some(lp), _) => lp,
+            (_, None, Some(rp)) => rp,
+            _ => None,
+        };
+        // 本地已有的条目按本地位置排序，远端新增的条目排在其后
+        let sort_key = match (l, r) {
+            (Some(f), _) => (0, f.index),
+            (None, Some(f)) => (1, f.index),
+            _ => (2, position),
+        };
+        merged.push((id.clone(), item, parent, sort_key));
+    }
+
+    (build_tree(merged), conflicts)
+}
+
+pub(super) fn build_tree(mut flat: Vec<(String, TreeItem, Option<String>, (usize, usize))>) -> Vec<TreeItem> {
+    flat.sort_by_key(|(_, _, _, key)| *key);
+    let existing: HashSet<String> = flat.iter().map(|(id, ..)| id.clone()).collect();
+    let mut children: HashMap<Option<String>, Vec<String>> = HashMap::new();
+    let mut items: HashMap<String, TreeItem> = HashMap::new();
+    for (id, item, parent, _) in flat {
+        // 父节点已被删除时挂到根下
+        let parent = parent.filter(|p| existing.contains(p));
+        children.entry(parent).or_default().push(id.clone());
+        items.insert(id, item);
+    }
+
+    let mut placed = HashSet::new();
+    let mut roots = attach(None, &children, &mut items, &mut placed);
+    // 两侧的移动形成环时，环上的条目无法从根到达，放回根下
+    let unreachable: Vec<String> = items.keys().filter(|id| !placed.contains(*id)).cloned().collect();
+    for id in unreachable {
+        if let Some(mut item) = items.remove(&id) {
+            placed.insert(id.clone());
+            item.parent_id = None;
+            item.children = attach(Some(id), &children, &mut items, &mut placed);
+            roots.push(item);
+        }
+    }
+    roots
+}
+
+fn attach(
+    parent: Option<String>,
+    children: &HashMap<Option<String>, Vec<String>>,
+    items: &mut HashMap<String, TreeItem>,
+    placed: &mut HashSet<String>,
+) -> Vec<TreeItem> {
+    let mut result = Vec::new();
+    for id in children.get(&parent).into_iter().flatten() {
+        if !placed.insert(id.clone()) {
+            continue;
+        }
+        let Some(mut item) = items.remove(id) else {
+            continue;
+        };
+        item.parent_id = parent.clone();
+        item.children = attach(Some(id.clone()), children, items, placed);
+        result.push(item);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ItemType;
+
+    fn prompt(id: &str, content: &str) -> TreeItem {
+        TreeItem {
+            id: id.to_string(),
+            name: id.to_string(),
+            item_type: ItemType::Prompt,
+            children: Vec::new(),
+            parent_id: None,
+            content: Some(content.into()),
+            versions: None,
+            metadata: ItemMetadata::default(),
+        }
+    }
+
+    fn folder(id: &str, children: Vec<TreeItem>) -> TreeItem {
+        let mut item = prompt(id, "");
+        item.item_type = ItemType::Folder;
+        item.content = None;
+        item.children = children
+            .into_iter()
+            .map(|mut child| {
+                child.parent_id = Some(id.to_string());
+                child
+            })
+            .collect();
+        item
+    }
+
+    fn to_json(items: &[TreeItem]) -> Value {
+        serde_json::to_value(items).unwrap()
+    }
+
+    #[test]
+    fn merge_text_combines_non_overlapping_edits() {
+        let merged = merge_text("a\nb\nc\nd\n", "A\nb\nc\nd\n", "a\nb\nc\nD\n");
+        assert_eq!(merged.as_deref(), Some("A\nb\nc\nD\n"));
+    }
+
+    #[test]
+    fn merge_text_rejects_overlapping_edits() {
+        assert_eq!(merge_text("a\nb\nc\n", "a\nX\nc\n", "a\nY\nc\n"), None);
+        assert_eq!(merge_text("a\nb\nc\n", "a\nY\nc\n", "a\nX\nc\n"), None);
+    }
+
+    #[test]
+    fn merge_text_accepts_identical_edits() {
+        let merged = merge_text("a\nb\nc\n", "a\nX\nc\n", "a\nX\nc\n");
+        assert_eq!(merged.as_deref(), Some("a\nX\nc\n"));
+    }
+
+    #[test]
+    fn merge_item_keeps_edit_over_delete_as_conflict() {
+        let base = prompt("p", "old");
+        let edited = prompt("p", "new");
+
+        let result = merge_item(Some(&base), None, Some(&edited));
+        assert!(result.conflict);
+        assert_eq!(result.item.and_then(|i| i.content).as_deref(), Some("new"));
+
+        let result = merge_item(Some(&base), None, Some(&base));
+        assert!(!result.conflict);
+        assert!(result.item.is_none());
+    }
+
+    #[test]
+    fn flatten_and_build_tree_round_trip() {
+        let tree = vec![folder("f", vec![prompt("a", "1"), prompt("b", "2")]), prompt("c", "3")];
+        let (mut map, mut order) = (HashMap::new(), Vec::new());
+        flatten(&tree, None, &mut map, &mut order);
+        assert_eq!(order, ["f", "a", "b", "c"]);
+        assert_eq!(map["b"].parent.as_deref(), Some("f"));
+        assert_eq!(map["b"].index, 1);
+        assert!(map["f"].item.children.is_empty());
+
+        let flat = order
+            .iter()
+            .enumerate()
+            .map(|(rank, id)| {
+                let flat = map.remove(id).unwrap();
+                (id.clone(), flat.item, flat.parent, (0, rank))
+            })
+            .collect();
+        assert_eq!(to_json(&build_tree(flat)), to_json(&tree));
+    }
+
+    #[test]
+    fn build_tree_moves_orphans_to_root() {
+        let flat = vec![("a".to_string(), prompt("a", ""), Some("gone".to_string()), (0, 0))];
+        let tree = build_tree(flat);
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].parent_id, None);
+    }
+
+    #[test]
+    fn merge_trees_applies_both_sides() {
+        let base = vec![prompt("a", "1\n"), prompt("b", "2\n")];
+        let local = vec![prompt("a", "1 local\n")];
+        let remote = vec![prompt("a", "1\n"), prompt("b", "2\n"), prompt("c", "3\n")];
+
+        let (merged, conflicts) = merge_trees(&base, &local, &remote);
+        assert!(conflicts.is_empty());
+        assert_eq!(
+            to_json(&merged),
+            to_json(&[prompt("a", "1 local\n"), prompt("c", "3\n")])
+        );
+    }
+}
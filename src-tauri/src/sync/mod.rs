@@ -0,0 +1,595 @@
+pub mod crdt;
+mod folder;
+mod git;
+mod layout;
+mod merge;
+mod status;
+mod webdav;
+
+use crate::errors::{Error, ErrorCode};
+use crate::log_error;
+use crate::models::{
+    ConflictChoice, GitSyncConfig, StoreChange, SyncConfig, SyncConflict, SyncStatus, TreeItem, WebDavConfig,
+};
+use crate::store::Store;
+use serde::{Deserialize, Serialize};
+use status::StatusLog;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use webdav::{Remote, Upload};
+
+pub use layout::{export as export_layout, file_name, import as import_layout, strip_secrets};
+
+/// 等待写入同步目标的一次库快照
+struct SyncJob {
+    items: Vec<TreeItem>,
+    summary: String,
+    /// 排队时的变更序号
+    seq: u64,
+}
+
+/// 上次成功同步 WebDAV 时远端的 ETag 与内容，用于判断哪一侧发生了变化
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct WebDavBase {
+    etag: Option<String>,
+    items: Vec<TreeItem>,
+}
+
+/// 同步状态：配置保存在 sync_config.json；写入同步目标（git 提交、目录导出）在后台线程中按顺序执行，不阻塞保存
+pub struct SyncState {
+    config: Mutex<SyncConfig>,
+    config_path: PathBuf,
+    data_dir: PathBuf,
+    /// 串行化对仓库的读写
+    repo_lock: Mutex<()>,
+    jobs: Mutex<Sender<SyncJob>>,
+    client: reqwest::Client,
+    /// 串行化 WebDAV 同步（手动与定时）
+    webdav_lock: tokio::sync::Mutex<()>,
+    app: AppHandle,
+    folder_watcher: Mutex<Option<notify::RecommendedWatcher>>,
+    /// 监听网盘客户端对 store.json 的修改
+    store_watcher: Mutex<Option<notify::RecommendedWatcher>>,
+    /// 同步目录中最近一次写出或读入的内容（不含凭据），用来区分目录中的外部修改与自己的写入
+    folder_base: Mutex<Option<Vec<TreeItem>>>,
+    /// 未解决的冲突，保存在 sync_conflicts.json
+    conflicts: Mutex<Vec<SyncConflict>>,
+    status: Mutex<StatusLog>,
+}
+
+impl SyncState {
+    pub fn new(app: &AppHandle, dir: PathBuf) -> Self {
+        let config_path = dir.join("sync_config.json");
+        let config = fs::read_to_string(&config_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        let (sender, receiver) = mpsc::channel::<SyncJob>();
+        let worker = app.clone();
+        std::thread::spawn(move || {
+            for job in receiver {
+                let sync = worker.state::<SyncState>();
+                let committed = sync.auto_commit(&job);
+                if let Err(e) = &committed {
+                    log_error!("Auto commit failed: {}", e);
+                }
+                // 没有远端时提交到本地仓库即算同步完成
+                if let Some((git, _)) = sync.git() {
+                    let has_remote = git.remote_url.is_some_and(|u| !u.trim().is_empty());
+                    if committed.is_err() || !has_remote {
+                        sync.report("git", job.seq, &committed);
+                    }
+                }
+                let exported = sync.export_folder(&job.items);
+                if let Err(e) = &exported {
+                    log_error!("Folder export failed: {}", e);
+                }
+                if sync.folder().is_some() {
+                    sync.report("folder", job.seq, &exported);
+                }
+            }
+        });
+        let folder_base = fs::read_to_string(dir.join("folder_base.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok());
+        let conflicts = fs::read_to_string(dir.join("sync_conflicts.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        // 定时同步 WebDAV；间隔为 0 或未启用时每分钟检查一次配置
+        let timer = app.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let interval = timer
+                    .try_state::<SyncState>()
+                    .and_then(|sync| sync.webdav())
+                    .map(|config| config.interval_minutes)
+                    .filter(|&minutes| minutes > 0);
+                tokio::time::sleep(Duration::from_secs(60 * interval.unwrap_or(1) as u64)).await;
+                if interval.is_none() {
+                    continue;
+                }
+                if let Some(sync) = timer.try_state::<SyncState>() {
+                    if let Err(e) = sync.webdav_sync(&timer.state::<Store>()).await {
+                        log_error!("WebDAV sync failed: {}", e);
+                    }
+                }
+            }
+        });
+
+        SyncState {
+            config: Mutex::new(config),
+            config_path,
+            data_dir: dir.clone(),
+            repo_lock: Mutex::new(()),
+            jobs: Mutex::new(sender),
+            client: reqwest::Client::new(),
+            webdav_lock: tokio::sync::Mutex::new(()),
+            app: app.clone(),
+            folder_watcher: Mutex::new(None),
+            store_watcher: Mutex::new(None),
+            folder_base: Mutex::new(folder_base),
+            conflicts: Mutex::new(conflicts),
+            status: Mutex::new(StatusLog::load(&dir)),
+        }
+    }
+
+    pub fn config(&self) -> SyncConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    pub fn set_config(&self, config: SyncConfig) -> Result<SyncConfig, Error> {
+        if let Some(git) = &config.git {
+            if git.branch.trim().is_empty() {
+                return Err(ErrorCode::BranchRequired.into());
+            }
+        }
+        if let Some(webdav) = config.webdav.as_ref().filter(|w| w.enabled) {
+            if !webdav.url.starts_with("http://") && !webdav.url.starts_with("https://") {
+                return Err(ErrorCode::InvalidWebDavUrl.into());
+            }
+        }
+        let content = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+        fs::write(&self.config_path, content).map_err(|e| e.to_string())?;
+        *self.config.lock().map_err(|e| e.to_string())? = config.clone();
+        Ok(config)
+    }
+
+    /// 加密只保护 store.json，git 仓库、同步目录与 WebDAV 上的副本以及本地的同步基准、冲突记录都是明文，
+    /// 所以启用加密前要求关闭全部同步目标、解决冲突，并删除残留的同步基准
+    pub fn prepare_encryption(&self) -> Result<(), Error> {
+        if has_targets(&self.config()) {
+            return Err(ErrorCode::SyncTargetsEnabled.into());
+        }
+        if !self.conflicts.lock().map_err(|e| e.to_string())?.is_empty() {
+            return Err(ErrorCode::UnresolvedConflicts.into());
+        }
+        let mut folder_base = self.folder_base.lock().map_err(|e| e.to_string())?;
+        for path in [self.data_dir.join("folder_base.json"), self.webdav_base_path()] {
+            if path.exists() {
+                fs::remove_file(&path).map_err(|e| e.to_string())?;
+            }
+        }
+        *folder_base = None;
+        Ok(())
+    }
+
+    /// 启用的 git 配置与仓库目录
+    fn git(&self) -> Option<(GitSyncConfig, PathBuf)> {
+        let config = self.config.lock().unwrap().git.clone().filter(|g| g.enabled)?;
+        let repo = config
+            .repo_path
+            .as_deref()
+            .filter(|p| !p.trim().is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| self.data_dir.join("library"));
+        Some((config, repo))
+    }
+
+    /// 库保存后排队写入同步目标；没有启用 git 或目录同步时不复制条目树
+    pub fn queue(&self, store: &Store, summary: String) {
+        if self.git().is_none() && self.folder().is_none() {
+            return;
+        }
+        let items = store.get_all();
+        let seq = self.current_seq();
+        if let Ok(jobs) = self.jobs.lock() {
+            let _ = jobs.send(SyncJob { items, summary, seq });
+        }
+    }
+
+    /// 记录一次本地修改，之后的同步状态会把这些条目计为待同步
+    pub fn record_change(&self, change: &StoreChange) {
+        if let Ok(mut status) = self.status.lock() {
+            status.record(change);
+        }
+    }
+
+    fn current_seq(&self) -> u64 {
+        self.status.lock().map(|s| s.seq()).unwrap_or_default()
+    }
+
+    /// 记录一次同步的结果：成功时 seq 之前的变更都已同步到该目标
+    fn report<T, E: ToString>(&self, target: &str, seq: u64, result: &Result<T, E>) {
+        if let Ok(mut status) = self.status.lock() {
+            match result {
+                Ok(_) => status.synced(target, seq),
+                Err(e) => status.failed(target, &e.to_string()),
+            }
+        }
+    }
+
+    /// 各启用的同步目标的待同步条目数、上次同步时间与错误
+    pub fn status(&self) -> SyncStatus {
+        let mut enabled = Vec::new();
+        if self.git().is_some() {
+            enabled.push("git");
+        }
+        if self.webdav().is_some() {
+            enabled.push("webdav");
+        }
+        if self.folder().is_some() {
+            enabled.push("folder");
+        }
+        self.status.lock().unwrap().status(&enabled)
+    }
+
+    fn auto_commit(&self, job: &SyncJob) -> Result<(), String> {
+        let Some((config, repo)) = self.git() else {
+            return Ok(());
+        };
+        let _guard = self.repo_lock.lock().map_err(|e| e.to_string())?;
+        git::ensure_repo(&repo, &config)?;
+        layout::export(&job.items, &repo)?;
+        git::commit_all(&repo, &job.summary)?;
+        Ok(())
+    }
+
+    /// 提交本地变更后从远端拉取合并，再用合并结果替换库
+    pub fn pull(&self, store: &Store) -> Result<(), Error> {
+        let result = self.pull_git(store);
+        if result.is_err() {
+            self.report("git", 0, &result);
+        }
+        result
+    }
+
+    fn pull_git(&self, store: &Store) -> Result<(), Error> {
+        let (config, repo) = self.git().ok_or(ErrorCode::GitSyncDisabled)?;
+        let _guard = self.repo_lock.lock().map_err(|e| e.to_string())?;
+        git::ensure_repo(&repo, &config)?;
+
+        let local = store.get_all();
+        layout::export(&local, &repo)?;
+        git::commit_all(&repo, "Local changes")?;
+        let conflicted = git::pull(&repo, &config)?;
+        if !conflicted.is_empty() {
+            self.resolve_git_conflicts(&repo, &conflicted)
+                .inspect_err(|_| git::abort_merge(&repo))?;
+            git::finish_merge(&repo, &format!("Merge {}", config.branch))?;
+        }
+
+        let mut items = layout::import(&repo)?;
+        layout::restore_secrets(&mut items, &local);
+        Ok(store.replace_all(items, format!("Pull from {}", config.branch))?)
+    }
+
+    /// 逐个文件做条目级三方合并；无法自动合并的部分保留本地版本并记录冲突
+    fn resolve_git_conflicts(&self, repo: &Path, paths: &[String]) -> Result<(), String> {
+        let mut conflicts = Vec::new();
+        for path in paths {
+            let file = repo.join(path);
+            let version =
+                |stage: u8| git::show_stage(repo, stage, path).and_then(|text| layout::parse_file(&file, &text));
+            if !layout::is_item_file(&file) {
+                // 顺序文件等：保留本地版本
+                if let Some(text) = git::show_stage(repo, 2, path) {
+                    fs::write(&file, text).map_err(|e| e.to_string())?;
+                }
+                continue;
+            }
+
+            let (base, local, remote) = (version(1), version(2), version(3));
+            let result = merge::merge_item(base.as_ref(), local.as_ref(), remote.as_ref());
+            match &result.item {
+                Some(item) => fs::write(&file, layout::render_file(&file, item)).map_err(|e| e.to_string())?,
+                None if file.exists() => fs::remove_file(&file).map_err(|e| e.to_string())?,
+                None => {}
+            }
+            if result.conflict {
+                conflicts.push(merge::Conflict {
+                    id: local
+                        .as_ref()
+                        .or(remote.as_ref())
+                        .map(|i| i.id.clone())
+                        .unwrap_or_default(),
+                    base,
+                    local,
+                    remote,
+                });
+            }
+        }
+        self.record_conflicts(conflicts, "git")
+    }
+
+    /// 提交本地变更并推送到远端
+    pub fn push(&self, store: &Store) -> Result<(), Error> {
+        let seq = self.current_seq();
+        let result = self.push_git(store);
+        self.report("git", seq, &result);
+        result
+    }
+
+    fn push_git(&self, store: &Store) -> Result<(), Error> {
+        let (config, repo) = self.git().ok_or(ErrorCode::GitSyncDisabled)?;
+        let _guard = self.repo_lock.lock().map_err(|e| e.to_string())?;
+        git::ensure_repo(&repo, &config)?;
+        layout::export(&store.get_all(), &repo)?;
+        git::commit_all(&repo, "Local changes")?;
+        Ok(git::push(&repo, &config)?)
+    }
+
+    fn webdav(&self) -> Option<WebDavConfig> {
+        self.config.lock().unwrap().webdav.clone().filter(|w| w.enabled)
+    }
+
+    fn webdav_base_path(&self) -> PathBuf {
+        self.data_dir.join("webdav_base.json")
+    }
+
+    fn load_webdav_base(&self) -> Option<WebDavBase> {
+        let content = fs::read_to_string(self.webdav_base_path()).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save_webdav_base(&self, base: &WebDavBase) -> Result<(), Error> {
+        let content = serde_json::to_string(base).map_err(|e| e.to_string())?;
+        fs::write(self.webdav_base_path(), content).map_err(|e| Error::from(e.to_string()))
+    }
+
+    /// 与 WebDAV 上的库文件同步：只有本地变化时上传，只有远端变化时下载；
+    /// 通过 ETag 判断远端是否变化，上传时带 If-Match 防止覆盖其他设备的修改
+    pub async fn webdav_sync(&self, store: &Store) -> Result<(), Error> {
+        let seq = self.current_seq();
+        let result = self.sync_webdav(store).await;
+        self.report("webdav", seq, &result);
+        result
+    }
+
+    async fn sync_webdav(&self, store: &Store) -> Result<(), Error> {
+        let config = self.webdav().ok_or(ErrorCode::WebDavSyncDisabled)?;
+        let _guard = self.webdav_lock.lock().await;
+
+        let base = self.load_webdav_base();
+        let local = layout::strip_secrets(&store.get_all());
+        let local_changed = base
+            .as_ref()
+            .map_or(!local.is_empty(), |b| !same_items(&b.items, &local));
+        let etag = base.as_ref().and_then(|b| b.etag.clone());
+
+        let upload_etag = match webdav::fetch(&self.client, &config, etag.as_deref()).await? {
+            Remote::NotModified if !local_changed => return Ok(()),
+            Remote::NotModified => etag,
+            Remote::Missing => None,
+            Remote::Changed { etag, items } => {
+                if same_items(&items, &local) {
+                    return self.save_webdav_base(&WebDavBase { etag, items });
+                }
+                if !local_changed {
+                    let mut downloaded = items.clone();
+                    layout::restore_secrets(&mut downloaded, &store.get_all());
+                    store.replace_all(downloaded, "Download from WebDAV".to_string())?;
+                    return self.save_webdav_base(&WebDavBase { etag, items });
+                }
+                // 两侧都有修改：与上次同步的内容做三方合并，再把合并结果上传
+                let base_items = base.map(|b| b.items).unwrap_or_default();
+                let merged = self.merge_into_store(store, &base_items, &local, &items, "webdav")?;
+                if same_items(&merged, &items) {
+                    return self.save_webdav_base(&WebDavBase { etag, items });
+                }
+                return self.upload_webdav(&config, merged, etag.as_deref()).await;
+            }
+        };
+        self.upload_webdav(&config, local, upload_etag.as_deref()).await
+    }
+
+    async fn upload_webdav(
+        &self,
+        config: &WebDavConfig,
+        items: Vec<TreeItem>,
+        etag: Option<&str>,
+    ) -> Result<(), Error> {
+        match webdav::upload(&self.client, config, &items, etag).await? {
+            Upload::Stored { etag } => self.save_webdav_base(&WebDavBase { etag, items }),
+            Upload::Conflict => Err(ErrorCode::WebDavChanged.into()),
+        }
+    }
+
+    fn folder(&self) -> Option<PathBuf> {
+        let config = self.config.lock().unwrap().folder.clone().filter(|f| f.enabled)?;
+        Some(PathBuf::from(config.path)).filter(|p| !p.as_os_str().is_empty())
+    }
+
+    fn save_folder_base(&self, items: &[TreeItem]) -> Result<(), String> {
+        let content = serde_json::to_string(items).map_err(|e| e.to_string())?;
+        fs::write(self.data_dir.join("folder_base.json"), content).map_err(|e| e.to_string())
+    }
+
+    /// （重新）启动目录同步：先对齐两侧，再开始监听目录；未启用时停止监听
+    pub fn start_folder_sync(&self, store: &Store) -> Result<(), String> {
+        let mut watcher = self.folder_watcher.lock().map_err(|e| e.to_string())?;
+        *watcher = None;
+        let Some(path) = self.folder() else {
+            return Ok(());
+        };
+        fs::create_dir_all(&path).map_err(|e| e.to_string())?;
+
+        let has_base = self.folder_base.lock().map_err(|e| e.to_string())?.is_some();
+        if has_base {
+            // 应用关闭期间目录中的修改
+            self.import_folder(store)?;
+        } else {
+            // 首次启用：两侧各自的条目都保留；目录中有其他文件时不合并也不导出
+            layout::check_target(&path)?;
+            let folder_items = layout::strip_secrets(&layout::import(&path)?);
+            if !folder_items.is_empty() {
+                let local = layout::strip_secrets(&store.get_all());
+                self.merge_into_store(store, &[], &local, &folder_items, "folder")?;
+            }
+        }
+        self.queue(store, "Export library".to_string());
+
+        *watcher = Some(folder::watch(&path, self.app.clone())?);
+        Ok(())
+    }
+
+    fn export_folder(&self, items: &[TreeItem]) -> Result<(), String> {
+        let Some(path) = self.folder() else {
+            return Ok(());
+        };
+        let mut base = self.folder_base.lock().map_err(|e| e.to_string())?;
+        layout::export(items, &path)?;
+        // 以读回的内容作为基准，与之后的导入结果可以直接比较
+        let written = layout::strip_secrets(&layout::import(&path)?);
+        self.save_folder_base(&written)?;
+        *base = Some(written);
+        Ok(())
+    }
+
+    /// 目录内容与上次写出 / 读入的不同时，说明有外部修改，与库中尚未写出的修改做三方合并
+    fn import_folder(&self, store: &Store) -> Result<(), String> {
+        let Some(path) = self.folder() else {
+            return Ok(());
+        };
+        let mut base = self.folder_base.lock().map_err(|e| e.to_string())?;
+        let imported = layout::strip_secrets(&layout::import(&path)?);
+        if base.as_deref().is_some_and(|b| same_items(b, &imported)) {
+            return Ok(());
+        }
+        let base_items = base.replace(imported.clone()).unwrap_or_default();
+        self.save_folder_base(&imported)?;
+        drop(base);
+
+        let local = layout::strip_secrets(&store.get_all());
+        self.merge_into_store(store, &base_items, &local, &imported, "folder")?;
+        Ok(())
+    }
+
+    /// 三方合并后替换库，记录冲突；返回合并结果（不含凭据）
+    fn merge_into_store(
+        &self,
+        store: &Store,
+        base: &[TreeItem],
+        local: &[TreeItem],
+        remote: &[TreeItem],
+        source: &str,
+    ) -> Result<Vec<TreeItem>, String> {
+        let (merged, conflicts) = merge::merge_trees(base, local, remote);
+        self.record_conflicts(conflicts, source)?;
+        if !same_items(&merged, local) {
+            let mut items = merged.clone();
+            layout::restore_secrets(&mut items, &store.get_all());
+            store.replace_all(items, format!("Merge changes from {}", source))?;
+        }
+        Ok(merged)
+    }
+
+    fn record_conflicts(&self, found: Vec<merge::Conflict>, source: &str) -> Result<(), String> {
+        if found.is_empty() {
+            return Ok(());
+        }
+        let mut conflicts = self.conflicts.lock().map_err(|e| e.to_string())?;
+        let now = chrono::Utc::now().timestamp_millis();
+        for conflict in found {
+            let name = conflict
+                .local
+                .as_ref()
+                .or(conflict.remote.as_ref())
+                .map(|i| i.name.clone())
+                .unwrap_or_default();
+            // 同一条目只保留最新的一次冲突
+            conflicts.retain(|c| c.item_id != conflict.id);
+            conflicts.push(SyncConflict {
+                item_id: conflict.id,
+                name,
+                source: source.to_string(),
+                detected_at: now,
+                base: conflict.base,
+                local: conflict.local,
+                remote: conflict.remote,
+            });
+        }
+        self.save_conflicts(&conflicts)
+    }
+
+    fn save_conflicts(&self, conflicts: &[SyncConflict]) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(conflicts).map_err(|e| e.to_string())?;
+        fs::write(self.data_dir.join("sync_conflicts.json"), content).map_err(|e| e.to_string())
+    }
+
+    pub fn conflicts(&self) -> Vec<SyncConflict> {
+        self.conflicts.lock().unwrap().clone()
+    }
+
+    /// 解决冲突：保留本地版本、采用远端版本或使用手动合并的内容
+    pub fn resolve_conflict(
+        &self,
+        store: &Store,
+        item_id: &str,
+        choice: ConflictChoice,
+        content: Option<String>,
+    ) -> Result<(), Error> {
+        let mut conflicts = self.conflicts.lock().map_err(|e| e.to_string())?;
+        let index = conflicts
+            .iter()
+            .position(|c| c.item_id == item_id)
+            .ok_or(ErrorCode::ConflictNotFound)?;
+
+        let chosen = match choice {
+            ConflictChoice::Local => conflicts[index].local.clone(),
+            ConflictChoice::Remote => conflicts[index].remote.clone(),
+            ConflictChoice::Custom => {
+                let content = content.ok_or_else(|| "Content is required for a custom resolution".to_string())?;
+                let mut item = conflicts[index]
+                    .local
+                    .clone()
+                    .or_else(|| conflicts[index].remote.clone())
+                    .ok_or_else(|| "Conflict has no version to edit".to_string())?;
+                item.content = Some(content.into());
+                Some(item)
+            }
+        };
+        match chosen {
+            Some(mut item) => {
+                // 冲突记录中不含凭据，沿用库中的
+                if let Some(current) = store.get_item(item_id) {
+                    layout::restore_secrets(std::slice::from_mut(&mut item), &[current]);
+                }
+                store.upsert_item(item)?;
+            }
+            None if store.get_item(item_id).is_some() => store.delete_item(item_id.to_string())?,
+            None => {}
+        }
+
+        conflicts.remove(index);
+        Ok(self.save_conflicts(&conflicts)?)
+    }
+}
+
+/// 是否启用了会在别处保存明文副本的同步目标
+pub fn has_targets(config: &SyncConfig) -> bool {
+    config.git.as_ref().is_some_and(|g| g.enabled)
+        || config.webdav.as_ref().is_some_and(|w| w.enabled)
+        || config.folder.as_ref().is_some_and(|f| f.enabled)
+}
+
+fn same_items(a: &[TreeItem], b: &[TreeItem]) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
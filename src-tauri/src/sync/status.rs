@@ -0,0 +1,116 @@
+use crate::log_error;
+use crate::models::{StoreChange, SyncStatus, SyncTargetStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 某个同步目标已同步到的变更序号
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+struct TargetProgress {
+    synced_seq: u64,
+    last_sync: Option<i64>,
+    last_error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct StatusData {
+    /// 最近一次变更的序号
+    seq: u64,
+    /// 条目 id -> 最近一次修改它的变更序号
+    changes: BTreeMap<String, u64>,
+    targets: BTreeMap<String, TargetProgress>,
+}
+
+/// 变更日志：每次本地修改分配一个递增序号，各同步目标记录已同步到的序号，
+/// 之后修改过的条目即为该目标待同步的条目。保存在 sync_status.json
+pub(super) struct StatusLog {
+    data: StatusData,
+    path: PathBuf,
+}
+
+impl StatusLog {
+    pub fn load(dir: &Path) -> Self {
+        let path = dir.join("sync_status.json");
+        let data = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        StatusLog { data, path }
+    }
+
+    fn save(&self) {
+        if let Ok(content) = serde_json::to_string(&self.data) {
+            if let Err(e) = fs::write(&self.path, content) {
+                log_error!("Failed to save sync status: {}", e);
+            }
+        }
+    }
+
+    pub fn seq(&self) -> u64 {
+        self.data.seq
+    }
+
+    /// 记录一次本地修改；同步拉取等整体替换不算作本地修改
+    pub fn record(&mut self, change: &StoreChange) {
+        if change.ids.is_empty() {
+            return;
+        }
+        self.data.seq += 1;
+        for id in &change.ids {
+            self.data.changes.insert(id.clone(), self.data.seq);
+        }
+        self.save();
+    }
+
+    /// 目标已包含序号不大于 seq 的全部变更
+    pub fn synced(&mut self, target: &str, seq: u64) {
+        let progress = self.data.targets.entry(target.to_string()).or_default();
+        progress.synced_seq = progress.synced_seq.max(seq);
+        progress.last_sync = Some(chrono::Utc::now().timestamp_millis());
+        progress.last_error = None;
+        self.save();
+    }
+
+    pub fn failed(&mut self, target: &str, error: &str) {
+        self.data.targets.entry(target.to_string()).or_default().last_error = Some(error.to_string());
+        self.save();
+    }
+
+    pub fn status(&self, enabled: &[&str]) -> SyncStatus {
+        let mut pending_ids = BTreeSet::new();
+        let mut targets = Vec::new();
+        for target in enabled {
+            let progress = self.data.targets.get(*target).cloned().unwrap_or_default();
+            let pending: Vec<&String> = self
+                .data
+                .changes
+                .iter()
+                .filter(|(_, &seq)| seq > progress.synced_seq)
+                .map(|(id, _)| id)
+                .collect();
+            pending_ids.extend(pending.iter().map(|id| id.to_string()));
+            targets.push(SyncTargetStatus {
+                target: target.to_string(),
+                pending: pending.len(),
+                last_sync: progress.last_sync,
+                last_error: progress.last_error,
+            });
+        }
+
+        let last_sync = targets
+            .iter()
+            .map(|t| t.last_sync)
+            .collect::<Option<Vec<i64>>>()
+            .and_then(|times| times.into_iter().min());
+        SyncStatus {
+            pending: pending_ids.len(),
+            pending_ids: pending_ids.into_iter().collect(),
+            last_sync,
+            last_error: targets.iter().find_map(|t| t.last_error.clone()),
+            targets,
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use crate::errors::{Error, ErrorCode};
+use crate::models::{TreeItem, WebDavConfig};
+use reqwest::header::{ETAG, IF_MATCH, IF_NONE_MATCH};
+use reqwest::{Client, RequestBuilder, StatusCode};
+
+/// 远端库文件的状态
+pub(super) enum Remote {
+    /// 与上次同步时的 ETag 相同
+    NotModified,
+    Missing,
+    Changed {
+        etag: Option<String>,
+        items: Vec<TreeItem>,
+    },
+}
+
+pub(super) enum Upload {
+    Stored {
+        etag: Option<String>,
+    },
+    /// 远端在此期间被其他设备修改（ETag 不匹配）
+    Conflict,
+}
+
+/// 配置的地址以 .json 结尾时直接作为文件地址，否则视为目录
+fn file_url(config: &WebDavConfig) -> String {
+    let url = config.url.trim();
+    if url.ends_with(".json") {
+        url.to_string()
+    } else {
+        format!("{}/prompt-library.json", url.trim_end_matches('/'))
+    }
+}
+
+fn authorized(builder: RequestBuilder, config: &WebDavConfig) -> RequestBuilder {
+    match config.username.as_deref().filter(|u| !u.is_empty()) {
+        Some(username) => builder.basic_auth(username, config.password.as_deref()),
+        None => builder,
+    }
+}
+
+fn etag_of(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+pub(super) async fn fetch(client: &Client, config: &WebDavConfig, etag: Option<&str>) -> Result<Remote, Error> {
+    let mut builder = authorized(client.get(file_url(config)), config);
+    if let Some(etag) = etag {
+        builder = builder.header(IF_NONE_MATCH, etag);
+    }
+    let response = builder.send().await.map_err(|e| e.to_string())?;
+
+    match response.status() {
+        StatusCode::NOT_MODIFIED => Ok(Remote::NotModified),
+        StatusCode::NOT_FOUND => Ok(Remote::Missing),
+        status if status.is_success() => {
+            let etag = etag_of(&response);
+            let items = response
+                .json::<Vec<TreeItem>>()
+                .await
+                .map_err(|e| format!("Invalid library on WebDAV server: {}", e))?;
+            Ok(Remote::Changed { etag, items })
+        }
+        status => Err(Error::with(ErrorCode::WebDavDownloadFailed, status)),
+    }
+}
+
+/// 上传库文件；有 ETag 时要求远端未被修改，没有时要求远端文件不存在
+pub(super) async fn upload(
+    client: &Client,
+    config: &WebDavConfig,
+    items: &[TreeItem],
+    etag: Option<&str>,
+) -> Result<Upload, Error> {
+    let body = serde_json::to_string_pretty(items).map_err(|e| e.to_string())?;
+    let mut builder = authorized(client.put(file_url(config)), config)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body);
+    builder = match etag {
+        Some(etag) => builder.header(IF_MATCH, etag),
+        None => builder.header(IF_NONE_MATCH, "*"),
+    };
+    let response = builder.send().await.map_err(|e| e.to_string())?;
+
+    match response.status() {
+        StatusCode::PRECONDITION_FAILED => Ok(Upload::Conflict),
+        status if status.is_success() => {
+            // 部分服务器在 PUT 响应中不返回 ETag，此时再查询一次
+            let etag = match etag_of(&response) {
+                Some(etag) => Some(etag),
+                None => {
+                    let head = authorized(client.head(file_url(config)), config)
+                        .send()
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    etag_of(&head)
+                }
+            };
+            Ok(Upload::Stored { etag })
+        }
+        status => Err(Error::with(ErrorCode::WebDavUploadFailed, status)),
+    }
+}
@@ -0,0 +1,708 @@
+use crate::errors::{Error, ErrorCode};
+use crate::models::{
+    IssueSeverity, ItemMetadata, PromptPreview, TemplateIssue, TemplateIssueKind, TemplateMode, TemplateVariable,
+    TreeItem, VariableDecl, VariablePosition, VariableType,
+};
+use std::collections::HashMap;
+
+/// 渲染一个 prompt 所需的模板信息
+#[derive(Debug, Clone, Default)]
+pub struct TemplateSpec {
+    pub mode: TemplateMode,
+    pub declared: Vec<VariableDecl>,
+}
+
+impl TemplateSpec {
+    pub fn of(metadata: &ItemMetadata) -> Self {
+        TemplateSpec {
+            mode: metadata.template_mode.unwrap_or_default(),
+            declared: metadata.variables.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// 内容中出现的一个 `{{name}}` 占位符
+#[derive(Debug, Clone)]
+pub struct Placeholder {
+    pub name: String,
+    /// 行号（1 起）
+    pub line: usize,
+    /// 列号（1 起，按字符计）
+    pub column: usize,
+    /// 占位符在内容中的字节范围（含花括号）
+    pub start: usize,
+    pub end: usize,
+}
+
+/// 合法的变量名：字母、数字、下划线、连字符与点
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// `{% raw %}...{% endraw %}` 块，块内的内容原样输出，不做任何替换
+#[derive(Debug, Clone, Copy)]
+struct RawBlock {
+    /// 整个块（含标签）的字节范围
+    start: usize,
+    end: usize,
+    /// 块内容的字节范围
+    inner_start: usize,
+    inner_end: usize,
+}
+
+/// 从 `from` 开始查找 `{% name %}` 标签，返回标签的字节范围
+fn find_tag(content: &str, from: usize, name: &str) -> Option<(usize, usize)> {
+    let mut offset = from;
+    while let Some(idx) = content[offset..].find("{%") {
+        let start = offset + idx;
+        let close = content[start + 2..].find("%}")?;
+        let end = start + 2 + close + 2;
+        if content[start + 2..end - 2].trim_matches(|c: char| c.is_whitespace() || c == '-') == name {
+            return Some((start, end));
+        }
+        offset = start + 2;
+    }
+    None
+}
+
+fn raw_blocks(content: &str) -> Vec<RawBlock> {
+    let mut blocks = Vec::new();
+    let mut offset = 0;
+    while let Some((start, inner_start)) = find_tag(content, offset, "raw") {
+        // 没有 endraw 时一直到内容末尾
+        let (inner_end, end) = find_tag(content, inner_start, "endraw").unwrap_or((content.len(), content.len()));
+        blocks.push(RawBlock {
+            start,
+            end,
+            inner_start,
+            inner_end,
+        });
+        offset = end;
+    }
+    blocks
+}
+
+fn overlaps_raw(blocks: &[RawBlock], start: usize, end: usize) -> bool {
+    blocks.iter().any(|b| b.start < end && start < b.end)
+}
+
+/// 还原字面量：raw 块去掉标签、内容原样保留，块外的 `\{\{` / `\}\}` 转为 `{{` / `}}`
+fn unescape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut last = 0;
+    for block in raw_blocks(text) {
+        output.push_str(&unescape_braces(&text[last..block.start]));
+        output.push_str(&text[block.inner_start..block.inner_end]);
+        last = block.end;
+    }
+    output.push_str(&unescape_braces(&text[last..]));
+    output
+}
+
+fn unescape_braces(text: &str) -> String {
+    text.replace("\\{\\{", "{{").replace("\\}\\}", "}}")
+}
+
+/// 解析内容中的全部 `{{name}}` 占位符（允许花括号内两侧有空格）
+pub fn parse_placeholders(content: &str) -> Vec<Placeholder> {
+    let mut placeholders = Vec::new();
+    let raw = raw_blocks(content);
+    let mut offset = 0;
+
+    while let Some(idx) = content[offset..].find("{{") {
+        let start = offset + idx;
+        let Some(close) = content[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + close + 2;
+        let name = content[start + 2..end - 2].trim();
+
+        if is_valid_name(name) && !overlaps_raw(&raw, start, end) {
+            let (line, column) = line_column(content, start);
+            placeholders.push(Placeholder {
+                name: name.to_string(),
+                line,
+                column,
+                start,
+                end,
+            });
+            offset = end;
+        } else {
+            // 不是合法占位符，跳过这对花括号继续查找
+            offset = start + 2;
+        }
+    }
+
+    placeholders
+}
+
+/// 内容中出现的一个 `{{> prompt:引用}}` 引用，引用可以是条目 id 或 `文件夹/名称` 路径
+#[derive(Debug, Clone)]
+pub struct Include {
+    pub reference: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+pub fn parse_includes(content: &str) -> Vec<Include> {
+    let mut includes = Vec::new();
+    let raw = raw_blocks(content);
+    let mut offset = 0;
+
+    while let Some(idx) = content[offset..].find("{{") {
+        let start = offset + idx;
+        let Some(close) = content[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + close + 2;
+        let inner = content[start + 2..end - 2].trim();
+        let reference = inner
+            .strip_prefix('>')
+            .map(str::trim_start)
+            .and_then(|rest| rest.strip_prefix("prompt:"))
+            .map(str::trim)
+            .filter(|r| !r.is_empty() && !overlaps_raw(&raw, start, end));
+
+        match reference {
+            Some(reference) => {
+                includes.push(Include {
+                    reference: reference.to_string(),
+                    start,
+                    end,
+                });
+                offset = end;
+            }
+            None => offset = start + 2,
+        }
+    }
+
+    includes
+}
+
+/// 递归展开 item 内容中的引用；`lookup` 按 id 或路径查找被引用的条目，出现循环引用时返回错误
+pub fn expand_includes(item: &TreeItem, lookup: &dyn Fn(&str) -> Option<TreeItem>) -> Result<String, Error> {
+    let mut stack = vec![(item.id.clone(), item.name.clone())];
+    expand_recursive(item.content.as_deref().unwrap_or_default(), lookup, &mut stack)
+}
+
+fn expand_recursive(
+    content: &str,
+    lookup: &dyn Fn(&str) -> Option<TreeItem>,
+    stack: &mut Vec<(String, String)>,
+) -> Result<String, Error> {
+    let includes = parse_includes(content);
+    if includes.is_empty() {
+        return Ok(content.to_string());
+    }
+
+    let mut output = String::with_capacity(content.len());
+    let mut last = 0;
+    for include in &includes {
+        let target = lookup(&include.reference)
+            .ok_or_else(|| Error::with(ErrorCode::IncludedItemNotFound, &include.reference))?;
+        if stack.iter().any(|(id, _)| *id == target.id) {
+            let chain: Vec<&str> = stack
+                .iter()
+                .map(|(_, name)| name.as_str())
+                .chain(std::iter::once(target.name.as_str()))
+                .collect();
+            return Err(Error::with(ErrorCode::IncludeCycle, chain.join(" -> ")));
+        }
+
+        stack.push((target.id.clone(), target.name.clone()));
+        let expanded = expand_recursive(target.content.as_deref().unwrap_or_default(), lookup, stack)?;
+        stack.pop();
+
+        output.push_str(&content[last..include.start]);
+        output.push_str(&expanded);
+        last = include.end;
+    }
+    output.push_str(&content[last..]);
+    Ok(output)
+}
+
+/// 字节偏移对应的行号与列号（均从 1 开始）
+pub fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+/// 按首次出现顺序汇总去重后的变量及其所有出现位置
+pub fn list_variables(content: &str) -> Vec<TemplateVariable> {
+    let mut variables: Vec<TemplateVariable> = Vec::new();
+    for placeholder in parse_placeholders(content) {
+        let position = VariablePosition {
+            line: placeholder.line,
+            column: placeholder.column,
+        };
+        match variables.iter_mut().find(|v| v.name == placeholder.name) {
+            Some(variable) => variable.positions.push(position),
+            None => variables.push(TemplateVariable {
+                name: placeholder.name,
+                positions: vec![position],
+            }),
+        }
+    }
+    variables
+}
+
+/// 确定每个变量的取值：调用方提供的值优先，其次是声明的默认值；
+/// 未声明的变量必须提供值，声明为必填的变量不能为空
+fn resolve_values<'a>(
+    placeholders: &[Placeholder],
+    declared: &'a [VariableDecl],
+    values: &'a HashMap<String, String>,
+) -> Result<HashMap<String, &'a str>, Error> {
+    let mut resolved = HashMap::new();
+    let mut missing: Vec<&str> = Vec::new();
+
+    for placeholder in placeholders {
+        let name = placeholder.name.as_str();
+        if resolved.contains_key(name) || missing.contains(&name) {
+            continue;
+        }
+        let decl = declared.iter().find(|d| d.name == name);
+        let value = values
+            .get(name)
+            .map(|v| v.as_str())
+            .or_else(|| decl.and_then(|d| d.default_value.as_deref()));
+
+        match (value, decl) {
+            (Some(value), Some(decl)) if decl.required && value.trim().is_empty() => missing.push(name),
+            (Some(value), _) => {
+                resolved.insert(name.to_string(), value);
+            }
+            // 已声明的可选变量没有值时渲染为空
+            (None, Some(decl)) if !decl.required => {
+                resolved.insert(name.to_string(), "");
+            }
+            (None, _) => missing.push(name),
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(Error::with(ErrorCode::MissingValues, missing.join(", ")));
+    }
+    Ok(resolved)
+}
+
+/// 渲染 prompt，按模板模式选择简单替换或 Tera
+pub fn render(content: &str, spec: &TemplateSpec, values: &HashMap<String, String>) -> Result<String, Error> {
+    check_values(&spec.declared, values)?;
+    match spec.mode {
+        TemplateMode::Simple => render_simple(content, &spec.declared, values),
+        TemplateMode::Tera => render_tera(content, &spec.declared, values),
+    }
+}
+
+/// 用每个变量的示例值或默认值渲染预览，不要求填写表单；
+/// 缺少值的变量原样保留为 `{{name}}`，并在结果中列出
+pub fn preview(content: &str, spec: &TemplateSpec) -> Result<PromptPreview, Error> {
+    let mut values: HashMap<String, String> = spec
+        .declared
+        .iter()
+        .filter_map(|d| {
+            let value = d.example_value.as_ref().or(d.default_value.as_ref())?;
+            Some((d.name.clone(), value.clone()))
+        })
+        .collect();
+
+    let mut missing = Vec::new();
+    for placeholder in parse_placeholders(content) {
+        if !values.contains_key(&placeholder.name) {
+            values.insert(placeholder.name.clone(), format!("{{{{{}}}}}", placeholder.name));
+            missing.push(placeholder.name);
+        }
+    }
+
+    let content = match spec.mode {
+        TemplateMode::Simple => render_simple(content, &[], &values)?,
+        TemplateMode::Tera => render_tera(content, &spec.declared, &values)?,
+    };
+    Ok(PromptPreview { content, missing })
+}
+
+/// 简单替换：将占位符替换为对应的值；有变量缺少值时返回错误并列出全部缺失的变量
+fn render_simple(content: &str, declared: &[VariableDecl], values: &HashMap<String, String>) -> Result<String, Error> {
+    let placeholders = parse_placeholders(content);
+    let resolved = resolve_values(&placeholders, declared, values)?;
+
+    // 占位符之间的文本还原转义与 raw 块，替换进来的值保持原样
+    let mut output = String::with_capacity(content.len());
+    let mut last = 0;
+    for placeholder in &placeholders {
+        output.push_str(&unescape(&content[last..placeholder.start]));
+        output.push_str(resolved[placeholder.name.as_str()]);
+        last = placeholder.end;
+    }
+    output.push_str(&unescape(&content[last..]));
+    Ok(output)
+}
+
+/// Tera 模式：声明的默认值与传入值组成上下文；形如 JSON 数组 / 对象的值按 JSON 解析，
+/// 以便在模板中循环 few-shot 示例
+fn render_tera(content: &str, declared: &[VariableDecl], values: &HashMap<String, String>) -> Result<String, Error> {
+    let mut context = tera::Context::new();
+    for decl in declared {
+        if let Some(default) = &decl.default_value {
+            context.insert(decl.name.as_str(), &typed_value(Some(decl), default));
+        }
+    }
+    for (name, value) in values {
+        let decl = declared.iter().find(|d| d.name == *name);
+        context.insert(name.as_str(), &typed_value(decl, value));
+    }
+
+    let missing: Vec<&str> = declared
+        .iter()
+        .filter(|d| d.required)
+        .filter(|d| {
+            values
+                .get(&d.name)
+                .or(d.default_value.as_ref())
+                .map_or(true, |v| v.trim().is_empty())
+        })
+        .map(|d| d.name.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(Error::with(ErrorCode::MissingValues, missing.join(", ")));
+    }
+
+    tera::Tera::one_off(content, &context, false).map_err(|e| tera_error(&e))
+}
+
+/// 校验变量声明本身：enum 需要可选值，默认值需符合类型
+pub fn check_declaration(decl: &VariableDecl) -> Result<(), Error> {
+    if decl.var_type == VariableType::Enum && decl.options.as_ref().map_or(true, |o| o.is_empty()) {
+        return Err(Error::with(ErrorCode::EnumWithoutOptions, &decl.name));
+    }
+    if let Some(default) = decl.default_value.as_deref().filter(|v| !v.is_empty()) {
+        if check_value(decl, default).is_err() {
+            let values = vec![decl.name.clone(), default.to_string()];
+            return Err(Error::new(ErrorCode::InvalidDefaultValue, values));
+        }
+    }
+    Ok(())
+}
+
+/// 按声明的类型校验传入值与默认值；多个变量不合法时列出全部变量名
+fn check_values(declared: &[VariableDecl], values: &HashMap<String, String>) -> Result<(), Error> {
+    let mut errors: Vec<(&str, Error)> = declared
+        .iter()
+        .filter_map(|decl| {
+            let value = values.get(&decl.name).or(decl.default_value.as_ref())?;
+            if value.is_empty() {
+                return None;
+            }
+            check_value(decl, value).err().map(|e| (decl.name.as_str(), e))
+        })
+        .collect();
+    match errors.len() {
+        0 => Ok(()),
+        1 => Err(errors.remove(0).1),
+        _ => {
+            let names: Vec<&str> = errors.iter().map(|(name, _)| *name).collect();
+            Err(Error::with(ErrorCode::InvalidValues, names.join(", ")))
+        }
+    }
+}
+
+fn check_value(decl: &VariableDecl, value: &str) -> Result<(), Error> {
+    let valid = match decl.var_type {
+        VariableType::String | VariableType::Multiline => true,
+        VariableType::Number => value.trim().parse::<f64>().is_ok_and(f64::is_finite),
+        VariableType::Boolean => parse_bool(value).is_some(),
+        VariableType::Enum => decl.options.as_ref().is_some_and(|o| o.iter().any(|opt| opt == value)),
+    };
+    if valid {
+        return Ok(());
+    }
+    let (code, mut values) = match decl.var_type {
+        VariableType::Number => (ErrorCode::NotANumber, vec![decl.name.clone()]),
+        VariableType::Boolean => (ErrorCode::NotABoolean, vec![decl.name.clone()]),
+        _ => (
+            ErrorCode::NotAnOption,
+            vec![decl.name.clone(), decl.options.clone().unwrap_or_default().join(", ")],
+        ),
+    };
+    values.push(value.to_string());
+    Err(Error::new(code, values))
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "1" => Some(true),
+        "false" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Tera 上下文中的值：number / boolean 变量转为对应类型，便于在条件中直接使用
+fn typed_value(decl: Option<&VariableDecl>, value: &str) -> serde_json::Value {
+    match decl.map(|d| d.var_type) {
+        Some(VariableType::Number) => serde_json::from_str::<serde_json::Number>(value.trim())
+            .map(serde_json::Value::Number)
+            .unwrap_or_else(|_| serde_json::Value::String(value.to_string())),
+        Some(VariableType::Boolean) => parse_bool(value)
+            .map(serde_json::Value::Bool)
+            .unwrap_or_else(|| serde_json::Value::String(value.to_string())),
+        _ => tera_value(value),
+    }
+}
+
+fn tera_value(value: &str) -> serde_json::Value {
+    let trimmed = value.trim_start();
+    if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        if let Ok(json) = serde_json::from_str(value) {
+            return json;
+        }
+    }
+    serde_json::Value::String(value.to_string())
+}
+
+/// 展开 Tera 错误链；语法错误中带有 `--> 行:列`，提取为行号与列号
+fn tera_error(error: &tera::Error) -> Error {
+    let mut messages = vec![error.to_string()];
+    let mut source = std::error::Error::source(error);
+    while let Some(e) = source {
+        messages.push(e.to_string());
+        source = e.source();
+    }
+    let detail = messages.join(": ");
+
+    let location = detail.split("--> ").nth(1).and_then(|rest| {
+        let mut parts = rest.split(|c: char| !c.is_ascii_digit()).filter(|p| !p.is_empty());
+        Some((parts.next()?.to_string(), parts.next()?.to_string()))
+    });
+    match location {
+        Some((line, column)) => Error::new(ErrorCode::TemplateErrorAt, vec![line, column, detail]),
+        None => Error::with(ErrorCode::TemplateError, detail),
+    }
+}
+
+impl TemplateIssue {
+    fn new(kind: TemplateIssueKind, severity: IssueSeverity, message: String, at: Option<(usize, usize)>) -> Self {
+        TemplateIssue {
+            kind,
+            severity,
+            message,
+            line: at.map(|(line, _)| line),
+            column: at.map(|(_, column)| column),
+        }
+    }
+}
+
+/// 检查 prompt 模板：未声明 / 未使用的变量、不成对的花括号、无效占位符、失效的引用以及 Tera 语法错误
+pub fn validate(item: &TreeItem, lookup: &dyn Fn(&str) -> Option<TreeItem>) -> Vec<TemplateIssue> {
+    let content = item.content.as_deref().unwrap_or_default();
+    let spec = TemplateSpec::of(&item.metadata);
+    let mut issues = Vec::new();
+
+    for include in parse_includes(content) {
+        let at = Some(line_column(content, include.start));
+        if lookup(&include.reference).is_none() {
+            issues.push(TemplateIssue::new(
+                TemplateIssueKind::BrokenInclude,
+                IssueSeverity::Error,
+                format!("Included item not found: {}", include.reference),
+                at,
+            ));
+        }
+    }
+    // 被引用条目中的问题（更深层的缺失或循环引用）在展开时才能发现
+    let expanded = match expand_includes(item, lookup) {
+        Ok(expanded) => expanded,
+        Err(e) => {
+            if !issues.iter().any(|i| i.kind == TemplateIssueKind::BrokenInclude) {
+                issues.push(TemplateIssue::new(
+                    TemplateIssueKind::BrokenInclude,
+                    IssueSeverity::Error,
+                    e.to_string(),
+                    None,
+                ));
+            }
+            content.to_string()
+        }
+    };
+
+    match spec.mode {
+        TemplateMode::Simple => {
+            check_braces(content, &mut issues);
+            let placeholders = parse_placeholders(content);
+            let mut reported: Vec<&str> = Vec::new();
+            for placeholder in &placeholders {
+                let name = placeholder.name.as_str();
+                if spec.declared.iter().any(|d| d.name == name) || reported.contains(&name) {
+                    continue;
+                }
+                reported.push(name);
+                issues.push(TemplateIssue::new(
+                    TemplateIssueKind::UndefinedVariable,
+                    IssueSeverity::Warning,
+                    format!("Variable '{}' is not declared", name),
+                    Some((placeholder.line, placeholder.column)),
+                ));
+            }
+            let used = parse_placeholders(&expanded);
+            for decl in &spec.declared {
+                if !used.iter().any(|p| p.name == decl.name) {
+                    issues.push(TemplateIssue::new(
+                        TemplateIssueKind::UnusedVariable,
+                        IssueSeverity::Warning,
+                        format!("Variable '{}' is declared but never used", decl.name),
+                        None,
+                    ));
+                }
+            }
+        }
+        TemplateMode::Tera => {
+            if let Err(e) = tera::Tera::default().add_raw_template("prompt", &expanded) {
+                let message = tera_error(&e).to_string();
+                issues.push(TemplateIssue::new(
+                    TemplateIssueKind::SyntaxError,
+                    IssueSeverity::Error,
+                    message,
+                    None,
+                ));
+            }
+            for decl in &spec.declared {
+                if !expanded.contains(decl.name.as_str()) {
+                    issues.push(TemplateIssue::new(
+                        TemplateIssueKind::UnusedVariable,
+                        IssueSeverity::Warning,
+                        format!("Variable '{}' is declared but never used", decl.name),
+                        None,
+                    ));
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+/// 检查 `{{` / `}}` 是否成对，以及花括号内是否为合法的变量名或引用
+fn check_braces(content: &str, issues: &mut Vec<TemplateIssue>) {
+    let raw = raw_blocks(content);
+    let mut offset = 0;
+    loop {
+        let open = content[offset..].find("{{").map(|i| offset + i);
+        let close = content[offset..].find("}}").map(|i| offset + i);
+
+        // raw 块中的花括号不参与检查
+        let next = open.into_iter().chain(close).min();
+        if let Some(block) = next.and_then(|next| raw.iter().find(|b| b.start <= next && next < b.end)) {
+            offset = block.end;
+            continue;
+        }
+
+        // 在下一个 `{{` 之前出现的 `}}`
+        if let Some(close) = close.filter(|&close| open.map_or(true, |open| close < open)) {
+            issues.push(TemplateIssue::new(
+                TemplateIssueKind::UnbalancedBraces,
+                IssueSeverity::Error,
+                "Closing '}}' without matching '{{'".to_string(),
+                Some(line_column(content, close)),
+            ));
+            offset = close + 2;
+            continue;
+        }
+        let Some(open) = open else {
+            break;
+        };
+
+        let next_open = content[open + 2..].find("{{").map(|i| open + 2 + i);
+        match close {
+            Some(close) if next_open.map_or(true, |next| close < next) => {
+                let inner = content[open + 2..close].trim();
+                if !inner.starts_with('>') && !is_valid_name(inner) {
+                    issues.push(TemplateIssue::new(
+                        TemplateIssueKind::InvalidPlaceholder,
+                        IssueSeverity::Warning,
+                        format!(
+                            "'{{{{{}}}}}' is not a valid variable name and will be left as is",
+                            inner
+                        ),
+                        Some(line_column(content, open)),
+                    ));
+                }
+                offset = close + 2;
+            }
+            _ => {
+                issues.push(TemplateIssue::new(
+                    TemplateIssueKind::UnbalancedBraces,
+                    IssueSeverity::Error,
+                    "'{{' is never closed".to_string(),
+                    Some(line_column(content, open)),
+                ));
+                offset = open + 2;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(content: &str) -> Vec<String> {
+        parse_placeholders(content).into_iter().map(|p| p.name).collect()
+    }
+
+    fn issue_kinds(content: &str) -> Vec<TemplateIssueKind> {
+        let mut issues = Vec::new();
+        check_braces(content, &mut issues);
+        issues.into_iter().map(|i| i.kind).collect()
+    }
+
+    #[test]
+    fn parse_placeholders_finds_valid_names() {
+        let content = "Hello {{name}}, {{ topic }} and {{not valid}}";
+        assert_eq!(names(content), ["name", "topic"]);
+        let first = &parse_placeholders(content)[0];
+        assert_eq!(&content[first.start..first.end], "{{name}}");
+        assert_eq!((first.line, first.column), (1, 7));
+    }
+
+    #[test]
+    fn parse_placeholders_skips_raw_blocks() {
+        assert_eq!(names("{% raw %}{{literal}}{% endraw %} {{name}}"), ["name"]);
+        assert_eq!(names("{%- raw -%}{{ a }}{%- endraw -%}{{b}}"), ["b"]);
+        // 没有 endraw 时一直到末尾
+        assert!(names("{% raw %}{{x}}").is_empty());
+    }
+
+    #[test]
+    fn raw_blocks_reports_tag_and_inner_ranges() {
+        let content = "a{% raw %}{{b}}{% endraw %}c";
+        let blocks = raw_blocks(content);
+        assert_eq!(blocks.len(), 1);
+        let block = blocks[0];
+        assert_eq!(&content[block.start..block.end], "{% raw %}{{b}}{% endraw %}");
+        assert_eq!(&content[block.inner_start..block.inner_end], "{{b}}");
+    }
+
+    #[test]
+    fn unescape_keeps_raw_content_and_escaped_braces() {
+        assert_eq!(unescape("{% raw %}{{x}}{% endraw %} \\{\\{y\\}\\}"), "{{x}} {{y}}");
+    }
+
+    #[test]
+    fn check_braces_reports_unbalanced_and_invalid() {
+        assert!(issue_kinds("{{a}} and {{> prompt:b}}").is_empty());
+        assert_eq!(issue_kinds("oops }}"), [TemplateIssueKind::UnbalancedBraces]);
+        assert_eq!(issue_kinds("{{a"), [TemplateIssueKind::UnbalancedBraces]);
+        assert_eq!(issue_kinds("{{not valid}}"), [TemplateIssueKind::InvalidPlaceholder]);
+    }
+
+    #[test]
+    fn check_braces_ignores_raw_blocks() {
+        assert!(issue_kinds("{% raw %}}} {{ {{not valid}}{% endraw %}").is_empty());
+    }
+}
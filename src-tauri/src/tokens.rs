@@ -0,0 +1,51 @@
+use crate::models::TokenCount;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use tiktoken_rs::CoreBPE;
+
+/// 按模型名缓存的 BPE 编码器（构建编码器开销较大）
+fn encoder_cache() -> &'static Mutex<HashMap<String, Arc<CoreBPE>>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Arc<CoreBPE>>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 获取模型对应的编码器；非 OpenAI 模型回退到 cl100k_base 作为近似
+fn encoder_for(model: &str) -> Result<Arc<CoreBPE>, String> {
+    let mut cache = encoder_cache().lock().map_err(|e| e.to_string())?;
+    if let Some(bpe) = cache.get(model) {
+        return Ok(bpe.clone());
+    }
+    let bpe = tiktoken_rs::get_bpe_from_model(model)
+        .or_else(|_| tiktoken_rs::cl100k_base())
+        .map_err(|e| e.to_string())?;
+    let bpe = Arc::new(bpe);
+    cache.insert(model.to_string(), bpe.clone());
+    Ok(bpe)
+}
+
+/// 模型上下文窗口大小（token 数）
+pub fn context_window(model: &str) -> usize {
+    let lower = model.to_lowercase();
+    if lower.starts_with("claude") {
+        200_000
+    } else if lower.starts_with("gemini-1.5") || lower.starts_with("gemini-2") {
+        1_048_576
+    } else if lower.starts_with("gemini") {
+        32_768
+    } else if lower.starts_with("deepseek") {
+        64_000
+    } else {
+        tiktoken_rs::model::get_context_size(&lower)
+    }
+}
+
+pub fn count(text: &str, model: &str) -> Result<TokenCount, String> {
+    let bpe = encoder_for(model)?;
+    let tokens = bpe.encode_with_special_tokens(text).len();
+    let context_window = context_window(model);
+    Ok(TokenCount {
+        tokens,
+        context_window,
+        exceeds_context: tokens > context_window,
+    })
+}
@@ -0,0 +1,141 @@
+use crate::applock::AppLock;
+use crate::clipboard;
+use crate::log_error;
+use crate::models::{TrayEntry, TrayMenuData, TreeItem};
+use crate::picker;
+use crate::store::Store;
+use crate::usage::UsageState;
+use std::collections::HashMap;
+use tauri::menu::{Menu, MenuBuilder, MenuItemBuilder, Submenu, SubmenuBuilder};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Manager, Wry};
+
+const TRAY_ID: &str = "main";
+const RECENT_LIMIT: usize = 10;
+const LIST_LIMIT: usize = 20;
+/// 菜单项 id 的前缀，其后为条目 id
+const COPY_PREFIX: &str = "copy:";
+/// 选中的 prompt 有必填变量没有默认值时发出，前端打开主窗口让用户填写
+pub const EVENT_NEEDS_VALUES: &str = "tray://needs-values";
+
+/// 托盘菜单的内容：置顶、收藏与最近使用的 prompt
+pub fn menu_data(store: &Store, usage: &UsageState) -> TrayMenuData {
+    let entry = |item: TreeItem| TrayEntry {
+        item_id: item.id,
+        name: item.name,
+    };
+    let pinned = store.flagged(|m| m.pinned == Some(true));
+    let favorites = store.flagged(|m| m.favorite == Some(true));
+    let recent = usage
+        .recent(RECENT_LIMIT)
+        .iter()
+        .filter_map(|id| store.get_item(id))
+        .map(entry)
+        .collect();
+    TrayMenuData {
+        pinned: pinned.into_iter().take(LIST_LIMIT).map(entry).collect(),
+        favorites: favorites.into_iter().take(LIST_LIMIT).map(entry).collect(),
+        recent,
+    }
+}
+
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Prompt Manager")
+        .menu(&build_menu(app)?)
+        .on_menu_event(|app, event| on_menu_event(app, event.id().as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+    Ok(())
+}
+
+/// 库或使用记录变化后重建菜单
+pub fn refresh(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    match build_menu(app) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => log_error!("Failed to rebuild tray menu: {}", e),
+    }
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let data = menu_data(&app.state::<Store>(), &app.state::<UsageState>());
+
+    let mut menu = MenuBuilder::new(app);
+    for entry in &data.pinned {
+        menu = menu.text(format!("{}{}", COPY_PREFIX, entry.item_id), &entry.name);
+    }
+    if !data.pinned.is_empty() {
+        menu = menu.separator();
+    }
+    menu.item(&submenu(app, "Favorites", &data.favorites)?)
+        .item(&submenu(app, "Recent", &data.recent)?)
+        .separator()
+        .text("picker", "Quick Picker")
+        .text("show", "Show Prompt Manager")
+        .separator()
+        .text("quit", "Quit")
+        .build()
+}
+
+fn submenu(app: &AppHandle, title: &str, entries: &[TrayEntry]) -> tauri::Result<Submenu<Wry>> {
+    let mut submenu = SubmenuBuilder::new(app, title);
+    if entries.is_empty() {
+        submenu = submenu.item(&MenuItemBuilder::new("(empty)").enabled(false).build(app)?);
+    }
+    for entry in entries {
+        submenu = submenu.text(format!("{}{}", COPY_PREFIX, entry.item_id), &entry.name);
+    }
+    submenu.build()
+}
+
+fn on_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        "picker" => {
+            if let Err(e) = picker::toggle(app) {
+                log_error!("Failed to open quick picker: {}", e);
+            }
+        }
+        "show" => show_main(app),
+        "quit" => app.exit(0),
+        _ => {
+            if let Some(item_id) = id.strip_prefix(COPY_PREFIX) {
+                if let Err(e) = select(app, item_id) {
+                    log_error!("Failed to copy prompt: {}", e);
+                }
+            }
+        }
+    }
+}
+
+pub fn show_main(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// 选中托盘中的 prompt：用变量默认值渲染后复制到剪贴板；
+/// 有必填变量没有默认值时打开主窗口让用户填写
+pub fn select(app: &AppHandle, item_id: &str) -> Result<(), String> {
+    // 锁定时只打开主窗口让用户解锁
+    if app.state::<AppLock>().is_locked() {
+        show_main(app);
+        return Ok(());
+    }
+    match clipboard::render(&app.state::<Store>(), item_id, &HashMap::new()) {
+        Ok((item, text)) => clipboard::write(app, &item, &text, false),
+        Err(_) => {
+            show_main(app);
+            let _ = app.emit(EVENT_NEEDS_VALUES, item_id);
+            Ok(())
+        }
+    }
+}
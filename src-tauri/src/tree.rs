@@ -0,0 +1,173 @@
+use crate::errors::{Error, ErrorCode};
+use crate::models::TreeItem;
+use std::collections::HashMap;
+
+/// 索引中的一个节点；`item.children` 始终为空，子节点以 id 列表保存
+struct Node {
+    item: TreeItem,
+    parent: Option<String>,
+    children: Vec<String>,
+}
+
+/// 以 id 为键的条目树：按 id 查找为 O(1)，查找祖先为 O(深度)。
+/// 与 `Vec<TreeItem>` 相互转换时保持条目内容与兄弟节点的顺序不变，store.json 的格式不受影响
+#[derive(Default)]
+pub struct Tree {
+    nodes: HashMap<String, Node>,
+    roots: Vec<String>,
+}
+
+impl Tree {
+    pub fn new(items: Vec<TreeItem>) -> Self {
+        let mut tree = Tree::default();
+        for item in items {
+            tree.attach(None, item);
+        }
+        tree
+    }
+
+    /// 还原为嵌套的条目树
+    pub fn to_items(&self) -> Vec<TreeItem> {
+        self.roots.iter().filter_map(|id| self.subtree(id)).collect()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.nodes.contains_key(id)
+    }
+
+    /// 条目本身（不含子节点）
+    pub fn node(&self, id: &str) -> Option<&TreeItem> {
+        self.nodes.get(id).map(|n| &n.item)
+    }
+
+    /// 修改条目本身；不要通过它修改 `children`
+    pub fn node_mut(&mut self, id: &str) -> Option<&mut TreeItem> {
+        self.nodes.get_mut(id).map(|n| &mut n.item)
+    }
+
+    /// 条目及其完整子树
+    pub fn subtree(&self, id: &str) -> Option<TreeItem> {
+        let node = self.nodes.get(id)?;
+        let mut item = node.item.clone();
+        item.children = node.children.iter().filter_map(|c| self.subtree(c)).collect();
+        Some(item)
+    }
+
+    /// 全部条目（不含子节点，顺序不定）
+    pub fn items(&self) -> impl Iterator<Item = &TreeItem> {
+        self.nodes.values().map(|n| &n.item)
+    }
+
+    pub fn roots(&self) -> &[String] {
+        &self.roots
+    }
+
+    pub fn children(&self, id: &str) -> &[String] {
+        self.nodes.get(id).map_or(&[], |n| &n.children)
+    }
+
+    pub fn parent(&self, id: &str) -> Option<&str> {
+        self.nodes.get(id)?.parent.as_deref()
+    }
+
+    /// ancestor_id 是否是 id 的祖先
+    pub fn is_ancestor(&self, ancestor_id: &str, id: &str) -> bool {
+        let mut current = self.parent(id);
+        while let Some(parent) = current {
+            if parent == ancestor_id {
+                return true;
+            }
+            current = self.parent(parent);
+        }
+        false
+    }
+
+    /// 子树中全部条目的 id（先父后子）
+    pub fn subtree_ids(&self, id: &str) -> Vec<String> {
+        let mut ids = Vec::new();
+        let mut stack = vec![id.to_string()];
+        while let Some(current) = stack.pop() {
+            if let Some(node) = self.nodes.get(&current) {
+                stack.extend(node.children.iter().rev().cloned());
+                ids.push(current);
+            }
+        }
+        ids
+    }
+
+    /// 把条目（及其子树）追加到 parent_id 的子节点末尾，parent_id 为 None 时追加到根
+    pub fn insert(&mut self, parent_id: Option<&str>, item: TreeItem) -> Result<(), Error> {
+        if let Some(parent_id) = parent_id {
+            if !self.contains(parent_id) {
+                return Err(ErrorCode::ParentNotFound.into());
+            }
+        }
+        self.attach(parent_id, item);
+        Ok(())
+    }
+
+    fn attach(&mut self, parent_id: Option<&str>, mut item: TreeItem) {
+        // id 重复的条目（旧数据或手工编辑）换一个新 id，避免覆盖
+        if self.contains(&item.id) {
+            item.id = uuid::Uuid::new_v4().to_string();
+        }
+        let id = item.id.clone();
+        let children = std::mem::take(&mut item.children);
+        match parent_id.and_then(|p| self.nodes.get_mut(p)) {
+            Some(parent) => parent.children.push(id.clone()),
+            None => self.roots.push(id.clone()),
+        }
+        self.nodes.insert(
+            id.clone(),
+            Node {
+                item,
+                parent: parent_id.map(str::to_string),
+                children: Vec::new(),
+            },
+        );
+        for child in children {
+            self.attach(Some(&id), child);
+        }
+    }
+
+    /// 取出条目及其子树
+    pub fn remove(&mut self, id: &str) -> Option<TreeItem> {
+        let item = self.subtree(id)?;
+        let siblings = match self.parent(id).map(str::to_string) {
+            Some(parent) => &mut self.nodes.get_mut(&parent)?.children,
+            None => &mut self.roots,
+        };
+        siblings.retain(|s| s != id);
+        for removed in self.subtree_ids(id) {
+            self.nodes.remove(&removed);
+        }
+        Some(item)
+    }
+
+    /// 按名称路径查找，返回条目 id
+    pub fn find_by_path(&self, segments: &[&str]) -> Option<&str> {
+        let mut level = &self.roots;
+        let mut found = None;
+        for segment in segments {
+            let id = level.iter().find(|id| self.nodes[*id].item.name == *segment)?;
+            level = &self.nodes[id].children;
+            found = Some(id.as_str());
+        }
+        found
+    }
+
+    /// 先序遍历，回调参数为条目（不含子节点）与所在文件夹的路径（`文件夹/子文件夹`，根下为空）
+    pub fn walk(&self, mut visit: impl FnMut(&TreeItem, &str)) {
+        let mut stack: Vec<(&String, String)> = self.roots.iter().rev().map(|id| (id, String::new())).collect();
+        while let Some((id, path)) = stack.pop() {
+            let node = &self.nodes[id];
+            visit(&node.item, &path);
+            let child_path = if path.is_empty() {
+                node.item.name.clone()
+            } else {
+                format!("{}/{}", path, node.item.name)
+            };
+            stack.extend(node.children.iter().rev().map(|c| (c, child_path.clone())));
+        }
+    }
+}
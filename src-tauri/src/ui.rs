@@ -0,0 +1,98 @@
+use crate::models::{UiPrefs, WindowGeometry};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{PhysicalPosition, PhysicalSize, WebviewWindow};
+
+/// 保存的窗口小于此尺寸时不恢复（多半是最小化时记下的）
+const MIN_WINDOW_SIZE: u32 = 200;
+
+/// 界面状态（窗口位置、展开的文件夹、选中的条目），保存在 ui_state.json。
+/// 窗口移动与缩放只更新内存，退出时写入
+pub struct UiState {
+    prefs: Mutex<UiPrefs>,
+    path: PathBuf,
+}
+
+impl UiState {
+    pub fn new(dir: PathBuf) -> Self {
+        let path = dir.join("ui_state.json");
+        let prefs = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        UiState {
+            prefs: Mutex::new(prefs),
+            path,
+        }
+    }
+
+    pub fn prefs(&self) -> UiPrefs {
+        self.prefs.lock().unwrap().clone()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let prefs = self.prefs.lock().map_err(|e| e.to_string())?;
+        let content = serde_json::to_string_pretty(&*prefs).map_err(|e| e.to_string())?;
+        fs::write(&self.path, content).map_err(|e| e.to_string())
+    }
+
+    pub fn set_expanded(&self, ids: Vec<String>) -> Result<(), String> {
+        self.prefs.lock().map_err(|e| e.to_string())?.expanded_ids = ids;
+        self.save()
+    }
+
+    pub fn set_selected(&self, id: Option<String>) -> Result<(), String> {
+        self.prefs.lock().map_err(|e| e.to_string())?.selected_id = id;
+        self.save()
+    }
+
+    /// 记下窗口当前的位置与大小；最小化或隐藏时不记录
+    pub fn remember_window(&self, window: &WebviewWindow) {
+        if window.is_minimized().unwrap_or(true) || !window.is_visible().unwrap_or(false) {
+            return;
+        }
+        let maximized = window.is_maximized().unwrap_or(false);
+        let mut prefs = self.prefs.lock().unwrap();
+        // 最大化时保留之前的位置与大小，取消最大化后回到原处
+        if maximized {
+            if let Some(geometry) = prefs.window.as_mut() {
+                geometry.maximized = true;
+                return;
+            }
+        }
+        let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+            return;
+        };
+        prefs.window = Some(WindowGeometry {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+            maximized,
+        });
+    }
+
+    /// 恢复窗口位置与大小；保存的位置已不在任何显示器上时（拔掉了外接显示器）只恢复大小
+    pub fn restore_window(&self, window: &WebviewWindow) {
+        let Some(geometry) = self.prefs.lock().unwrap().window else {
+            return;
+        };
+        if geometry.width >= MIN_WINDOW_SIZE && geometry.height >= MIN_WINDOW_SIZE {
+            let _ = window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+        }
+        let visible = window.available_monitors().unwrap_or_default().iter().any(|monitor| {
+            let (origin, size) = (monitor.position(), monitor.size());
+            geometry.x >= origin.x
+                && geometry.y >= origin.y
+                && geometry.x < origin.x + size.width as i32
+                && geometry.y < origin.y + size.height as i32
+        });
+        if visible {
+            let _ = window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+        }
+        if geometry.maximized {
+            let _ = window.maximize();
+        }
+    }
+}
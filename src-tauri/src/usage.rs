@@ -0,0 +1,53 @@
+use crate::models::ItemUsage;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// 条目的使用统计，保存在 usage.json（不随库同步）
+pub struct UsageState {
+    data: Mutex<HashMap<String, ItemUsage>>,
+    path: PathBuf,
+}
+
+impl UsageState {
+    pub fn new(dir: PathBuf) -> Self {
+        let path = dir.join("usage.json");
+        let data = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        UsageState {
+            data: Mutex::new(data),
+            path,
+        }
+    }
+
+    /// 记录一次使用
+    pub fn record(&self, item_id: &str) -> Result<ItemUsage, String> {
+        let mut data = self.data.lock().map_err(|e| e.to_string())?;
+        let usage = data.entry(item_id.to_string()).or_default();
+        usage.count += 1;
+        usage.last_used = Some(chrono::Utc::now().timestamp_millis());
+        let usage = usage.clone();
+
+        let content = serde_json::to_string(&*data).map_err(|e| e.to_string())?;
+        fs::write(&self.path, content).map_err(|e| e.to_string())?;
+        Ok(usage)
+    }
+
+    pub fn get(&self, item_id: &str) -> ItemUsage {
+        self.data.lock().unwrap().get(item_id).cloned().unwrap_or_default()
+    }
+
+    /// 最近使用的条目 id，最新的在前
+    pub fn recent(&self, limit: usize) -> Vec<String> {
+        let data = self.data.lock().unwrap();
+        let mut used: Vec<(&String, i64)> = data
+            .iter()
+            .filter_map(|(id, usage)| usage.last_used.map(|time| (id, time)))
+            .collect();
+        used.sort_by(|a, b| b.1.cmp(&a.1));
+        used.into_iter().take(limit).map(|(id, _)| id.clone()).collect()
+    }
+}
@@ -0,0 +1,177 @@
+use crate::errors::{Error, ErrorCode};
+use crate::log_error;
+use crate::models::{ChangeKind, StoreChange, TreeItem, Webhook};
+use crate::store::Store;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const SIGNATURE_HEADER: &str = "X-Prompt-Manager-Signature";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// POST 给 webhook 的内容
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WebhookPayload {
+    event: ChangeKind,
+    ids: Vec<String>,
+    summary: String,
+    timestamp: i64,
+    /// 变更后的条目（不含子节点与凭据）；删除时为空
+    items: Vec<TreeItem>,
+}
+
+/// webhook 配置，保存在 webhooks.json；每次库变更后在后台发送，不阻塞保存
+pub struct WebhookState {
+    hooks: Mutex<Vec<Webhook>>,
+    path: PathBuf,
+    client: reqwest::Client,
+}
+
+impl WebhookState {
+    pub fn new(dir: PathBuf) -> Self {
+        let path = dir.join("webhooks.json");
+        let hooks = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        WebhookState {
+            hooks: Mutex::new(hooks),
+            path,
+            client: reqwest::Client::builder().timeout(TIMEOUT).build().unwrap_or_default(),
+        }
+    }
+
+    fn save(&self, hooks: &[Webhook]) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(hooks).map_err(|e| e.to_string())?;
+        fs::write(&self.path, content).map_err(|e| e.to_string())
+    }
+
+    pub fn list(&self) -> Vec<Webhook> {
+        self.hooks.lock().unwrap().clone()
+    }
+
+    /// 新增或按 id 更新
+    pub fn upsert(&self, mut hook: Webhook) -> Result<Webhook, Error> {
+        let url = reqwest::Url::parse(hook.url.trim()).map_err(|e| Error::with(ErrorCode::InvalidWebhookUrl, e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(ErrorCode::WebhookSchemeUnsupported.into());
+        }
+        hook.url = url.to_string();
+        hook.secret = hook.secret.filter(|s| !s.is_empty());
+
+        let mut hooks = self.hooks.lock().map_err(|e| e.to_string())?;
+        match hooks.iter_mut().find(|h| !hook.id.is_empty() && h.id == hook.id) {
+            Some(existing) => *existing = hook.clone(),
+            None => {
+                hook.id = uuid::Uuid::new_v4().to_string();
+                hooks.push(hook.clone());
+            }
+        }
+        self.save(&hooks)?;
+        Ok(hook)
+    }
+
+    pub fn remove(&self, id: &str) -> Result<(), String> {
+        let mut hooks = self.hooks.lock().map_err(|e| e.to_string())?;
+        hooks.retain(|h| h.id != id);
+        self.save(&hooks)
+    }
+
+    /// 把变更发送给订阅了该类型的 webhook
+    pub fn dispatch(&self, store: &Store, change: &StoreChange) {
+        let targets: Vec<Webhook> = self
+            .list()
+            .into_iter()
+            .filter(|h| h.enabled && (h.events.is_empty() || h.events.contains(&change.kind)))
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+
+        let items = match change.kind {
+            ChangeKind::Deleted | ChangeKind::Replaced => Vec::new(),
+            _ => change.ids.iter().filter_map(|id| store.get_item(id)).collect(),
+        };
+        let payload = WebhookPayload {
+            event: change.kind,
+            ids: change.ids.clone(),
+            summary: change.summary.clone(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            items: strip(items),
+        };
+        let Ok(body) = serde_json::to_vec(&payload) else {
+            return;
+        };
+
+        for hook in targets {
+            let client = self.client.clone();
+            let body = body.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = deliver(&client, &hook, body).await {
+                    log_error!("Webhook {} failed: {}", hook.url, e);
+                }
+            });
+        }
+    }
+
+    /// 发送一条测试事件，返回 HTTP 状态码
+    pub async fn test(&self, id: &str) -> Result<u16, Error> {
+        let hook = self
+            .list()
+            .into_iter()
+            .find(|h| h.id == id)
+            .ok_or(ErrorCode::WebhookNotFound)?;
+        let payload = WebhookPayload {
+            event: ChangeKind::Updated,
+            ids: Vec::new(),
+            summary: "Test event from Prompt Manager".to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            items: Vec::new(),
+        };
+        let body = serde_json::to_vec(&payload).map_err(|e| e.to_string())?;
+        Ok(deliver(&self.client, &hook, body).await?)
+    }
+}
+
+fn strip(items: Vec<TreeItem>) -> Vec<TreeItem> {
+    let mut items = crate::sync::strip_secrets(&items);
+    for item in &mut items {
+        item.children.clear();
+    }
+    items
+}
+
+async fn deliver(client: &reqwest::Client, hook: &Webhook, body: Vec<u8>) -> Result<u16, String> {
+    let mut request = client
+        .post(&hook.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json");
+    if let Some(secret) = &hook.secret {
+        request = request.header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, &body)));
+    }
+    let response = request.body(body).send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(status.as_u16())
+    } else {
+        Err(format!("HTTP {}", status))
+    }
+}
+
+/// 请求体的 HMAC-SHA256（十六进制），接收方用同一个 secret 校验
+fn sign(secret: &str, body: &[u8]) -> String {
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return String::new();
+    };
+    mac.update(body);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
@@ -1,5 +1,5 @@
 
-export type ItemType = 'prompt' | 'folder' | 'settings';
+export type ItemType = 'prompt' | 'folder' | 'settings' | 'provider' | 'model' | 'snippet';
 
 export interface PromptVersion {
   id: string;
@@ -17,6 +17,58 @@ export interface ItemMetadata {
   modelName?: string;    // e.g. "gpt-4", "gemini-1.5-pro"
   baseUrl?: string;      // API base URL
   apiKey?: string;       // API Key
+  // 计价（仅 model 类型使用，美元 / 百万 token）
+  inputPrice?: number;
+  outputPrice?: number;
+  // 默认生成参数（仅 model 类型使用）
+  generation?: GenerationParams;
+  // 网络配置（provider / model 类型使用）
+  proxy?: ProxyConfig;
+  rateLimit?: RateLimit;
+  authHeader?: string;   // e.g. Azure 的 "api-key"，为空时使用 Authorization: Bearer
+  extraHeaders?: Record<string, string>;
+  queryParams?: Record<string, string>;  // e.g. { "api-version": "2024-06-01" }
+  // 声明的模板变量（仅 prompt 类型使用）
+  variables?: VariableDecl[];
+  templateMode?: TemplateMode;  // 缺省为 'simple'
+  readOnly?: boolean;  // 来自只读挂载库
+  clock?: Record<string, number>;  // 向量时钟：设备 id -> 修改次数
+  favorite?: boolean;
+  pinned?: boolean;
+}
+
+export type TemplateMode = 'simple' | 'tera';
+
+export interface VariableDecl {
+  name: string;
+  description?: string;
+  defaultValue?: string;
+  exampleValue?: string; // 预览时使用，优先于 defaultValue
+  required: boolean;
+  type?: VariableType;   // 缺省为 'string'
+  options?: string[];    // 仅 enum 类型使用
+}
+
+export type VariableType = 'string' | 'number' | 'boolean' | 'enum' | 'multiline';
+
+export interface ProxyConfig {
+  url: string;           // e.g. http://127.0.0.1:7890, socks5://127.0.0.1:1080
+  username?: string;
+  password?: string;
+  noProxy?: string;
+}
+
+export interface RateLimit {
+  maxConcurrent: number;
+  requestsPerMinute?: number;
+  maxRetries: number;
+}
+
+export interface GenerationParams {
+  temperature?: number;
+  topP?: number;
+  maxTokens?: number;
+  systemPrompt?: string;
 }
 
 export interface TreeItem {